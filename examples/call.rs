@@ -14,7 +14,13 @@ async fn main() -> Result<()> {
         starknet_rpc: format!(
             "https://starknet-mainnet.g.alchemy.com/starknet/version/rpc/v0_7/{api_key}"
         ),
+        starknet_rpc_providers: vec![],
+        quorum: Default::default(),
         gateway_url: None,
+        gateway_pubkey: None,
+        ethereum_rpc: None,
+        core_contract: None,
+        checkpoint: None,
         data_dir: "tmp".to_owned(),
     };
 
@@ -31,7 +37,7 @@ async fn main() -> Result<()> {
         calldata: vec![],
     };
 
-    let state = beerus.get_state().await?;
+    let state = beerus.get_state().await?.latest;
     let res = beerus.execute(calldata, state)?;
     println!("{:#?}", res);
 
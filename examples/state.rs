@@ -13,15 +13,21 @@ async fn main() -> Result<()> {
         starknet_rpc: format!(
             "https://starknet-mainnet.g.alchemy.com/starknet/version/rpc/v0_7/{api_key}"
         ),
+        starknet_rpc_providers: vec![],
+        quorum: Default::default(),
         gateway_url: None,
+        gateway_pubkey: None,
+        ethereum_rpc: None,
+        core_contract: None,
+        checkpoint: None,
         data_dir: "tmp".to_owned(),
     };
 
     let http = Http::new();
     let beerus = Client::new(&config, http).await?;
 
-    let state = beerus.get_state().await?;
-    tracing::info!("{state:#?}");
+    let snapshot = beerus.get_state().await?;
+    tracing::info!("{snapshot:#?}");
 
     Ok(())
 }
@@ -1,12 +1,18 @@
 use std::{sync::Arc, time::Duration};
 
 use beerus::{
-    client::Http,
+    client::{Http, Snapshot, State},
     config::{check_data_dir, ServerConfig},
+    head::{HeadTracker, KnownHeaders, Update},
+    rpc::RpcConfig,
+    store::{self, HeaderStore},
 };
 use tokio::sync::RwLock;
 use validator::Validate;
 
+// Headers fetched per tick while walking back from the trusted head
+const HEADER_SYNC_LIMIT: usize = 100;
+
 #[cfg(not(tarpaulin_include))] // exclude from code-coverage report
 #[tokio::main]
 async fn main() -> eyre::Result<()> {
@@ -14,42 +20,102 @@ async fn main() -> eyre::Result<()> {
 
     let config = get_config().await?;
 
+    let headers =
+        Arc::new(RwLock::new(HeaderStore::open(&config.client.data_dir)?));
     let http = Http::new();
-    let beerus = beerus::client::Client::new(&config.client, http).await?;
+    let beerus = beerus::client::Client::new(&config.client, http)
+        .await?
+        .with_headers(headers.clone());
 
-    let state = beerus.get_state().await?;
-    tracing::info!(?state, "initialized");
-    let state = Arc::new(RwLock::new(state));
+    let snapshot = beerus.get_state().await?;
+    tracing::info!(?snapshot, "initialized");
+    let mut tracker = HeadTracker::new(snapshot.latest.clone());
+    let reorgs = tracker.reorgs();
+    let state = Arc::new(RwLock::new(snapshot.latest));
+    let gateway = beerus.gateway().clone();
 
     {
         let state = state.clone();
+        let headers = headers.clone();
         let period = Duration::from_secs(config.poll_secs);
+        let checkpoint = config.client.checkpoint.as_ref().map(|checkpoint| {
+            State::new(
+                checkpoint.block_number,
+                checkpoint.block_hash.clone(),
+                checkpoint.root.clone(),
+            )
+        });
         tokio::spawn(async move {
             let mut tick = tokio::time::interval(period);
-            let mut current = state.read().await.clone();
             loop {
                 tick.tick().await;
-                match beerus.get_state().await {
-                    Ok(update) if update != current => {
-                        *state.write().await = update.clone();
-                        current = update;
-                        tracing::info!(state=?current, "updated");
+                let gateway = beerus.gateway();
+                let Snapshot { latest, finalized, headers: fetched } =
+                    match beerus.get_state().await {
+                        Ok(snapshot) => snapshot,
+                        Err(e) => {
+                            tracing::error!(error=%e, "state update failed");
+                            continue;
+                        }
+                    };
+                // The head is linked to both, so a reorg deeper than the
+                // tracked blocks resets the tracker on top of them
+                let anchor = finalized.as_ref().or(checkpoint.as_ref());
+                let known: &dyn KnownHeaders = &fetched;
+                match tracker.update(gateway, Some(known), latest, anchor).await
+                {
+                    Ok(Update::Unchanged) => (),
+                    Ok(Update::Advanced(head)) => {
+                        *state.write().await = head.clone();
+                        tracing::info!(state=?head, ?finalized, "updated");
+                    }
+                    Ok(Update::Reorg(reorg)) => {
+                        *state.write().await = reorg.head.clone();
+                        tracing::warn!(
+                            state=?reorg.head,
+                            ancestor=reorg.ancestor,
+                            "updated after reorg"
+                        );
+                        match store::truncate(&headers, reorg.ancestor).await {
+                            Ok(0) => (),
+                            Ok(dropped) => {
+                                tracing::info!(dropped, "headers dropped")
+                            }
+                            Err(e) => {
+                                tracing::error!(error=%e, "header drop failed");
+                            }
+                        }
                     }
-                    Ok(_) => (),
                     Err(e) => {
-                        tracing::error!(error=%e, "state update failed");
+                        tracing::error!(error=%e, "head update failed");
+                    }
+                }
+                match store::sync(
+                    &headers,
+                    gateway,
+                    tracker.head(),
+                    HEADER_SYNC_LIMIT,
+                )
+                .await
+                {
+                    Ok(0) => (),
+                    Ok(synced) => tracing::info!(synced, "headers synced"),
+                    Err(e) => {
+                        tracing::error!(error=%e, "header sync failed");
                     }
                 }
             }
         });
     }
 
-    let server = beerus::rpc::serve(
-        &config.client.starknet_rpc,
-        &config.rpc_addr,
-        state,
-    )
-    .await?;
+    let rpc_config = RpcConfig {
+        reorgs,
+        headers,
+        providers: config.client.starknet_rpc_providers.clone(),
+        quorum: config.client.quorum,
+        ..RpcConfig::new(&config.client.starknet_rpc, gateway, state)
+    };
+    let server = beerus::rpc::serve(rpc_config, &config.rpc_addr).await?;
 
     tracing::info!(port = server.port(), "rpc server started");
     server.done().await;
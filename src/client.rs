@@ -1,17 +1,98 @@
+use std::{collections::BTreeMap, sync::Arc};
+
 use eyre::Result;
+use starknet_crypto::Felt as FieldElement;
+use tokio::sync::Mutex;
 
-use crate::config::{get_gateway_url, Config};
+use crate::config::{
+    get_core_contract, get_gateway_pubkey, get_gateway_url, Checkpoint, Config,
+    Quorum,
+};
 use crate::feeder::GatewayClient;
 use crate::gen::client::Client as StarknetClient;
-use crate::gen::{gen, Felt, FunctionCall, Rpc};
+use crate::gen::{
+    gen, Address, BlockHash, BlockId, BlockNumber, ChainId, Felt, FunctionCall,
+    Rpc, StorageKey,
+};
+use crate::hash::block::{BlockHeader, GasPrice, L1DaMode};
+use crate::head::{walk_parents, KnownHeaders};
+use crate::l1::EthereumClient;
 
 const RPC_SPEC_VERSION: &str = "0.7.1";
 
+/// Error code returned when the providers do not agree on a response
+pub const QUORUM_ERROR_CODE: i64 = 32103;
+
+// Recent blocks known to descend from the checkpoint, so that a new head is
+// usually linked to one of them in a few steps
+const LINEAGE_SIZE: usize = 128;
+
+// Headers fetched at most to link a head to the checkpoint (or to the state
+// finalized on L1): an older checkpoint must be replaced, unless the headers
+// are already known
+pub(crate) const LINEAGE_WALK_LIMIT: usize = 1024;
+
 #[derive(Debug, Clone)]
 pub struct State {
     pub block_number: u64,
     pub block_hash: Felt,
     pub root: Felt,
+    pub timestamp: u64,
+    pub sequencer_address: FieldElement,
+    pub l1_gas_price: GasPrice,
+    pub l1_data_gas_price: GasPrice,
+    pub l1_da_mode: L1DaMode,
+    pub starknet_version: String,
+}
+
+impl State {
+    /// State known only by the block number, hash and root (e.g. read from the
+    /// core contract on L1), with the rest of the block context left empty.
+    pub fn new(block_number: u64, block_hash: Felt, root: Felt) -> Self {
+        Self {
+            block_number,
+            block_hash,
+            root,
+            timestamp: 0,
+            sequencer_address: FieldElement::ZERO,
+            l1_gas_price: GasPrice::default(),
+            l1_data_gas_price: GasPrice::default(),
+            l1_da_mode: L1DaMode::default(),
+            starknet_version: String::new(),
+        }
+    }
+}
+
+impl TryFrom<&BlockHeader> for State {
+    type Error = iamgroot::jsonrpc::Error;
+
+    fn try_from(
+        header: &BlockHeader,
+    ) -> std::result::Result<Self, Self::Error> {
+        Ok(Self {
+            block_number: header.block_number,
+            block_hash: Felt::try_new(&format!("{:#x}", header.block_hash))?,
+            root: Felt::try_new(&format!("{:#x}", header.state_root))?,
+            timestamp: header.timestamp,
+            sequencer_address: header.sequencer_address,
+            l1_gas_price: header.l1_gas_price.clone(),
+            l1_data_gas_price: header.l1_data_gas_price.clone(),
+            l1_da_mode: header.l1_da_mode,
+            starknet_version: header.starknet_version.clone(),
+        })
+    }
+}
+
+/// The latest state reported by the feeder gateway, and (when an Ethereum
+/// RPC is configured) the state finalized in the Starknet core contract, once
+/// it is checked to be an ancestor of the latest state.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub latest: State,
+    pub finalized: Option<State>,
+    /// Headers fetched to link the latest state to the checkpoint, highest
+    /// first, so that they need not be fetched again to follow the head.
+    pub headers: Vec<BlockHeader>,
 }
 
 async fn post<Q: serde::Serialize, R: serde::de::DeserializeOwned>(
@@ -101,6 +182,172 @@ impl gen::client::blocking::HttpClient for Http {
     }
 }
 
+/// Send the request and return its result as the node sent it, for the values
+/// that are checked over their JSON rather than over the parsed types (e.g.
+/// legacy classes, hashed over the ABI as declared).
+pub async fn request<T: gen::client::HttpClient>(
+    http: &T,
+    url: &str,
+    method: &str,
+    params: impl serde::Serialize,
+) -> std::result::Result<serde_json::Value, iamgroot::jsonrpc::Error> {
+    let response = http.post(url, &raw_request(method, params)?).await?;
+    raw_result(response)
+}
+
+/// Blocking version of [`request`].
+pub fn request_blocking<T: gen::client::blocking::HttpClient>(
+    http: &T,
+    url: &str,
+    method: &str,
+    params: impl serde::Serialize,
+) -> std::result::Result<serde_json::Value, iamgroot::jsonrpc::Error> {
+    let response = http.post(url, &raw_request(method, params)?)?;
+    raw_result(response)
+}
+
+fn raw_request(
+    method: &str,
+    params: impl serde::Serialize,
+) -> std::result::Result<iamgroot::jsonrpc::Request, iamgroot::jsonrpc::Error> {
+    let params = serde_json::to_value(params).map_err(|e| {
+        iamgroot::jsonrpc::Error::new(4001, format!("Invalid params: {e}."))
+    })?;
+    Ok(iamgroot::jsonrpc::Request::new(method.to_owned(), params)
+        .with_id(iamgroot::jsonrpc::Id::Number(1)))
+}
+
+fn raw_result(
+    mut response: iamgroot::jsonrpc::Response,
+) -> std::result::Result<serde_json::Value, iamgroot::jsonrpc::Error> {
+    if let Some(error) = response.error.take() {
+        return Err(error);
+    }
+    response.result.take().ok_or_else(|| {
+        iamgroot::jsonrpc::Error::new(5003, "Response missing".to_owned())
+    })
+}
+
+/// Methods whose results legitimately differ between providers (e.g. by how
+/// far each of them has synced): these are only sent to the given URL.
+const NO_QUORUM_METHODS: &[&str] = &[
+    "starknet_specVersion",
+    "starknet_syncing",
+    "starknet_blockNumber",
+    "starknet_blockHashAndNumber",
+    "starknet_getTransactionStatus",
+];
+
+/// Sends each request to the given URL and to the additional providers at the
+/// same time, and returns the response only if enough of them return the
+/// same one.
+#[derive(Clone)]
+pub struct QuorumHttp<T> {
+    http: T,
+    providers: Vec<String>,
+    quorum: Quorum,
+}
+
+impl<T> QuorumHttp<T> {
+    pub fn new(http: T, providers: Vec<String>, quorum: Quorum) -> Self {
+        Self { http, providers, quorum }
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
+impl<T: gen::client::HttpClient> gen::client::HttpClient for QuorumHttp<T> {
+    async fn post(
+        &self,
+        url: &str,
+        request: &iamgroot::jsonrpc::Request,
+    ) -> std::result::Result<
+        iamgroot::jsonrpc::Response,
+        iamgroot::jsonrpc::Error,
+    > {
+        if self.providers.is_empty()
+            || NO_QUORUM_METHODS.contains(&request.method.as_str())
+        {
+            return self.http.post(url, request).await;
+        }
+
+        let urls = std::iter::once(url)
+            .chain(self.providers.iter().map(String::as_str));
+        let results = futures::future::join_all(urls.map(|url| async move {
+            (url, self.http.post(url, request).await)
+        }))
+        .await;
+        let mut responses = Vec::with_capacity(results.len());
+        for (url, result) in results {
+            match result {
+                Ok(response) => responses.push((url, response)),
+                Err(e) => {
+                    tracing::warn!(
+                        url,
+                        method = %request.method,
+                        error = ?e,
+                        "provider failed"
+                    );
+                }
+            }
+        }
+
+        // Responses agree when the results are equal, or the error codes are
+        // (the error messages are free to differ between providers)
+        let vote = |response: &iamgroot::jsonrpc::Response| {
+            (
+                response.result.clone(),
+                response.error.as_ref().map(|error| error.code),
+            )
+        };
+        let mut votes: Vec<(usize, usize)> = Vec::new();
+        for (index, (_, response)) in responses.iter().enumerate() {
+            let key = vote(response);
+            match votes
+                .iter_mut()
+                .find(|(first, _)| vote(&responses[*first].1) == key)
+            {
+                Some((_, count)) => *count += 1,
+                None => votes.push((index, 1)),
+            }
+        }
+
+        let threshold = self.quorum.threshold(self.providers.len() + 1);
+        match votes.iter().max_by_key(|(_, count)| *count) {
+            Some((index, count)) if *count >= threshold => {
+                if *count < responses.len() {
+                    tracing::warn!(
+                        method = %request.method,
+                        agreed = count,
+                        responded = responses.len(),
+                        "providers mismatch"
+                    );
+                }
+                Ok(responses.swap_remove(*index).1)
+            }
+            _ => {
+                let urls =
+                    responses.iter().map(|(url, _)| *url).collect::<Vec<_>>();
+                tracing::error!(
+                    method = %request.method,
+                    ?urls,
+                    ?votes,
+                    threshold,
+                    "quorum not reached"
+                );
+                Err(iamgroot::jsonrpc::Error::new(
+                    QUORUM_ERROR_CODE,
+                    format!(
+                        "Quorum not reached: method={} threshold={threshold} votes={:?}",
+                        request.method,
+                        votes.iter().map(|(_, count)| *count).collect::<Vec<_>>()
+                    ),
+                ))
+            }
+        }
+    }
+}
+
 pub struct Client<
     T: gen::client::HttpClient
         + gen::client::blocking::HttpClient
@@ -108,8 +355,20 @@ pub struct Client<
         + 'static,
 > {
     starknet: StarknetClient<T>,
+    quorum: StarknetClient<QuorumHttp<T>>,
     gateway: GatewayClient,
+    ethereum: Option<EthereumClient>,
     http: T,
+    chain_id: ChainId,
+    lineage: Option<Mutex<Lineage>>,
+    headers: Option<Arc<dyn KnownHeaders>>,
+}
+
+/// Blocks that are known to descend from the trusted checkpoint.
+struct Lineage {
+    checkpoint: Checkpoint,
+    blocks: BTreeMap<u64, Felt>,
+    limit: usize,
 }
 
 impl<
@@ -121,33 +380,95 @@ impl<
 {
     pub async fn new(config: &Config, http: T) -> Result<Self> {
         let starknet = StarknetClient::new(&config.starknet_rpc, http.clone());
-        let rpc_spec_version = starknet.specVersion().await?;
+        let quorum = StarknetClient::new(
+            &config.starknet_rpc,
+            QuorumHttp::new(
+                http.clone(),
+                config.starknet_rpc_providers.clone(),
+                config.quorum,
+            ),
+        );
+        let rpc_spec_version = quorum.specVersion().await?;
         if rpc_spec_version != RPC_SPEC_VERSION {
             eyre::bail!("RPC spec version mismatch: expected {RPC_SPEC_VERSION} but got {rpc_spec_version}");
         }
+        let chain_id = quorum.chainId().await?;
         let url = if let Some(url) = config.gateway_url.as_ref() {
             url.as_str()
         } else {
             get_gateway_url(&config.starknet_rpc).await?
         };
-        let gateway = GatewayClient::new(url)?;
-        Ok(Self { starknet, gateway, http })
+        let pubkey = if let Some(pubkey) = config.gateway_pubkey.as_ref() {
+            pubkey.as_str()
+        } else {
+            get_gateway_pubkey(&config.starknet_rpc).await?
+        };
+        let gateway = GatewayClient::new(url, pubkey)?;
+        let ethereum = if let Some(url) = config.ethereum_rpc.as_ref() {
+            let contract = if let Some(addr) = config.core_contract.as_ref() {
+                addr.as_str()
+            } else {
+                get_core_contract(&config.starknet_rpc).await?
+            };
+            Some(EthereumClient::new(url, contract))
+        } else {
+            None
+        };
+        let lineage = if let Some(checkpoint) = config.checkpoint.as_ref() {
+            check_checkpoint(&gateway, checkpoint).await?;
+            Some(Mutex::new(Lineage {
+                checkpoint: checkpoint.clone(),
+                blocks: BTreeMap::new(),
+                limit: LINEAGE_WALK_LIMIT,
+            }))
+        } else {
+            None
+        };
+        Ok(Self {
+            starknet,
+            quorum,
+            gateway,
+            ethereum,
+            http,
+            chain_id,
+            lineage,
+            headers: None,
+        })
+    }
+
+    /// Verified headers to follow before asking the gateway, when the new
+    /// heads are linked to the checkpoint.
+    pub fn with_headers(mut self, headers: Arc<dyn KnownHeaders>) -> Self {
+        self.headers = Some(headers);
+        self
     }
 
     pub fn starknet(&self) -> &StarknetClient<T> {
         &self.starknet
     }
 
+    /// Client for the methods that cannot be verified locally: the response
+    /// is returned only when the quorum of the providers agrees on it.
+    pub fn quorum(&self) -> &StarknetClient<QuorumHttp<T>> {
+        &self.quorum
+    }
+
+    pub fn gateway(&self) -> &GatewayClient {
+        &self.gateway
+    }
+
     pub fn execute(
         &self,
         request: FunctionCall,
         state: State,
     ) -> Result<Vec<Felt>> {
-        let client = gen::client::blocking::Client::new(
+        let call_info = crate::exe::call(
             &self.starknet.url,
             self.http.clone(),
-        );
-        let call_info = crate::exe::call(client, request, state)?;
+            request,
+            state,
+            &self.chain_id,
+        )?;
         call_info
             .execution
             .retdata
@@ -157,11 +478,241 @@ impl<
             .collect()
     }
 
-    pub async fn get_state(&self) -> Result<State> {
-        self.gateway.get_state().await
+    /// Execute the calls against the same state, reusing the values and
+    /// classes fetched by the previous calls. With `chained` set, each call
+    /// sees the writes of the previous successful calls. Every call gets its
+    /// own outcome: a failed call does not fail the others.
+    pub fn execute_many(
+        &self,
+        requests: Vec<FunctionCall>,
+        state: State,
+        chained: bool,
+    ) -> Result<Vec<Result<Vec<Felt>>>> {
+        let calls = crate::exe::call_many(
+            &self.starknet.url,
+            self.http.clone(),
+            requests,
+            chained,
+            state,
+            &self.chain_id,
+        )?;
+        Ok(calls
+            .into_iter()
+            .map(|call_info| {
+                call_info?
+                    .execution
+                    .retdata
+                    .0
+                    .into_iter()
+                    .map(|felt| as_felt(&felt.to_bytes_be()))
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// Read values of many storage keys of a contract at the given state from
+    /// a single storage proof, and verify all of them against the state root.
+    pub async fn get_storage_at_batch(
+        &self,
+        contract_address: Address,
+        keys: Vec<StorageKey>,
+        state: State,
+    ) -> Result<Vec<Felt>> {
+        let block_id =
+            BlockId::BlockHash { block_hash: BlockHash(state.block_hash) };
+        let proof = self
+            .starknet
+            .getProof(block_id, contract_address.clone(), keys.clone())
+            .await?;
+        let values = proof.storage_values(&keys)?;
+        proof.verify_many(state.root, contract_address, &keys, &values)?;
+        Ok(values)
+    }
+
+    pub async fn get_state(&self) -> Result<Snapshot> {
+        let latest = self.gateway.get_state().await?;
+        let fetched = if let Some(lineage) = self.lineage.as_ref() {
+            let headers = self.headers.as_deref();
+            lineage.lock().await.check(&self.gateway, headers, &latest).await?
+        } else {
+            Vec::new()
+        };
+        let finalized = if let Some(ethereum) = self.ethereum.as_ref() {
+            let finalized = ethereum.get_state().await?;
+            if finalized.block_number > latest.block_number {
+                eyre::bail!(
+                    "L1 state is ahead of L2: finalized={} latest={}",
+                    finalized.block_number,
+                    latest.block_number
+                );
+            }
+            if finalized.block_number == latest.block_number
+                && finalized != latest
+            {
+                eyre::bail!(
+                    "L1 and L2 states mismatch at block {}",
+                    latest.block_number
+                );
+            }
+            let headers = self.headers.as_deref();
+            let limit = LINEAGE_WALK_LIMIT;
+            match is_ancestor(
+                &self.gateway,
+                headers,
+                &finalized,
+                &latest,
+                limit,
+            )
+            .await?
+            {
+                Some(true) => Some(finalized),
+                Some(false) => eyre::bail!(
+                    "L1 state is not an ancestor of L2: finalized={} latest={}",
+                    finalized.block_number,
+                    latest.block_number
+                ),
+                None => {
+                    tracing::warn!(
+                        finalized = finalized.block_number,
+                        latest = latest.block_number,
+                        "L1 state too far behind to be checked"
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        Ok(Snapshot { latest, finalized, headers: fetched })
+    }
+}
+
+impl Lineage {
+    /// Check that the head descends from the checkpoint: follow the verified
+    /// parent links back to the checkpoint or to a block already known to
+    /// descend from it. Returns the headers fetched on the way.
+    async fn check(
+        &mut self,
+        gateway: &GatewayClient,
+        headers: Option<&dyn KnownHeaders>,
+        head: &State,
+    ) -> Result<Vec<BlockHeader>> {
+        let checkpoint = self.checkpoint.block_number;
+        if head.block_number < checkpoint {
+            eyre::bail!(
+                "Head is behind the checkpoint: head={} checkpoint={}",
+                head.block_number,
+                checkpoint
+            );
+        }
+
+        let is_known = |block_number: u64, block_hash: &Felt| {
+            self.blocks
+                .get(&block_number)
+                .map(|known| known.as_ref() == block_hash.as_ref())
+                .unwrap_or_default()
+        };
+        let walk = walk_parents(
+            gateway,
+            headers,
+            (head.block_number, head.block_hash.clone()),
+            self.limit,
+            |block_number, block_hash| {
+                block_number <= checkpoint || is_known(block_number, block_hash)
+            },
+        )
+        .await?;
+        let Some((block_number, block_hash)) = walk.end else {
+            eyre::bail!(
+                "Walk passed the checkpoint: head={}",
+                head.block_number
+            );
+        };
+        if block_number > checkpoint && !is_known(block_number, &block_hash) {
+            eyre::bail!(
+                "Checkpoint too old: more than {} headers between the head {} and the checkpoint {}",
+                self.limit,
+                head.block_number,
+                checkpoint
+            );
+        }
+        if block_number == checkpoint
+            && !same_felt(&block_hash, &self.checkpoint.block_hash)?
+        {
+            eyre::bail!(
+                "Head does not descend from the checkpoint: head={}",
+                head.block_number
+            );
+        }
+
+        self.blocks.extend(walk.passed);
+        while self.blocks.len() > LINEAGE_SIZE {
+            self.blocks.pop_first();
+        }
+        Ok(walk.fetched)
+    }
+}
+
+/// Check that the gateway's chain contains the checkpoint block.
+async fn check_checkpoint(
+    gateway: &GatewayClient,
+    checkpoint: &Checkpoint,
+) -> Result<()> {
+    let block_id = BlockId::BlockNumber {
+        block_number: BlockNumber::try_new(checkpoint.block_number as i64)?,
+    };
+    let header = gateway.get_header(&block_id).await?;
+    let block_hash = Felt::try_new(&format!("{:#x}", header.block_hash))?;
+    let root = Felt::try_new(&format!("{:#x}", header.state_root))?;
+    if !same_felt(&block_hash, &checkpoint.block_hash)?
+        || !same_felt(&root, &checkpoint.root)?
+    {
+        eyre::bail!(
+            "Checkpoint not found: block {} has hash {} and root {}",
+            checkpoint.block_number,
+            block_hash.as_ref(),
+            root.as_ref()
+        );
+    }
+    tracing::info!(block_number = checkpoint.block_number, "checkpoint found");
+    Ok(())
+}
+
+/// Check that the block is an ancestor of the head, following the verified
+/// parent links down from the head. `None` when more than `limit` headers
+/// would have to be fetched to reach the block.
+async fn is_ancestor(
+    gateway: &GatewayClient,
+    headers: Option<&dyn KnownHeaders>,
+    block: &State,
+    head: &State,
+    limit: usize,
+) -> Result<Option<bool>> {
+    let walk = walk_parents(
+        gateway,
+        headers,
+        (head.block_number, head.block_hash.clone()),
+        limit,
+        |block_number, _| block_number <= block.block_number,
+    )
+    .await?;
+    match walk.end {
+        Some((block_number, block_hash))
+            if block_number == block.block_number =>
+        {
+            Ok(Some(same_felt(&block_hash, &block.block_hash)?))
+        }
+        _ => Ok(None),
     }
 }
 
+// Felts are compared by value: the hex strings may differ in letter case
+fn same_felt(lhs: &Felt, rhs: &Felt) -> Result<bool> {
+    let lhs = FieldElement::from_hex(lhs.as_ref())?;
+    let rhs = FieldElement::from_hex(rhs.as_ref())?;
+    Ok(lhs == rhs)
+}
+
 fn as_felt(bytes: &[u8]) -> Result<Felt> {
     // RPC spec FELT regex: leading zeroes are not allowed
     let hex = hex::encode(bytes);
@@ -170,3 +721,206 @@ fn as_felt(bytes: &[u8]) -> Result<Felt> {
     let felt = Felt::try_new(&hex)?;
     Ok(felt)
 }
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::config::MAINNET_GATEWAY_PUBKEY;
+
+    fn header(block_number: u64, parent_hash: FieldElement) -> BlockHeader {
+        let mut header = BlockHeader {
+            block_number,
+            parent_hash,
+            state_root: FieldElement::from(block_number),
+            sequencer_address: FieldElement::from_hex_unchecked("0x6"),
+            timestamp: 1,
+            starknet_version: "0.13.1".to_owned(),
+            ..Default::default()
+        };
+        header.block_hash = header.compute_hash();
+        header
+    }
+
+    fn state(header: &BlockHeader) -> State {
+        State::try_from(header).unwrap()
+    }
+
+    async fn mount(server: &MockServer, header: &BlockHeader, expect: u64) {
+        respond(server, header.block_hash, header, expect).await;
+    }
+
+    // Respond to the query of the block hash with the given header
+    async fn respond(
+        server: &MockServer,
+        block_hash: FieldElement,
+        header: &BlockHeader,
+        expect: u64,
+    ) {
+        let block_hash = format!("{:#x}", block_hash);
+        Mock::given(path("/feeder_gateway/get_block"))
+            .and(query_param("blockHash", block_hash.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "block_number": header.block_number,
+                    "block_hash": format!("{:#x}", header.block_hash),
+                    "parent_block_hash": format!("{:#x}", header.parent_hash),
+                    "state_root": format!("{:#x}", header.state_root),
+                    "sequencer_address": "0x6",
+                    "timestamp": 1,
+                    "starknet_version": "0.13.1",
+                    "status": "ACCEPTED_ON_L2",
+                    "transactions": [],
+                    "transaction_receipts": []
+                }),
+            ))
+            .expect(expect)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_lineage() {
+        let checkpoint = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, checkpoint.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+        let fork_11 = header(11, FieldElement::from(10u64));
+        let fork_12 = header(12, fork_11.block_hash);
+
+        let server = MockServer::start().await;
+        mount(&server, &block_11, 1).await;
+        mount(&server, &block_12, 1).await;
+        mount(&server, &fork_11, 1).await;
+        mount(&server, &fork_12, 1).await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let mut lineage = lineage(&checkpoint, LINEAGE_WALK_LIMIT);
+
+        let fetched =
+            lineage.check(&gateway, None, &state(&block_12)).await.unwrap();
+        assert_eq!(lineage.blocks.len(), 2);
+        assert_eq!(fetched.len(), 2);
+
+        // Known blocks are not fetched again
+        let fetched =
+            lineage.check(&gateway, None, &state(&block_12)).await.unwrap();
+        assert!(fetched.is_empty());
+
+        let result = lineage.check(&gateway, None, &state(&fork_12)).await;
+        assert!(result.is_err());
+        assert_eq!(lineage.blocks.len(), 2);
+
+        let head = state(&header(9, 0u64.into()));
+        let result = lineage.check(&gateway, None, &head).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_is_ancestor() {
+        let block_10 = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, block_10.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+
+        let server = MockServer::start().await;
+        mount(&server, &block_12, 3).await;
+        mount(&server, &block_11, 2).await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let head = state(&block_12);
+        let block = state(&block_10);
+        let result = is_ancestor(&gateway, None, &block, &head, 2).await;
+        assert_eq!(result.unwrap(), Some(true));
+
+        let fork =
+            State::new(10, Felt::try_new("0xa").unwrap(), block.root.clone());
+        let result = is_ancestor(&gateway, None, &fork, &head, 2).await;
+        assert_eq!(result.unwrap(), Some(false));
+
+        let result = is_ancestor(&gateway, None, &block, &head, 1).await;
+        assert_eq!(result.unwrap(), None);
+    }
+
+    fn lineage(checkpoint: &BlockHeader, limit: usize) -> Lineage {
+        let State { block_number, block_hash, root, .. } = state(checkpoint);
+        Lineage {
+            checkpoint: Checkpoint { block_number, block_hash, root },
+            blocks: BTreeMap::new(),
+            limit,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lineage_hash_mismatch() {
+        let checkpoint = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, checkpoint.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+        let other_12 = header(12, FieldElement::from(11u64));
+
+        // The gateway returns a valid header, but not the one asked for
+        let server = MockServer::start().await;
+        respond(&server, block_12.block_hash, &other_12, 1).await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let mut lineage = lineage(&checkpoint, LINEAGE_WALK_LIMIT);
+        let result = lineage.check(&gateway, None, &state(&block_12)).await;
+        assert!(result.unwrap_err().to_string().contains("hash mismatch"));
+        assert!(lineage.blocks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lineage_limit() {
+        let checkpoint = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, checkpoint.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+
+        let server = MockServer::start().await;
+        mount(&server, &block_12, 1).await;
+        mount(&server, &block_11, 0).await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let mut lineage = lineage(&checkpoint, 1);
+        let result = lineage.check(&gateway, None, &state(&block_12)).await;
+        assert!(result.unwrap_err().to_string().contains("too old"));
+        assert!(lineage.blocks.is_empty());
+    }
+
+    struct Known(BTreeMap<u64, (Felt, Felt)>);
+
+    impl KnownHeaders for Known {
+        fn link(&self, block_number: u64) -> Option<(Felt, Felt)> {
+            self.0.get(&block_number).cloned()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lineage_known_headers() {
+        let checkpoint = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, checkpoint.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+
+        // Nothing is mounted: every header has to come from the known ones
+        let server = MockServer::start().await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let link = |header: &BlockHeader| {
+            let parent_hash = format!("{:#x}", header.parent_hash);
+            let parent_hash = Felt::try_new(&parent_hash).unwrap();
+            (header.block_number, (state(header).block_hash, parent_hash))
+        };
+        let known = Known(BTreeMap::from([link(&block_11), link(&block_12)]));
+
+        let mut lineage = lineage(&checkpoint, 0);
+        let head = state(&block_12);
+        lineage.check(&gateway, Some(&known), &head).await.unwrap();
+        assert_eq!(lineage.blocks.len(), 2);
+    }
+}
@@ -1,11 +1,15 @@
 use std::fs;
 use std::net::SocketAddr;
 use std::path::Path;
+use std::str::FromStr;
 
 use eyre::{eyre, Context, Result};
 
 use serde::Deserialize;
-use validator::Validate;
+use validator::{Validate, ValidationError};
+
+use crate::gen::Felt;
+use crate::l1::{MAINNET_CORE_CONTRACT, SEPOLIA_CORE_CONTRACT};
 
 #[cfg(not(target_arch = "wasm32"))]
 const DEFAULT_DATA_DIR: &str = "tmp";
@@ -14,6 +18,18 @@ const DEFAULT_POLL_SECS: u64 = 30;
 pub const MAINNET_STARKNET_CHAINID: &str = "0x534e5f4d41494e";
 pub const SEPOLIA_STARKNET_CHAINID: &str = "0x534e5f5345504f4c4941";
 
+/// Fee token contracts (same on Mainnet and Sepolia)
+pub const ETH_FEE_TOKEN: &str =
+    "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+pub const STRK_FEE_TOKEN: &str =
+    "0x4718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d";
+
+/// Public keys the sequencer uses to sign the blocks of each chain
+pub const MAINNET_GATEWAY_PUBKEY: &str =
+    "0x48253ff2c3bed7af18bde0b611b083b39445959102d4947c51c4db6aa4f4e58";
+pub const SEPOLIA_GATEWAY_PUBKEY: &str =
+    "0x1252b6bce1351844c677869c6327e80eae1535755b611c66b8f46e595b40eea";
+
 #[derive(Clone, Deserialize, Debug, Validate)]
 pub struct ServerConfig {
     #[serde(flatten)]
@@ -25,12 +41,79 @@ pub struct ServerConfig {
     pub rpc_addr: SocketAddr,
 }
 
+/// How many of the Starknet RPC providers must return the same response for
+/// a method that cannot be verified locally.
+#[derive(Clone, Copy, Default, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Quorum {
+    #[default]
+    Majority,
+    All,
+}
+
+impl Quorum {
+    pub fn threshold(&self, providers: usize) -> usize {
+        match self {
+            Quorum::Majority => providers / 2 + 1,
+            Quorum::All => providers,
+        }
+    }
+}
+
+impl FromStr for Quorum {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "majority" => Ok(Quorum::Majority),
+            "all" => Ok(Quorum::All),
+            _ => eyre::bail!("Unexpected quorum: {s}"),
+        }
+    }
+}
+
+/// Trusted block to bootstrap from: only the heads that descend from it by
+/// verified parent links are accepted.
+#[derive(Clone, Deserialize, Debug)]
+pub struct Checkpoint {
+    pub block_number: u64,
+    pub block_hash: Felt,
+    pub root: Felt,
+}
+
+impl FromStr for Checkpoint {
+    type Err = eyre::Report;
+
+    /// Parse the checkpoint from `<block_number>,<block_hash>,<root>`
+    fn from_str(s: &str) -> Result<Self> {
+        let parts = s.split(',').map(str::trim).collect::<Vec<_>>();
+        let [block_number, block_hash, root] = parts.as_slice() else {
+            eyre::bail!("Unexpected checkpoint: {s}");
+        };
+        Ok(Self {
+            block_number: block_number.parse()?,
+            block_hash: Felt::try_new(block_hash)?,
+            root: Felt::try_new(root)?,
+        })
+    }
+}
+
 #[derive(Clone, Deserialize, Debug, Validate)]
 pub struct Config {
     #[validate(url)]
     pub starknet_rpc: String,
+    #[serde(default)]
+    #[validate(custom(function = "validate_urls"))]
+    pub starknet_rpc_providers: Vec<String>,
+    #[serde(default)]
+    pub quorum: Quorum,
     #[validate(url)]
     pub gateway_url: Option<String>,
+    pub gateway_pubkey: Option<String>,
+    #[validate(url)]
+    pub ethereum_rpc: Option<String>,
+    pub core_contract: Option<String>,
+    pub checkpoint: Option<Checkpoint>,
     #[cfg(not(target_arch = "wasm32"))]
     #[serde(default = "default_data_dir")]
     pub data_dir: String,
@@ -41,6 +124,15 @@ fn default_data_dir() -> String {
     DEFAULT_DATA_DIR.to_owned()
 }
 
+fn validate_urls(urls: &[String]) -> Result<(), ValidationError> {
+    for url in urls {
+        if url::Url::parse(url).is_err() {
+            return Err(ValidationError::new("url"));
+        }
+    }
+    Ok(())
+}
+
 fn default_poll_secs() -> u64 {
     DEFAULT_POLL_SECS
 }
@@ -61,11 +153,33 @@ impl ServerConfig {
         } else {
             default_rpc_addr()
         };
+        let starknet_rpc_providers =
+            if let Ok(urls) = std::env::var("STARKNET_RPC_PROVIDERS") {
+                urls.split(',').map(|url| url.trim().to_owned()).collect()
+            } else {
+                Vec::new()
+            };
+        let quorum = if let Ok(quorum) = std::env::var("QUORUM") {
+            quorum.parse()?
+        } else {
+            Quorum::default()
+        };
+        let checkpoint = if let Ok(checkpoint) = std::env::var("CHECKPOINT") {
+            Some(checkpoint.parse()?)
+        } else {
+            None
+        };
         Ok(Self {
             client: Config {
                 starknet_rpc: std::env::var("STARKNET_RPC")
                     .context("STARKNET_RPC env var missing")?,
+                starknet_rpc_providers,
+                quorum,
                 gateway_url: std::env::var("GATEWAY_URL").ok(),
+                gateway_pubkey: std::env::var("GATEWAY_PUBKEY").ok(),
+                ethereum_rpc: std::env::var("ETHEREUM_RPC").ok(),
+                core_contract: std::env::var("CORE_CONTRACT").ok(),
+                checkpoint,
                 #[cfg(not(target_arch = "wasm32"))]
                 data_dir: std::env::var("DATA_DIR")
                     .unwrap_or_else(|_| default_data_dir()),
@@ -90,6 +204,24 @@ pub async fn get_gateway_url(starknet_rpc: &str) -> Result<&'static str> {
     }
 }
 
+pub async fn get_gateway_pubkey(starknet_rpc: &str) -> Result<&'static str> {
+    let chain_id = call_method(starknet_rpc, "starknet_chainId").await?;
+    match chain_id.as_str() {
+        MAINNET_STARKNET_CHAINID => Ok(MAINNET_GATEWAY_PUBKEY),
+        SEPOLIA_STARKNET_CHAINID => Ok(SEPOLIA_GATEWAY_PUBKEY),
+        _ => eyre::bail!("Unexpected chain id: {}", chain_id),
+    }
+}
+
+pub async fn get_core_contract(starknet_rpc: &str) -> Result<&'static str> {
+    let chain_id = call_method(starknet_rpc, "starknet_chainId").await?;
+    match chain_id.as_str() {
+        MAINNET_STARKNET_CHAINID => Ok(MAINNET_CORE_CONTRACT),
+        SEPOLIA_STARKNET_CHAINID => Ok(SEPOLIA_CORE_CONTRACT),
+        _ => eyre::bail!("Unexpected chain id: {}", chain_id),
+    }
+}
+
 pub fn check_data_dir<P: AsRef<Path>>(path: &P) -> Result<()> {
     let path = path.as_ref();
     if !path.exists() {
@@ -142,7 +274,13 @@ mod tests {
         let config = ServerConfig {
             client: Config {
                 starknet_rpc: "bar".to_string(),
+                starknet_rpc_providers: vec![],
+                quorum: Quorum::Majority,
                 gateway_url: None,
+                gateway_pubkey: None,
+                ethereum_rpc: None,
+                core_contract: None,
+                checkpoint: None,
                 data_dir: Default::default(),
             },
             poll_secs: 300,
@@ -159,7 +297,13 @@ mod tests {
         let config = ServerConfig {
             client: Config {
                 starknet_rpc: "bar".to_string(),
+                starknet_rpc_providers: vec![],
+                quorum: Quorum::Majority,
                 gateway_url: None,
+                gateway_pubkey: None,
+                ethereum_rpc: None,
+                core_contract: None,
+                checkpoint: None,
                 data_dir: Default::default(),
             },
             poll_secs: 9999,
@@ -170,4 +314,50 @@ mod tests {
         assert!(response.is_err());
         assert!(response.unwrap_err().to_string().contains("poll_secs"));
     }
+
+    #[tokio::test]
+    async fn wrong_provider_urls() {
+        let config = Config {
+            starknet_rpc: "http://localhost:9545".to_string(),
+            starknet_rpc_providers: vec![
+                "http://localhost:9546".to_string(),
+                "bar".to_string(),
+            ],
+            quorum: Quorum::All,
+            gateway_url: None,
+            gateway_pubkey: None,
+            ethereum_rpc: None,
+            core_contract: None,
+            checkpoint: None,
+            data_dir: Default::default(),
+        };
+        let response = config.validate();
+
+        assert!(response.is_err());
+        assert!(response
+            .unwrap_err()
+            .to_string()
+            .contains("starknet_rpc_providers"));
+    }
+
+    #[test]
+    fn quorum_threshold() {
+        assert_eq!(Quorum::Majority.threshold(1), 1);
+        assert_eq!(Quorum::Majority.threshold(2), 2);
+        assert_eq!(Quorum::Majority.threshold(3), 2);
+        assert_eq!(Quorum::All.threshold(3), 3);
+        assert_eq!("all".parse::<Quorum>().unwrap(), Quorum::All);
+        assert!("some".parse::<Quorum>().is_err());
+    }
+
+    #[test]
+    fn parse_checkpoint() {
+        let checkpoint: Checkpoint = "650000, 0x1a2b, 0x3c4d".parse().unwrap();
+        assert_eq!(checkpoint.block_number, 650000);
+        assert_eq!(checkpoint.block_hash.as_ref(), "0x1a2b");
+        assert_eq!(checkpoint.root.as_ref(), "0x3c4d");
+
+        assert!("650000,0x1a2b".parse::<Checkpoint>().is_err());
+        assert!("latest,0x1a2b,0x3c4d".parse::<Checkpoint>().is_err());
+    }
 }
@@ -1,13 +1,32 @@
 use alloy_primitives::U256;
 use blockifier::state::state_api::{State as BlockifierState, StateReader};
+use cairo_lang_starknet_classes::casm_contract_class::CasmContractClass;
 use lru::LruCache;
 use starknet_api::{core::ContractAddress, state::StorageKey};
 use starknet_types_core::felt::Felt as StarkFelt;
+use std::hash::Hash;
 use std::num::NonZeroUsize;
 use std::sync::{LazyLock, Mutex};
 
+use super::{err::Error, map, overlay::Overlay};
 use crate::gen;
 
+// Drop the cached entries whose keys match, returning how many were dropped
+fn drop_where<K: Hash + Eq + Clone, V>(
+    cache: &mut LruCache<K, V>,
+    matches: impl Fn(&K) -> bool,
+) -> usize {
+    let keys = cache
+        .iter()
+        .filter(|(key, _)| matches(key))
+        .map(|(key, _)| key.clone())
+        .collect::<Vec<_>>();
+    for key in &keys {
+        cache.pop(key);
+    }
+    keys.len()
+}
+
 mod storage {
     use super::*;
 
@@ -30,6 +49,11 @@ mod storage {
         guard.put(key, value)
     }
 
+    pub fn invalidate(block_hash: &U256) -> usize {
+        let mut guard = CACHE.lock().expect("storage-cache-lock");
+        drop_where(&mut guard, |key| &key.0 == block_hash)
+    }
+
     pub fn key(
         block_hash: &gen::Felt,
         contract_address: &ContractAddress,
@@ -65,6 +89,11 @@ mod class_hash {
         guard.put(key, value)
     }
 
+    pub fn invalidate(block_hash: &U256) -> usize {
+        let mut guard = CACHE.lock().expect("classhash-cache-lock");
+        drop_where(&mut guard, |key| &key.0 == block_hash)
+    }
+
     pub fn key(
         block_hash: &gen::Felt,
         contract_address: &ContractAddress,
@@ -98,6 +127,49 @@ mod contract_class {
         guard.put(key, value)
     }
 
+    pub fn invalidate(block_hash: &U256) -> usize {
+        let mut guard = CACHE.lock().expect("contractclass-cache-lock");
+        drop_where(&mut guard, |key| &key.0 == block_hash)
+    }
+
+    pub fn key(
+        block_hash: &gen::Felt,
+        class_hash: &starknet_api::core::ClassHash,
+    ) -> Key {
+        (
+            block_hash.as_ref().parse().unwrap(),
+            U256::from_be_bytes(class_hash.0.to_bytes_be()),
+        )
+    }
+}
+
+mod compiled_class_hash {
+    use super::*;
+
+    type Key = (U256, U256); // block hash + class hash
+    type Value = starknet_api::core::CompiledClassHash;
+
+    const SIZE: usize = 256;
+
+    static CACHE: LazyLock<Mutex<LruCache<Key, Value>>> = LazyLock::new(|| {
+        Mutex::new(LruCache::new(NonZeroUsize::new(SIZE).unwrap()))
+    });
+
+    pub fn get(key: &Key) -> Option<Value> {
+        let mut guard = CACHE.lock().expect("compiledclasshash-cache-lock");
+        guard.get(key).cloned()
+    }
+
+    pub fn set(key: Key, value: Value) -> Option<Value> {
+        let mut guard = CACHE.lock().expect("compiledclasshash-cache-lock");
+        guard.put(key, value)
+    }
+
+    pub fn invalidate(block_hash: &U256) -> usize {
+        let mut guard = CACHE.lock().expect("compiledclasshash-cache-lock");
+        drop_where(&mut guard, |key| &key.0 == block_hash)
+    }
+
     pub fn key(
         block_hash: &gen::Felt,
         class_hash: &starknet_api::core::ClassHash,
@@ -109,21 +181,107 @@ mod contract_class {
     }
 }
 
+// CASM of verified Sierra classes. The class hash commits to the class, so
+// these do not depend on the block.
+mod casm {
+    use super::*;
+
+    type Key = U256; // class hash
+    type Value = CasmContractClass;
+
+    const SIZE: usize = 64;
+
+    static CACHE: LazyLock<Mutex<LruCache<Key, Value>>> = LazyLock::new(|| {
+        Mutex::new(LruCache::new(NonZeroUsize::new(SIZE).unwrap()))
+    });
+
+    pub fn get(key: &Key) -> Option<Value> {
+        let mut guard = CACHE.lock().expect("casm-cache-lock");
+        guard.get(key).cloned()
+    }
+
+    pub fn set(key: Key, value: Value) -> Option<Value> {
+        let mut guard = CACHE.lock().expect("casm-cache-lock");
+        guard.put(key, value)
+    }
+
+    pub fn key(class_hash: &gen::Felt) -> Key {
+        class_hash.as_ref().parse().unwrap()
+    }
+}
+
+/// CASM of the Sierra class compiled earlier, if any.
+pub fn get_casm(class_hash: &gen::Felt) -> Option<CasmContractClass> {
+    casm::get(&casm::key(class_hash))
+}
+
+/// Compile the Sierra class (which must already be verified against the
+/// class hash) unless it was compiled before.
+pub fn compile_sierra(
+    class_hash: &gen::Felt,
+    class: &gen::ContractClass,
+) -> Result<CasmContractClass, Error> {
+    if let Some(ret) = get_casm(class_hash) {
+        return Ok(ret);
+    }
+    let ret = map::compile_sierra(class)?;
+    casm::set(casm::key(class_hash), ret.clone());
+    Ok(ret)
+}
+
+/// Drop the values cached for the given block (e.g. when a reorg removes the
+/// block from the canonical chain).
+pub fn invalidate(block_hash: &gen::Felt) {
+    let Ok(key) = block_hash.as_ref().parse::<U256>() else {
+        return;
+    };
+    let storage = storage::invalidate(&key);
+    let class_hash = class_hash::invalidate(&key);
+    let contract_class = contract_class::invalidate(&key);
+    let compiled_class_hash = compiled_class_hash::invalidate(&key);
+    tracing::debug!(
+        ?block_hash,
+        storage,
+        class_hash,
+        contract_class,
+        compiled_class_hash,
+        "cache invalidated"
+    );
+}
+
 pub trait HasBlockHash {
     fn get_block_hash(&self) -> &gen::Felt;
 }
 
-pub struct CachedState<T: StateReader + BlockifierState + HasBlockHash> {
+/// Writes on top of the block state: these are never cached, and the values
+/// cached for the block must not shadow them.
+pub trait HasOverlay {
+    fn get_overlay(&self) -> &Overlay;
+}
+
+pub struct CachedState<
+    T: StateReader + BlockifierState + HasBlockHash + HasOverlay,
+> {
     inner: T,
 }
 
-impl<T: StateReader + BlockifierState + HasBlockHash> CachedState<T> {
+impl<T: StateReader + BlockifierState + HasBlockHash + HasOverlay>
+    CachedState<T>
+{
     pub fn new(inner: T) -> Self {
         Self { inner }
     }
 }
 
-impl<T: StateReader + BlockifierState + HasBlockHash> StateReader
+impl<T: StateReader + BlockifierState + HasBlockHash + HasOverlay> HasOverlay
+    for CachedState<T>
+{
+    fn get_overlay(&self) -> &Overlay {
+        self.inner.get_overlay()
+    }
+}
+
+impl<T: StateReader + BlockifierState + HasBlockHash + HasOverlay> StateReader
     for CachedState<T>
 {
     fn get_storage_at(
@@ -131,6 +289,11 @@ impl<T: StateReader + BlockifierState + HasBlockHash> StateReader
         contract_address: ContractAddress,
         storage_key: StorageKey,
     ) -> blockifier::state::state_api::StateResult<StarkFelt> {
+        let overlay = self.inner.get_overlay();
+        if let Some(ret) = overlay.get_storage_at(contract_address, storage_key)
+        {
+            return Ok(ret);
+        }
         let block_hash = self.inner.get_block_hash();
         if let Some(ret) = storage::get(&storage::key(
             block_hash,
@@ -160,6 +323,10 @@ impl<T: StateReader + BlockifierState + HasBlockHash> StateReader
         contract_address: ContractAddress,
     ) -> blockifier::state::state_api::StateResult<starknet_api::core::ClassHash>
     {
+        let overlay = self.inner.get_overlay();
+        if let Some(ret) = overlay.get_class_hash_at(contract_address) {
+            return Ok(ret);
+        }
         let block_hash = self.inner.get_block_hash();
         if let Some(ret) =
             class_hash::get(&class_hash::key(block_hash, &contract_address))
@@ -177,6 +344,10 @@ impl<T: StateReader + BlockifierState + HasBlockHash> StateReader
     ) -> blockifier::state::state_api::StateResult<
         blockifier::execution::contract_class::ContractClass,
     > {
+        let overlay = self.inner.get_overlay();
+        if let Some(ret) = overlay.get_compiled_contract_class(class_hash) {
+            return Ok(ret);
+        }
         let block_hash = self.inner.get_block_hash();
         if let Some(ret) =
             contract_class::get(&contract_class::key(block_hash, &class_hash))
@@ -197,12 +368,28 @@ impl<T: StateReader + BlockifierState + HasBlockHash> StateReader
     ) -> blockifier::state::state_api::StateResult<
         starknet_api::core::CompiledClassHash,
     > {
-        self.inner.get_compiled_class_hash(class_hash)
+        let overlay = self.inner.get_overlay();
+        if let Some(ret) = overlay.get_compiled_class_hash(class_hash) {
+            return Ok(ret);
+        }
+        let block_hash = self.inner.get_block_hash();
+        if let Some(ret) = compiled_class_hash::get(&compiled_class_hash::key(
+            block_hash,
+            &class_hash,
+        )) {
+            return Ok(ret);
+        }
+        let ret = self.inner.get_compiled_class_hash(class_hash)?;
+        compiled_class_hash::set(
+            compiled_class_hash::key(block_hash, &class_hash),
+            ret,
+        );
+        Ok(ret)
     }
 }
 
-impl<T: StateReader + BlockifierState + HasBlockHash> BlockifierState
-    for CachedState<T>
+impl<T: StateReader + BlockifierState + HasBlockHash + HasOverlay>
+    BlockifierState for CachedState<T>
 {
     fn set_storage_at(
         &mut self,
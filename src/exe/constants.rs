@@ -0,0 +1,111 @@
+use std::{
+    collections::HashSet,
+    sync::{LazyLock, Mutex},
+};
+
+use blockifier::versioned_constants::VersionedConstants;
+
+use crate::hash::block::parse_version;
+
+// Constants of the older Starknet versions, as shipped with blockifier
+// (the latest ones are built into blockifier itself)
+const V0_13_0: &str =
+    include_str!("../../etc/constants/versioned_constants_13_0.json");
+const V0_13_1: &str =
+    include_str!("../../etc/constants/versioned_constants_13_1.json");
+const V0_13_1_1: &str =
+    include_str!("../../etc/constants/versioned_constants_13_1_1.json");
+
+// Starknet version the latest constants of blockifier belong to
+const LATEST: [u64; 4] = [0, 13, 2, 0];
+
+static CONSTANTS: LazyLock<Vec<([u64; 4], VersionedConstants)>> =
+    LazyLock::new(|| {
+        [
+            ([0, 13, 0, 0], V0_13_0),
+            ([0, 13, 1, 0], V0_13_1),
+            ([0, 13, 1, 1], V0_13_1_1),
+        ]
+        .into_iter()
+        .map(|(version, json)| {
+            let constants = serde_json::from_str(json)
+                .expect("versioned constants are malformed");
+            (version, constants)
+        })
+        .collect()
+    });
+
+// Unknown versions already warned about, so that each is logged once
+static WARNED: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(Default::default);
+
+/// Versioned constants (gas costs, step limits etc.) of the given Starknet
+/// version. Patch releases of the latest version share its constants. Other
+/// unknown versions get the closest older constants (the oldest ones before
+/// 0.13.0), or the latest constants when newer or missing.
+pub fn get(starknet_version: &str) -> &'static VersionedConstants {
+    let version = parse_version(starknet_version);
+    if version[..3] == LATEST[..3] {
+        return VersionedConstants::latest_constants();
+    }
+    if let Some((_, constants)) =
+        CONSTANTS.iter().find(|(known, _)| known == &version)
+    {
+        return constants;
+    }
+
+    let (using, constants) = if version == [0; 4] || version > LATEST {
+        (LATEST, VersionedConstants::latest_constants())
+    } else {
+        let (known, constants) = CONSTANTS
+            .iter()
+            .rev()
+            .find(|(known, _)| known < &version)
+            .unwrap_or(&CONSTANTS[0]);
+        (*known, constants)
+    };
+    if first_warning(starknet_version) {
+        tracing::warn!(
+            starknet_version,
+            using = ?using,
+            "unknown starknet version: using constants of another version"
+        );
+    }
+    constants
+}
+
+fn first_warning(starknet_version: &str) -> bool {
+    WARNED
+        .lock()
+        .expect("constants-warned-lock")
+        .insert(starknet_version.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constants_by_version() {
+        let latest = VersionedConstants::latest_constants();
+        assert_eq!(get("0.13.0").invoke_tx_max_n_steps, 3_000_000);
+        assert_eq!(get("0.13.1").invoke_tx_max_n_steps, 4_000_000);
+        assert_eq!(get("0.13.1.1").invoke_tx_max_n_steps, 4_000_000);
+        assert!(std::ptr::eq(get("0.13.2"), latest));
+        assert!(std::ptr::eq(get("0.13.2.1"), latest));
+
+        // Versions before 0.13.0 get the oldest constants shipped
+        assert_eq!(get("0.12.3").invoke_tx_max_n_steps, 3_000_000);
+        assert!(std::ptr::eq(get("0.12.3"), get("0.13.0")));
+
+        assert!(std::ptr::eq(get("0.13.3"), latest));
+        assert!(std::ptr::eq(get(""), latest));
+    }
+
+    #[test]
+    fn test_first_warning() {
+        assert!(first_warning("0.11.2"));
+        assert!(!first_warning("0.11.2"));
+        assert!(first_warning("0.11.1"));
+    }
+}
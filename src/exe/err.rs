@@ -1,6 +1,9 @@
 use cairo_lang_starknet_classes::casm_contract_class::StarknetSierraCompilationError;
 use thiserror::Error as ThisError;
 
+// Starknet RPC error of a transaction that failed to execute
+const TRANSACTION_EXECUTION_ERROR: i64 = 41;
+
 #[derive(Debug, ThisError)]
 pub enum Error {
     #[error("io error: {0:?}")]
@@ -25,6 +28,8 @@ pub enum Error {
     ),
     #[error("sierra compilation error: {0:?}")]
     SierraCompilation(#[from] StarknetSierraCompilationError),
+    #[error("transaction {0} reverted: {1}")]
+    Reverted(usize, String),
     #[error("program error: {0}")]
     Program(String),
     #[error("{0}")]
@@ -33,9 +38,12 @@ pub enum Error {
 
 impl From<Error> for blockifier::state::errors::StateError {
     fn from(error: Error) -> Self {
-        blockifier::state::errors::StateError::StateReadError(format!(
-            "{error:?}"
-        ))
+        match error {
+            Error::State(e) => e,
+            e => blockifier::state::errors::StateError::StateReadError(
+                format!("{e:?}"),
+            ),
+        }
     }
 }
 
@@ -43,6 +51,12 @@ impl From<Error> for iamgroot::jsonrpc::Error {
     fn from(error: Error) -> Self {
         match error {
             Error::IamGroot(e) => e,
+            e @ (Error::Transaction(_) | Error::Reverted(..)) => {
+                iamgroot::jsonrpc::Error {
+                    code: TRANSACTION_EXECUTION_ERROR,
+                    message: e.to_string(),
+                }
+            }
             e => iamgroot::jsonrpc::Error { code: 500, message: e.to_string() },
         }
     }
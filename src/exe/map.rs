@@ -43,20 +43,8 @@ impl TryFrom<gen::GetClassResult> for ContractClass {
 
     fn try_from(value: gen::GetClassResult) -> Result<Self, Self::Error> {
         Ok(match value {
-            gen::GetClassResult::ContractClass(ref class) => {
-                let mut json = serde_json::to_value(&value)?;
-                if let Some(abi) = class.abi.as_ref() {
-                    let abi: serde_json::Value = serde_json::from_str(abi)?;
-                    json["abi"] = abi;
-                }
-                let contract_class: CairoContractClass =
-                    serde_json::from_value(json)?;
-                let casm_contract_class =
-                    CasmContractClass::from_contract_class(
-                        contract_class,
-                        /*add_pythonic_hints=*/ false,
-                        /*max_bytecode_size=*/ u16::MAX as usize,
-                    )?;
+            gen::GetClassResult::ContractClass(class) => {
+                let casm_contract_class = compile_sierra(&class)?;
                 let class = casm_contract_class
                     .try_into()
                     .map_err(|e| Error::Program(format!("{e}")))?;
@@ -71,6 +59,24 @@ impl TryFrom<gen::GetClassResult> for ContractClass {
     }
 }
 
+/// Compile the Sierra class to CASM (the same way the sequencer does).
+pub(crate) fn compile_sierra(
+    class: &gen::ContractClass,
+) -> Result<CasmContractClass, Error> {
+    let mut json = serde_json::to_value(class)?;
+    if let Some(abi) = class.abi.as_ref() {
+        let abi: serde_json::Value = serde_json::from_str(abi)?;
+        json["abi"] = abi;
+    }
+    let contract_class: CairoContractClass = serde_json::from_value(json)?;
+    let casm_contract_class = CasmContractClass::from_contract_class(
+        contract_class,
+        /*add_pythonic_hints=*/ false,
+        /*max_bytecode_size=*/ u16::MAX as usize,
+    )?;
+    Ok(casm_contract_class)
+}
+
 fn build_contract_class(
     class: DeprecatedContractClass,
 ) -> Result<ContractClassV0, Error> {
@@ -85,7 +91,7 @@ fn build_contract_class(
     Ok(class)
 }
 
-fn decode_program(program: &str) -> Result<String, Error> {
+pub(crate) fn decode_program(program: &str) -> Result<String, Error> {
     let program = decode_base64(program)?;
     let program = decompress(&program)?;
     Ok(program)
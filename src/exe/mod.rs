@@ -10,14 +10,23 @@ use blockifier::{
         contract_class::ContractClass,
         entry_point::{CallEntryPoint, CallType, EntryPointExecutionContext},
     },
+    fee::actual_cost::TransactionReceipt,
     state::{
+        cached_state::{
+            CachedState as BlockifierCachedState, TransactionalState,
+        },
         errors::StateError,
         state_api::{State as BlockifierState, StateReader, StateResult},
     },
-    transaction::objects::{
-        CommonAccountFields, DeprecatedTransactionInfo, TransactionInfo,
+    transaction::{
+        account_transaction::AccountTransaction,
+        objects::{
+            CommonAccountFields, DeprecatedTransactionInfo, FeeType,
+            HasRelatedFeeType, TransactionExecutionInfo, TransactionInfo,
+        },
+        transaction_execution::Transaction,
+        transactions::ExecutableTransaction,
     },
-    versioned_constants::VersionedConstants,
 };
 use starknet_api::{
     block::{BlockNumber as StarknetBlockNumber, BlockTimestamp},
@@ -37,19 +46,120 @@ use starknet_types_core::felt::Felt as StarkFelt;
 
 use crate::{
     client::State,
+    config::{
+        ETH_FEE_TOKEN, MAINNET_STARKNET_CHAINID, SEPOLIA_STARKNET_CHAINID,
+        STRK_FEE_TOKEN,
+    },
     gen::{self, blocking::Rpc},
+    hash::block::L1DaMode,
 };
 
 pub mod cache;
+pub mod constants;
 pub mod err;
 pub mod map;
+pub mod overlay;
+pub mod trace;
+pub mod tx;
 
+use cache::HasOverlay;
 use err::Error;
+use overlay::{Overlay, StateOverride};
 
-pub fn call<T: gen::client::blocking::HttpClient>(
-    client: gen::client::blocking::Client<T>,
+pub fn call<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
     function_call: gen::FunctionCall,
     state: State,
+    chain_id: &gen::ChainId,
+) -> Result<CallInfo, Error> {
+    call_with_overrides(url, http, function_call, &[], state, chain_id)
+}
+
+/// Execute the call and export its writes (storage, nonces, deployed contracts
+/// and declared classes) as a state diff.
+pub fn call_with_state_diff<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    function_call: gen::FunctionCall,
+    state: State,
+    chain_id: &gen::ChainId,
+) -> Result<(CallInfo, gen::StateDiff), Error> {
+    let block_context = block_context(&state, chain_id)?;
+
+    let state_proxy: StateProxy<T> = StateProxy::new(url, http, state);
+    let mut state_proxy = cache::CachedState::new(state_proxy);
+
+    let call_info =
+        execute_call(function_call, &mut state_proxy, &block_context)?;
+    let state_diff = state_proxy.get_overlay().state_diff()?;
+    tracing::debug!(?call_info, ?state_diff, "call completed");
+    Ok((call_info, state_diff))
+}
+
+/// Execute the call as if the contracts had the overridden values instead of
+/// the ones in the verified state of the block. With any override given, the
+/// result is not verified by the chain.
+pub fn call_with_overrides<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    function_call: gen::FunctionCall,
+    overrides: &[StateOverride],
+    state: State,
+    chain_id: &gen::ChainId,
+) -> Result<CallInfo, Error> {
+    let block_context = block_context(&state, chain_id)?;
+
+    let mut state_proxy: StateProxy<T> = StateProxy::new(url, http, state);
+    for state_override in overrides {
+        state_proxy.overlay.apply(state_override)?;
+    }
+    let mut state_proxy = cache::CachedState::new(state_proxy);
+
+    let call_info =
+        execute_call(function_call, &mut state_proxy, &block_context)?;
+    tracing::debug!(?call_info, "call completed");
+    Ok(call_info)
+}
+
+/// Execute the calls one after another against the same verified state of
+/// the block, so that the values and classes read by a call are reused by
+/// the next ones. With `chained` set, every call sees the writes of the
+/// previous successful calls, otherwise it sees the state of the block only.
+/// A failed call does not fail the others.
+pub fn call_many<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    function_calls: Vec<gen::FunctionCall>,
+    chained: bool,
+    state: State,
+    chain_id: &gen::ChainId,
+) -> Result<Vec<Result<CallInfo, Error>>, Error> {
+    let block_context = block_context(&state, chain_id)?;
+
+    let state_proxy: StateProxy<T> = StateProxy::new(url, http, state);
+    let mut state =
+        BlockifierCachedState::new(cache::CachedState::new(state_proxy));
+
+    let mut calls = Vec::with_capacity(function_calls.len());
+    for (index, function_call) in function_calls.into_iter().enumerate() {
+        let mut call_state =
+            TransactionalState::create_transactional(&mut state);
+        let call_info =
+            execute_call(function_call, &mut call_state, &block_context);
+        tracing::debug!(index, ok = call_info.is_ok(), "call_many");
+        if chained && call_info.is_ok() {
+            call_state.commit();
+        }
+        calls.push(call_info);
+    }
+    Ok(calls)
+}
+
+fn execute_call(
+    function_call: gen::FunctionCall,
+    state: &mut dyn BlockifierState,
+    block_context: &BlockContext,
 ) -> Result<CallInfo, Error> {
     let gen::FunctionCall { calldata, contract_address, entry_point_selector } =
         function_call;
@@ -61,40 +171,6 @@ pub fn call<T: gen::client::blocking::HttpClient>(
 
     let entry_point_selector: StarkFelt = entry_point_selector.try_into()?;
 
-    let one = NonZeroU128::new(1)
-        .ok_or_else(|| Error::Custom("NonZeroU128 is zero"))?;
-    let block_info = BlockInfo {
-        block_number: StarknetBlockNumber::default(),
-        block_timestamp: BlockTimestamp::default(),
-        sequencer_address: ContractAddress::default(),
-        gas_prices: GasPrices {
-            eth_l1_gas_price: one,
-            strk_l1_gas_price: one,
-            eth_l1_data_gas_price: one,
-            strk_l1_data_gas_price: one,
-        },
-        use_kzg_da: false,
-    };
-
-    let chain_info = ChainInfo {
-        chain_id: BlockifierChainId::Mainnet,
-        fee_token_addresses: FeeTokenAddresses {
-            strk_fee_token_address: ContractAddress::default(),
-            eth_fee_token_address: ContractAddress::default(),
-        },
-    };
-
-    let versioned_constants = VersionedConstants::latest_constants().to_owned();
-
-    let bouncer_config = BouncerConfig::default();
-
-    let block_context = BlockContext::new(
-        block_info,
-        chain_info,
-        versioned_constants,
-        bouncer_config,
-    );
-
     let tx_info = TransactionInfo::Deprecated(DeprecatedTransactionInfo {
         common_fields: CommonAccountFields {
             transaction_hash: TransactionHash::default(),
@@ -110,7 +186,10 @@ pub fn call<T: gen::client::blocking::HttpClient>(
         max_fee: Fee::default(),
     });
 
-    let tx_context = Arc::new(TransactionContext { block_context, tx_info });
+    let tx_context = Arc::new(TransactionContext {
+        block_context: block_context.clone(),
+        tx_info,
+    });
     let limit_steps_by_resources = false;
     let mut context = EntryPointExecutionContext::new(
         tx_context.clone(),
@@ -130,23 +209,471 @@ pub fn call<T: gen::client::blocking::HttpClient>(
         initial_gas: u64::MAX,
     };
 
-    let state_proxy: StateProxy<T> = StateProxy { client, state };
-    let mut state_proxy = cache::CachedState::new(state_proxy);
-
     let mut resources = Default::default();
-    let call_info = call_entry_point.execute(
-        &mut state_proxy,
-        &mut resources,
-        &mut context,
-    )?;
-
-    tracing::debug!(?call_info, "call completed");
+    let call_info =
+        call_entry_point.execute(state, &mut resources, &mut context)?;
     Ok(call_info)
 }
 
+/// Estimate the fees of the transactions by executing them one after another
+/// (each one on top of the effects of the previous ones) against the verified
+/// state of the block. Fees are computed but not charged, so the sender does
+/// not need the balance to cover them.
+pub fn estimate_fee<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    transactions: Vec<gen::BroadcastedTxn>,
+    simulation_flags: &[gen::SimulationFlagForEstimateFee],
+    state: State,
+    chain_id: &gen::ChainId,
+) -> Result<Vec<gen::FeeEstimate>, Error> {
+    let flags = Flags::from(simulation_flags);
+    let mut executor = Executor::new(url, http, state, chain_id, flags)?;
+
+    let mut estimates = Vec::with_capacity(transactions.len());
+    for (index, txn) in transactions.into_iter().enumerate() {
+        let (_, info, fee_estimate) = executor.execute(index, txn)?;
+        if let Some(reason) = info.revert_error {
+            return Err(Error::Reverted(index, reason));
+        }
+        estimates.push(fee_estimate);
+    }
+    Ok(estimates)
+}
+
+/// Simulate the transactions one after another against the verified state of
+/// the block, each one seeing the writes of the previous ones, and trace the
+/// calls made by every transaction. Reverted invoke transactions are traced
+/// with their revert reason.
+pub fn simulate<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    transactions: Vec<gen::BroadcastedTxn>,
+    simulation_flags: &[gen::SimulationFlag],
+    state: State,
+    chain_id: &gen::ChainId,
+) -> Result<Vec<gen::SimulatedTransaction>, Error> {
+    let flags = Flags::from(simulation_flags);
+    let mut executor = Executor::new(url, http, state, chain_id, flags)?;
+
+    let mut simulated = Vec::with_capacity(transactions.len());
+    for (index, txn) in transactions.into_iter().enumerate() {
+        let (tx, info, fee_estimate) = executor.execute(index, txn)?;
+        let tx = Transaction::AccountTransaction(tx);
+        simulated.push(gen::SimulatedTransaction {
+            fee_estimation: Some(fee_estimate),
+            transaction_trace: Some(trace::transaction_trace(
+                &tx, &info, None,
+            )?),
+        });
+    }
+    Ok(simulated)
+}
+
+/// How broadcasted transactions are executed: whether the account validates
+/// them and whether the fee is charged. Fee estimates are never charged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Flags {
+    validate: bool,
+    charge_fee: bool,
+}
+
+impl From<&[gen::SimulationFlagForEstimateFee]> for Flags {
+    fn from(flags: &[gen::SimulationFlagForEstimateFee]) -> Self {
+        Self {
+            validate: !flags.iter().any(|flag| {
+                matches!(flag, gen::SimulationFlagForEstimateFee::SkipValidate)
+            }),
+            charge_fee: false,
+        }
+    }
+}
+
+impl From<&[gen::SimulationFlag]> for Flags {
+    fn from(flags: &[gen::SimulationFlag]) -> Self {
+        Self {
+            validate: !flags
+                .iter()
+                .any(|flag| matches!(flag, gen::SimulationFlag::SkipValidate)),
+            charge_fee: !flags
+                .iter()
+                .any(|flag| matches!(flag, gen::SimulationFlag::SkipFeeCharge)),
+        }
+    }
+}
+
+/// Executes broadcasted transactions one after another against the verified
+/// state of a block, each one on top of the writes of the previous ones.
+struct Executor<T: gen::client::blocking::HttpClient> {
+    state: BlockifierCachedState<cache::CachedState<StateProxy<T>>>,
+    block_context: BlockContext,
+    chain_id: gen::ChainId,
+    flags: Flags,
+}
+
+impl<T: gen::client::blocking::HttpClient + Clone> Executor<T> {
+    fn new(
+        url: &str,
+        http: T,
+        state: State,
+        chain_id: &gen::ChainId,
+        flags: Flags,
+    ) -> Result<Self, Error> {
+        let block_context = block_context(&state, chain_id)?;
+        let state_proxy: StateProxy<T> = StateProxy::new(url, http, state);
+        Ok(Self {
+            state: BlockifierCachedState::new(cache::CachedState::new(
+                state_proxy,
+            )),
+            block_context,
+            chain_id: chain_id.clone(),
+            flags,
+        })
+    }
+
+    /// Execute the next transaction and compute its fee from the gas prices
+    /// of the block.
+    fn execute(
+        &mut self,
+        index: usize,
+        txn: gen::BroadcastedTxn,
+    ) -> Result<
+        (AccountTransaction, TransactionExecutionInfo, gen::FeeEstimate),
+        Error,
+    > {
+        let tx = tx::account_transaction(txn, &self.chain_id)?;
+        let fee_type = tx.fee_type();
+        let info = tx.execute(
+            &mut self.state,
+            &self.block_context,
+            self.flags.charge_fee,
+            self.flags.validate,
+        )?;
+        tracing::debug!(
+            index,
+            reverted = info.revert_error.is_some(),
+            receipt=?info.transaction_receipt,
+            "execute"
+        );
+        let fee_estimate = fee_estimate(
+            &info.transaction_receipt,
+            &fee_type,
+            &self.block_context,
+        )?;
+        Ok((tx, info, fee_estimate))
+    }
+}
+
+/// Re-execute the transactions of a block in order, starting from the state
+/// of its parent and in the context of the block itself, and trace each one
+/// with the state diff it produced. Tracing a prefix of the block traces its
+/// last transaction.
+pub fn trace_block<T: gen::client::blocking::HttpClient + Clone>(
+    url: &str,
+    http: T,
+    transactions: Vec<(gen::Txn, gen::Felt)>,
+    parent: State,
+    block: State,
+    chain_id: &gen::ChainId,
+) -> Result<Vec<gen::TransactionTrace>, Error> {
+    let block_context = block_context(&block, chain_id)?;
+
+    // A declare transaction refers to its class by hash only: the class is
+    // fetched as of the traced block, where it is already declared.
+    let transactions = transactions
+        .into_iter()
+        .map(|(txn, tx_hash)| {
+            let class = match &txn {
+                gen::Txn::DeclareTxn(tx) => Some(verified_class(
+                    &http,
+                    url,
+                    &block.block_hash,
+                    tx::declared_class_hash(tx),
+                )?),
+                _ => None,
+            };
+            tx::block_transaction(txn, &tx_hash, class)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let state_proxy: StateProxy<T> = StateProxy::new(url, http, parent);
+    let mut state =
+        BlockifierCachedState::new(cache::CachedState::new(state_proxy));
+
+    let mut traces = Vec::with_capacity(transactions.len());
+    for (index, tx) in transactions.iter().enumerate() {
+        let mut tx_state = TransactionalState::create_transactional(&mut state);
+        let info = tx.execute(&mut tx_state, &block_context, true, true)?;
+        tracing::debug!(
+            index,
+            reverted = info.revert_error.is_some(),
+            receipt=?info.transaction_receipt,
+            "trace"
+        );
+        let diff = tx_state.to_state_diff()?;
+        let state_diff = trace::state_diff(&diff, |address| {
+            let class_hash = tx_state.state.get_class_hash_at(*address)?;
+            Ok(class_hash == ClassHash::default())
+        })?;
+        tx_state.commit();
+        traces.push(trace::transaction_trace(tx, &info, Some(state_diff))?);
+    }
+    Ok(traces)
+}
+
+fn fee_estimate(
+    receipt: &TransactionReceipt,
+    fee_type: &FeeType,
+    block_context: &BlockContext,
+) -> Result<gen::FeeEstimate, Error> {
+    let gas_prices = &block_context.block_info().gas_prices;
+    let gas_price = gas_prices.get_gas_price_by_fee_type(fee_type).get();
+    let data_gas_price =
+        gas_prices.get_data_gas_price_by_fee_type(fee_type).get();
+    Ok(gen::FeeEstimate {
+        data_gas_consumed: Some(
+            StarkFelt::from(receipt.gas.l1_data_gas).try_into()?,
+        ),
+        data_gas_price: Some(StarkFelt::from(data_gas_price).try_into()?),
+        gas_consumed: StarkFelt::from(receipt.gas.l1_gas).try_into()?,
+        gas_price: StarkFelt::from(gas_price).try_into()?,
+        overall_fee: StarkFelt::from(receipt.fee.0).try_into()?,
+        unit: match fee_type {
+            FeeType::Eth => gen::PriceUnit::Wei,
+            FeeType::Strk => gen::PriceUnit::Fri,
+        },
+    })
+}
+
+/// Context of the block the call is executed at: its number, timestamp,
+/// sequencer and gas prices, and the chain with its fee tokens.
+fn block_context(
+    state: &State,
+    chain_id: &gen::ChainId,
+) -> Result<BlockContext, Error> {
+    let block_info = BlockInfo {
+        block_number: StarknetBlockNumber(state.block_number),
+        block_timestamp: BlockTimestamp(state.timestamp),
+        sequencer_address: ContractAddress(state.sequencer_address.try_into()?),
+        gas_prices: GasPrices {
+            eth_l1_gas_price: gas_price(&state.l1_gas_price.price_in_wei)?,
+            strk_l1_gas_price: gas_price(&state.l1_gas_price.price_in_fri)?,
+            eth_l1_data_gas_price: gas_price(
+                &state.l1_data_gas_price.price_in_wei,
+            )?,
+            strk_l1_data_gas_price: gas_price(
+                &state.l1_data_gas_price.price_in_fri,
+            )?,
+        },
+        use_kzg_da: state.l1_da_mode == L1DaMode::Blob,
+    };
+
+    let chain_info = ChainInfo {
+        chain_id: match chain_id.as_ref().as_str() {
+            MAINNET_STARKNET_CHAINID => BlockifierChainId::Mainnet,
+            SEPOLIA_STARKNET_CHAINID => BlockifierChainId::Sepolia,
+            other => BlockifierChainId::Other(chain_name(other)),
+        },
+        fee_token_addresses: FeeTokenAddresses {
+            strk_fee_token_address: ContractAddress(
+                StarkFelt::from_hex_unchecked(STRK_FEE_TOKEN).try_into()?,
+            ),
+            eth_fee_token_address: ContractAddress(
+                StarkFelt::from_hex_unchecked(ETH_FEE_TOKEN).try_into()?,
+            ),
+        },
+    };
+
+    let versioned_constants =
+        constants::get(&state.starknet_version).to_owned();
+
+    let bouncer_config = BouncerConfig::default();
+
+    Ok(BlockContext::new(
+        block_info,
+        chain_info,
+        versioned_constants,
+        bouncer_config,
+    ))
+}
+
+// Blockifier requires non-zero gas prices, and the oldest blocks have none
+fn gas_price(price: &StarkFelt) -> Result<NonZeroU128, Error> {
+    let one = NonZeroU128::new(1)
+        .ok_or_else(|| Error::Custom("NonZeroU128 is zero"))?;
+    Ok(NonZeroU128::new(as_u128(price)?).unwrap_or(one))
+}
+
+fn as_u128(felt: &StarkFelt) -> Result<u128, Error> {
+    let bytes = felt.to_bytes_be();
+    let (high, low) = bytes.split_at(16);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(Error::Custom("value out of u128 range"));
+    }
+    let low: [u8; 16] =
+        low.try_into().map_err(|_| Error::Custom("value out of u128 range"))?;
+    Ok(u128::from_be_bytes(low))
+}
+
+// Chain id is the ASCII name of the chain encoded as a felt
+fn chain_name(chain_id: &str) -> String {
+    let hex = chain_id.trim_start_matches("0x");
+    let hex =
+        if hex.len() % 2 == 1 { format!("0{hex}") } else { hex.to_owned() };
+    match hex::decode(&hex) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => chain_id.to_owned(),
+    }
+}
+
+// Starknet RPC error of a class that is not declared
+const CLASS_HASH_NOT_FOUND: i64 = 28;
+
+// JSON-RPC error of a method that the provider does not serve
+const METHOD_NOT_FOUND: i64 = -32601;
+
+// Fetch the class as of the given block and check that it hashes to the
+// requested class hash. The class is hashed as received, before it is parsed.
+fn verified_class<T: gen::client::blocking::HttpClient>(
+    http: &T,
+    url: &str,
+    block_hash: &gen::Felt,
+    class_hash: &gen::Felt,
+) -> Result<gen::GetClassResult, Error> {
+    let block_id = gen::BlockId::BlockHash {
+        block_hash: gen::BlockHash(block_hash.clone()),
+    };
+    let class = match crate::client::request_blocking(
+        http,
+        url,
+        "starknet_getClass",
+        (block_id, class_hash),
+    ) {
+        Ok(class) => class,
+        Err(e) if e.code == CLASS_HASH_NOT_FOUND => {
+            let class_hash = ClassHash(class_hash.clone().try_into()?);
+            return Err(StateError::UndeclaredClassHash(class_hash).into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let ret = crate::hash::class::verified(class, class_hash).map_err(|e| {
+        StateError::StateReadError(format!(
+            "Failed to verify class hash: {e:?}"
+        ))
+    })?;
+    Ok(ret)
+}
+
+/// Verified state of a block, with the writes of the execution kept in an
+/// overlay on top of it.
 struct StateProxy<T: gen::client::blocking::HttpClient> {
     client: gen::client::blocking::Client<T>,
+    // Classes are fetched as raw JSON, to be hashed before they are parsed
+    http: T,
     state: State,
+    overlay: Overlay,
+}
+
+impl<T: gen::client::blocking::HttpClient> StateProxy<T> {
+    fn new(url: &str, http: T, state: State) -> Self
+    where
+        T: Clone,
+    {
+        let client = gen::client::blocking::Client::new(url, http.clone());
+        Self { client, http, state, overlay: Overlay::default() }
+    }
+
+    // Class proof of the block, with the class commitment it must match
+    // (checked against the state root), or `None` if the provider does not
+    // serve class proofs.
+    fn class_proof(
+        &self,
+        class_hash: &gen::Felt,
+    ) -> Result<Option<(gen::GetClassProofResult, gen::Felt)>, Error> {
+        let block_id = gen::BlockId::BlockHash {
+            block_hash: gen::BlockHash(self.state.block_hash.clone()),
+        };
+        let class_proof = match self
+            .client
+            .getClassProof(block_id.clone(), class_hash.clone())
+        {
+            Ok(class_proof) => class_proof,
+            Err(error) if error.code == METHOD_NOT_FOUND => {
+                tracing::warn!(
+                    ?class_hash,
+                    ?error,
+                    "class proof not available"
+                );
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        // Any contract proof commits to the classes of the block
+        let address = gen::Address(gen::Felt::try_new("0x1")?);
+        let proof = self.client.getProof(block_id, address, vec![])?;
+        let class_commitment =
+            proof.verify_class_commitment(self.state.root.clone())?;
+        Ok(Some((class_proof, class_commitment)))
+    }
+
+    // Compiled class hash claimed for the class by the state update of the
+    // block that declared it, found by bisecting the blocks up to this one
+    // over the class trie. The claim is to be proven against the class leaf.
+    fn declared_compiled_class_hash(
+        &self,
+        class_hash: &gen::Felt,
+    ) -> Result<gen::Felt, Error> {
+        let block_id = |block_number: u64| -> Result<gen::BlockId, Error> {
+            Ok(gen::BlockId::BlockNumber {
+                block_number: gen::BlockNumber::try_new(block_number as i64)?,
+            })
+        };
+        let has_leaf = |block_number: u64| -> Result<bool, Error> {
+            // A block before the class trie has no proof to serve
+            let Ok(class_proof) = self
+                .client
+                .getClassProof(block_id(block_number)?, class_hash.clone())
+            else {
+                return Ok(false);
+            };
+            let class_commitment = class_proof.class_commitment.clone();
+            Ok(class_proof
+                .verify_absent(class_commitment, class_hash.clone())
+                .is_err())
+        };
+
+        // The class has a leaf in the trie of this block
+        let (mut lo, mut hi) = (0, self.state.block_number);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if has_leaf(mid)? {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        tracing::info!(?class_hash, block_number = hi, "declared");
+
+        let gen::GetStateUpdateResult::StateUpdate(state_update) =
+            self.client.getStateUpdate(block_id(hi)?)?
+        else {
+            return Err(Error::Custom("pending state update"));
+        };
+        state_update
+            .state_diff
+            .declared_classes
+            .into_iter()
+            .find(|declared| {
+                declared
+                    .class_hash
+                    .as_ref()
+                    .is_some_and(|hash| hash.as_ref() == class_hash.as_ref())
+            })
+            .and_then(|declared| declared.compiled_class_hash)
+            .ok_or(Error::Custom("class not declared in the state update"))
+    }
 }
 
 impl<T: gen::client::blocking::HttpClient> cache::HasBlockHash
@@ -157,6 +684,12 @@ impl<T: gen::client::blocking::HttpClient> cache::HasBlockHash
     }
 }
 
+impl<T: gen::client::blocking::HttpClient> HasOverlay for StateProxy<T> {
+    fn get_overlay(&self) -> &Overlay {
+        &self.overlay
+    }
+}
+
 impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
     fn get_storage_at(
         &self,
@@ -164,6 +697,11 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
         storage_key: StarknetStorageKey,
     ) -> StateResult<StarkFelt> {
         tracing::info!(?contract_address, ?storage_key, "get_storage_at");
+        if let Some(value) =
+            self.overlay.get_storage_at(contract_address, storage_key)
+        {
+            return Ok(value);
+        }
 
         let felt: gen::Felt = contract_address.0.key().try_into()?;
         let address = gen::Address(felt);
@@ -181,11 +719,6 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
             .map_err(Into::<Error>::into)?;
         tracing::info!(?address, ?key, value=?ret, "get_storage_at");
 
-        if ret.as_ref() == "0x0" {
-            tracing::info!("get_storage_at: skipping proof for zero value");
-            return Ok(ret.try_into()?);
-        }
-
         let proof = self
             .client
             .getProof(block_id, address.clone(), vec![key.clone()])
@@ -209,20 +742,39 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
         contract_address: ContractAddress,
     ) -> StateResult<Nonce> {
         tracing::info!(?contract_address, "get_nonce_at");
+        if let Some(nonce) = self.overlay.get_nonce_at(contract_address) {
+            return Ok(nonce);
+        }
 
         let block_id = gen::BlockId::BlockHash {
             block_hash: gen::BlockHash(self.state.block_hash.clone()),
         };
 
         let felt: gen::Felt = contract_address.0.key().try_into()?;
-        let contract_address = gen::Address(felt);
+        let address = gen::Address(felt);
 
-        let ret = self
+        let proof = self
             .client
-            .getNonce(block_id, contract_address)
+            .getProof(block_id, address.clone(), vec![])
             .map_err(Into::<Error>::into)?;
+        tracing::info!("get_nonce_at: proof received");
+
+        let global_root = self.state.root.clone();
+        let contract_data =
+            proof.verify_contract(global_root, address).map_err(|e| {
+                StateError::StateReadError(format!(
+                    "Failed to verify merkle proof: {e:?}"
+                ))
+            })?;
+        let Some(contract_data) = contract_data else {
+            // Nothing is deployed at the address yet
+            tracing::info!("get_nonce_at: proven undeployed");
+            return Ok(Nonce::default());
+        };
+        let nonce = contract_data.nonce.clone();
+        tracing::info!(?nonce, "get_nonce_at: proof verified");
 
-        Ok(Nonce(ret.try_into()?))
+        Ok(Nonce(nonce.try_into()?))
     }
 
     fn get_class_hash_at(
@@ -230,20 +782,41 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
         contract_address: ContractAddress,
     ) -> StateResult<ClassHash> {
         tracing::info!(?contract_address, "get_class_hash_at");
+        if let Some(class_hash) =
+            self.overlay.get_class_hash_at(contract_address)
+        {
+            return Ok(class_hash);
+        }
 
         let block_id = gen::BlockId::BlockHash {
             block_hash: gen::BlockHash(self.state.block_hash.clone()),
         };
 
         let felt: gen::Felt = contract_address.0.key().try_into()?;
-        let contract_address = gen::Address(felt);
+        let address = gen::Address(felt);
 
-        let ret = self
+        let proof = self
             .client
-            .getClassHashAt(block_id, contract_address)
+            .getProof(block_id, address.clone(), vec![])
             .map_err(Into::<Error>::into)?;
+        tracing::info!("get_class_hash_at: proof received");
+
+        let global_root = self.state.root.clone();
+        let contract_data =
+            proof.verify_contract(global_root, address).map_err(|e| {
+                StateError::StateReadError(format!(
+                    "Failed to verify merkle proof: {e:?}"
+                ))
+            })?;
+        let Some(contract_data) = contract_data else {
+            // Nothing is deployed at the address yet
+            tracing::info!("get_class_hash_at: proven undeployed");
+            return Ok(ClassHash::default());
+        };
+        let class_hash = contract_data.class_hash.clone();
+        tracing::info!(?class_hash, "get_class_hash_at: proof verified");
 
-        Ok(ClassHash(ret.try_into()?))
+        Ok(ClassHash(class_hash.try_into()?))
     }
 
     fn get_compiled_contract_class(
@@ -251,19 +824,32 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
         class_hash: ClassHash,
     ) -> StateResult<ContractClass> {
         tracing::info!(?class_hash, "get_compiled_contract_class");
-
-        let block_id = gen::BlockId::BlockHash {
-            block_hash: gen::BlockHash(self.state.block_hash.clone()),
-        };
+        if let Some(class) =
+            self.overlay.get_compiled_contract_class(class_hash)
+        {
+            return Ok(class);
+        }
 
         let class_hash: gen::Felt = class_hash.0.try_into()?;
-
-        let ret = self
-            .client
-            .getClass(block_id, class_hash)
-            .map_err(Into::<Error>::into)?;
-
-        Ok(ret.try_into()?)
+        let ret = verified_class(
+            &self.http,
+            &self.client.url,
+            &self.state.block_hash,
+            &class_hash,
+        )?;
+        tracing::info!(?class_hash, "get_compiled_contract_class: verified");
+
+        let class = match ret {
+            gen::GetClassResult::ContractClass(class) => {
+                let casm = cache::compile_sierra(&class_hash, &class)?;
+                ContractClass::V1(
+                    casm.try_into()
+                        .map_err(|e| Error::Program(format!("{e}")))?,
+                )
+            }
+            ret => ret.try_into()?,
+        };
+        Ok(class)
     }
 
     fn get_compiled_class_hash(
@@ -271,7 +857,49 @@ impl<T: gen::client::blocking::HttpClient> StateReader for StateProxy<T> {
         class_hash: ClassHash,
     ) -> StateResult<CompiledClassHash> {
         tracing::info!(?class_hash, "get_compiled_class_hash");
-        Err(StateError::UndeclaredClassHash(class_hash))
+        if let Some(compiled_class_hash) =
+            self.overlay.get_compiled_class_hash(class_hash)
+        {
+            return Ok(compiled_class_hash);
+        }
+
+        let class_hash: gen::Felt = class_hash.0.try_into()?;
+        let Some((class_proof, class_commitment)) =
+            self.class_proof(&class_hash)?
+        else {
+            // A compiled class hash is only known from a class proof: the
+            // one of a local compilation depends on the compiler version
+            return Err(StateError::StateReadError(format!(
+                "Compiled class hash cannot be verified without class proofs: {class_hash:?}"
+            )));
+        };
+
+        if class_proof
+            .verify_absent(class_commitment.clone(), class_hash.clone())
+            .is_ok()
+        {
+            // Undeclared or Cairo 0 class: the trie has no leaf for it
+            tracing::info!(?class_hash, "get_compiled_class_hash: no leaf");
+            return Ok(CompiledClassHash::default());
+        }
+
+        // The leaf commits to a hash of the value, so the value to prove is
+        // the one the chain claims for the class
+        let compiled_class_hash =
+            self.declared_compiled_class_hash(&class_hash)?;
+        class_proof
+            .verify(class_commitment, class_hash, compiled_class_hash.clone())
+            .map_err(|e| {
+                StateError::StateReadError(format!(
+                    "Failed to verify compiled class hash: {e:?}"
+                ))
+            })?;
+        tracing::info!(
+            ?compiled_class_hash,
+            "get_compiled_class_hash: proof verified"
+        );
+        let compiled_class_hash: StarkFelt = compiled_class_hash.try_into()?;
+        Ok(CompiledClassHash(compiled_class_hash))
     }
 }
 
@@ -283,6 +911,7 @@ impl<T: gen::client::blocking::HttpClient> BlockifierState for StateProxy<T> {
         value: StarkFelt,
     ) -> StateResult<()> {
         tracing::info!(?contract_address, ?key, ?value, "set_storage_at");
+        self.overlay.set_storage_at(contract_address, key, value);
         Ok(())
     }
 
@@ -291,6 +920,8 @@ impl<T: gen::client::blocking::HttpClient> BlockifierState for StateProxy<T> {
         contract_address: ContractAddress,
     ) -> StateResult<()> {
         tracing::info!(?contract_address, "increment_nonce");
+        let nonce = self.get_nonce_at(contract_address)?.try_increment()?;
+        self.overlay.set_nonce_at(contract_address, nonce);
         Ok(())
     }
 
@@ -300,6 +931,8 @@ impl<T: gen::client::blocking::HttpClient> BlockifierState for StateProxy<T> {
         class_hash: ClassHash,
     ) -> StateResult<()> {
         tracing::info!(?contract_address, ?class_hash, "set_class_hash_at");
+        let previous = self.get_class_hash_at(contract_address)?;
+        self.overlay.set_class_hash_at(contract_address, class_hash, previous);
         Ok(())
     }
 
@@ -309,6 +942,7 @@ impl<T: gen::client::blocking::HttpClient> BlockifierState for StateProxy<T> {
         contract_class: ContractClass,
     ) -> StateResult<()> {
         tracing::info!(?class_hash, ?contract_class, "set_contract_class");
+        self.overlay.set_contract_class(class_hash, contract_class);
         Ok(())
     }
 
@@ -322,6 +956,7 @@ impl<T: gen::client::blocking::HttpClient> BlockifierState for StateProxy<T> {
             ?compiled_class_hash,
             "set_compiled_class_hash"
         );
+        self.overlay.set_compiled_class_hash(class_hash, compiled_class_hash);
         Ok(())
     }
 
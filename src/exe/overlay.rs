@@ -0,0 +1,267 @@
+use std::collections::{HashMap, HashSet};
+
+use blockifier::{
+    execution::contract_class::ContractClass, state::cached_state::StateMaps,
+};
+use serde::Deserialize;
+use starknet_api::{
+    core::{ClassHash, CompiledClassHash, ContractAddress, Nonce},
+    state::StorageKey,
+};
+use starknet_types_core::felt::Felt as StarkFelt;
+
+use super::{err::Error, trace};
+use crate::gen;
+
+/// Values of a contract to be read instead of the ones in the state of the
+/// block (e.g. to see what a view function returns for another balance). A
+/// call made with overrides is not verified against the chain.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StateOverride {
+    pub contract_address: gen::Address,
+    #[serde(default)]
+    pub nonce: Option<gen::Felt>,
+    #[serde(default)]
+    pub class_hash: Option<gen::Felt>,
+    #[serde(default)]
+    pub storage: Vec<StorageOverride>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct StorageOverride {
+    pub key: gen::StorageKey,
+    pub value: gen::Felt,
+}
+
+/// Writes made on top of the verified state of a block. Values written here
+/// take precedence over the block state on subsequent reads, and all writes
+/// together can be exported as a state diff.
+#[derive(Debug, Default)]
+pub struct Overlay {
+    writes: StateMaps,
+    classes: HashMap<ClassHash, ContractClass>,
+    // Contracts that had no class in the block state before the write
+    deployed: HashSet<ContractAddress>,
+}
+
+impl Overlay {
+    pub fn is_empty(&self) -> bool {
+        self.writes == StateMaps::default()
+    }
+
+    pub fn get_storage_at(
+        &self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+    ) -> Option<StarkFelt> {
+        self.writes.storage.get(&(contract_address, key)).copied()
+    }
+
+    pub fn get_nonce_at(
+        &self,
+        contract_address: ContractAddress,
+    ) -> Option<Nonce> {
+        self.writes.nonces.get(&contract_address).copied()
+    }
+
+    pub fn get_class_hash_at(
+        &self,
+        contract_address: ContractAddress,
+    ) -> Option<ClassHash> {
+        self.writes.class_hashes.get(&contract_address).copied()
+    }
+
+    pub fn get_compiled_contract_class(
+        &self,
+        class_hash: ClassHash,
+    ) -> Option<ContractClass> {
+        self.classes.get(&class_hash).cloned()
+    }
+
+    pub fn get_compiled_class_hash(
+        &self,
+        class_hash: ClassHash,
+    ) -> Option<CompiledClassHash> {
+        self.writes.compiled_class_hashes.get(&class_hash).copied()
+    }
+
+    pub fn set_storage_at(
+        &mut self,
+        contract_address: ContractAddress,
+        key: StorageKey,
+        value: StarkFelt,
+    ) {
+        self.writes.storage.insert((contract_address, key), value);
+    }
+
+    pub fn set_nonce_at(
+        &mut self,
+        contract_address: ContractAddress,
+        nonce: Nonce,
+    ) {
+        self.writes.nonces.insert(contract_address, nonce);
+    }
+
+    /// Set the class of the contract, given the class it had so far (the
+    /// default class hash for a contract that is being deployed).
+    pub fn set_class_hash_at(
+        &mut self,
+        contract_address: ContractAddress,
+        class_hash: ClassHash,
+        previous: ClassHash,
+    ) {
+        if previous == ClassHash::default() {
+            self.deployed.insert(contract_address);
+        }
+        self.writes.class_hashes.insert(contract_address, class_hash);
+    }
+
+    pub fn set_contract_class(
+        &mut self,
+        class_hash: ClassHash,
+        contract_class: ContractClass,
+    ) {
+        self.writes.declared_contracts.insert(class_hash, true);
+        self.classes.insert(class_hash, contract_class);
+    }
+
+    pub fn set_compiled_class_hash(
+        &mut self,
+        class_hash: ClassHash,
+        compiled_class_hash: CompiledClassHash,
+    ) {
+        self.writes
+            .compiled_class_hashes
+            .insert(class_hash, compiled_class_hash);
+    }
+
+    /// Put the overridden values of the contract in place of the ones in the
+    /// state of the block.
+    pub fn apply(
+        &mut self,
+        state_override: &StateOverride,
+    ) -> Result<(), Error> {
+        let felt = |felt: &gen::Felt| -> Result<StarkFelt, Error> {
+            StarkFelt::from_hex(felt.as_ref())
+                .map_err(|_| Error::Custom("invalid felt"))
+        };
+        let contract_address = ContractAddress(
+            felt(&state_override.contract_address.0)?.try_into()?,
+        );
+        if let Some(nonce) = state_override.nonce.as_ref() {
+            self.set_nonce_at(contract_address, Nonce(felt(nonce)?));
+        }
+        if let Some(class_hash) = state_override.class_hash.as_ref() {
+            self.writes
+                .class_hashes
+                .insert(contract_address, ClassHash(felt(class_hash)?));
+        }
+        for StorageOverride { key, value } in &state_override.storage {
+            let key = StarkFelt::from_hex(key.as_ref())
+                .map_err(|_| Error::Custom("invalid storage key"))?;
+            self.set_storage_at(
+                contract_address,
+                StorageKey(key.try_into()?),
+                felt(value)?,
+            );
+        }
+        Ok(())
+    }
+
+    /// All writes so far as a state diff.
+    pub fn state_diff(&self) -> Result<gen::StateDiff, Error> {
+        trace::state_diff(&self.writes, |contract_address| {
+            Ok(self.deployed.contains(contract_address))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(address: u64) -> ContractAddress {
+        ContractAddress(StarkFelt::from(address).try_into().unwrap())
+    }
+
+    fn key(key: u64) -> StorageKey {
+        StorageKey(StarkFelt::from(key).try_into().unwrap())
+    }
+
+    #[test]
+    fn test_overlay_state_diff() {
+        let mut overlay = Overlay::default();
+        assert!(overlay.is_empty());
+
+        overlay.set_storage_at(contract(0x1), key(0x2), StarkFelt::ONE);
+        overlay.set_storage_at(contract(0x1), key(0x2), StarkFelt::TWO);
+        overlay.set_nonce_at(contract(0x1), Nonce(StarkFelt::THREE));
+        let class_hash = ClassHash(StarkFelt::from(0xcu64));
+        overlay.set_class_hash_at(
+            contract(0x3),
+            class_hash,
+            ClassHash::default(),
+        );
+        overlay.set_class_hash_at(
+            contract(0x4),
+            class_hash,
+            ClassHash(StarkFelt::ONE),
+        );
+        assert!(!overlay.is_empty());
+
+        // The latest write wins
+        assert_eq!(
+            overlay.get_storage_at(contract(0x1), key(0x2)),
+            Some(StarkFelt::TWO)
+        );
+        assert_eq!(overlay.get_storage_at(contract(0x1), key(0x3)), None);
+        assert_eq!(
+            overlay.get_nonce_at(contract(0x1)),
+            Some(Nonce(StarkFelt::THREE))
+        );
+        assert_eq!(overlay.get_class_hash_at(contract(0x3)), Some(class_hash));
+        assert_eq!(overlay.get_class_hash_at(contract(0x1)), None);
+
+        let diff = overlay.state_diff().unwrap();
+        assert_eq!(diff.storage_diffs.len(), 1);
+        assert_eq!(diff.storage_diffs[0].address.as_ref(), "0x1");
+        let entry = &diff.storage_diffs[0].storage_entries[0];
+        assert_eq!(entry.value.as_ref().unwrap().as_ref(), "0x2");
+        assert_eq!(diff.nonces.len(), 1);
+        assert_eq!(diff.deployed_contracts.len(), 1);
+        assert_eq!(diff.deployed_contracts[0].address.as_ref(), "0x3");
+        assert_eq!(diff.replaced_classes.len(), 1);
+        assert_eq!(
+            diff.replaced_classes[0]
+                .contract_address
+                .as_ref()
+                .unwrap()
+                .0
+                .as_ref(),
+            "0x4"
+        );
+    }
+
+    #[test]
+    fn test_state_override() {
+        let state_override: StateOverride =
+            serde_json::from_value(serde_json::json!({
+                "contract_address": "0x1",
+                "nonce": "0x5",
+                "storage": [{ "key": "0x2", "value": "0x3" }]
+            }))
+            .unwrap();
+
+        let mut overlay = Overlay::default();
+        overlay.apply(&state_override).unwrap();
+        assert_eq!(
+            overlay.get_nonce_at(contract(0x1)),
+            Some(Nonce(StarkFelt::from(5u64)))
+        );
+        assert_eq!(
+            overlay.get_storage_at(contract(0x1), key(0x2)),
+            Some(StarkFelt::THREE)
+        );
+        assert_eq!(overlay.get_class_hash_at(contract(0x1)), None);
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::BTreeMap;
+
+use blockifier::{
+    execution::{call_info::CallInfo, entry_point::CallType},
+    state::cached_state::StateMaps,
+    transaction::{
+        account_transaction::AccountTransaction,
+        objects::TransactionExecutionInfo, transaction_execution::Transaction,
+    },
+};
+use cairo_vm::{
+    types::builtin_name::BuiltinName,
+    vm::runners::cairo_runner::ExecutionResources,
+};
+use starknet_api::{
+    core::ContractAddress, deprecated_contract_class::EntryPointType,
+};
+use starknet_types_core::felt::Felt as StarkFelt;
+
+use super::err::Error;
+use crate::gen;
+
+/// Trace of an executed transaction: the validation, execution and fee
+/// transfer invocations with their nested calls, events and messages, and
+/// the state diff of the transaction (when it was recorded).
+pub fn transaction_trace(
+    tx: &Transaction,
+    info: &TransactionExecutionInfo,
+    state_diff: Option<gen::StateDiff>,
+) -> Result<gen::TransactionTrace, Error> {
+    let validate_invocation = info
+        .validate_call_info
+        .as_ref()
+        .map(function_invocation)
+        .transpose()?;
+    let fee_transfer_invocation = info
+        .fee_transfer_call_info
+        .as_ref()
+        .map(function_invocation)
+        .transpose()?;
+    let execution_resources = gen::ExecutionResources {
+        computation_resources: computation_resources(
+            &info.transaction_receipt.resources.vm_resources,
+        ),
+        data_availability: Some(gen::ExecutionResourcesDataAvailability {
+            l1_gas: info.transaction_receipt.da_gas.l1_gas as i64,
+            l1_data_gas: info.transaction_receipt.da_gas.l1_data_gas as i64,
+        }),
+    };
+
+    let tx = match tx {
+        Transaction::AccountTransaction(tx) => tx,
+        Transaction::L1HandlerTransaction(_) => {
+            let function_invocation = info
+                .execute_call_info
+                .as_ref()
+                .map(function_invocation)
+                .transpose()?
+                .ok_or(Error::Custom("missing l1 handler invocation"))?;
+            return Ok(gen::TransactionTrace::L1HandlerTxnTrace(
+                gen::L1HandlerTxnTrace {
+                    execution_resources,
+                    function_invocation,
+                    r#type: gen::L1HandlerTxnTraceType::L1Handler,
+                    state_diff,
+                },
+            ));
+        }
+    };
+
+    Ok(match tx {
+        AccountTransaction::Invoke(_) => {
+            let execute_invocation = match (
+                info.revert_error.as_ref(),
+                info.execute_call_info.as_ref(),
+            ) {
+                (Some(revert_reason), _) => {
+                    gen::InvokeTxnTraceExecuteInvocation::RevertReason {
+                        revert_reason: revert_reason.clone(),
+                    }
+                }
+                (None, Some(call_info)) => {
+                    gen::InvokeTxnTraceExecuteInvocation::FunctionInvocation(
+                        function_invocation(call_info)?,
+                    )
+                }
+                (None, None) => {
+                    return Err(Error::Custom("missing execute invocation"))
+                }
+            };
+            gen::TransactionTrace::InvokeTxnTrace(gen::InvokeTxnTrace {
+                execute_invocation,
+                execution_resources,
+                fee_transfer_invocation,
+                r#type: gen::InvokeTxnTraceType::Invoke,
+                state_diff,
+                validate_invocation,
+            })
+        }
+        AccountTransaction::Declare(_) => {
+            gen::TransactionTrace::DeclareTxnTrace(gen::DeclareTxnTrace {
+                execution_resources,
+                fee_transfer_invocation,
+                r#type: gen::DeclareTxnTraceType::Declare,
+                state_diff,
+                validate_invocation,
+            })
+        }
+        AccountTransaction::DeployAccount(_) => {
+            let constructor_invocation = info
+                .execute_call_info
+                .as_ref()
+                .map(function_invocation)
+                .transpose()?
+                .ok_or(Error::Custom("missing constructor invocation"))?;
+            gen::TransactionTrace::DeployAccountTxnTrace(
+                gen::DeployAccountTxnTrace {
+                    constructor_invocation,
+                    execution_resources,
+                    fee_transfer_invocation,
+                    r#type: gen::DeployAccountTxnTraceType::DeployAccount,
+                    state_diff,
+                    validate_invocation,
+                },
+            )
+        }
+    })
+}
+
+pub fn function_invocation(
+    call_info: &CallInfo,
+) -> Result<gen::FunctionInvocation, Error> {
+    let call = &call_info.call;
+    let contract_address = address(&call.storage_address)?;
+
+    let events = call_info
+        .execution
+        .events
+        .iter()
+        .map(|ordered| {
+            Ok(gen::OrderedEvent {
+                order: Some(ordered.order as i64),
+                event: gen::Event {
+                    from_address: gen::Address(contract_address.clone()),
+                    event_content: gen::EventContent {
+                        keys: felts(
+                            ordered.event.keys.iter().map(|key| key.0),
+                        )?,
+                        data: felts(ordered.event.data.0.iter().copied())?,
+                    },
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let messages = call_info
+        .execution
+        .l2_to_l1_messages
+        .iter()
+        .map(|ordered| {
+            Ok(gen::OrderedMessage {
+                order: Some(ordered.order as i64),
+                msg_to_l1: gen::MsgToL1 {
+                    from_address: contract_address.clone(),
+                    payload: felts(ordered.message.payload.0.iter().copied())?,
+                    to_address: StarkFelt::from(ordered.message.to_address)
+                        .try_into()?,
+                },
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let calls = call_info
+        .inner_calls
+        .iter()
+        .map(|inner| function_invocation(inner).map(gen::NestedCall))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(gen::FunctionInvocation {
+        function_call: gen::FunctionCall {
+            calldata: felts(call.calldata.0.iter().copied())?,
+            contract_address: gen::Address(contract_address),
+            entry_point_selector: call.entry_point_selector.0.try_into()?,
+        },
+        call_type: match call.call_type {
+            CallType::Call => gen::CallType::Call,
+            CallType::Delegate => gen::CallType::LibraryCall,
+        },
+        caller_address: address(&call.caller_address)?,
+        calls,
+        class_hash: call
+            .class_hash
+            .ok_or(Error::Custom("call without class hash"))?
+            .0
+            .try_into()?,
+        entry_point_type: match call.entry_point_type {
+            EntryPointType::Constructor => gen::EntryPointType::Constructor,
+            EntryPointType::External => gen::EntryPointType::External,
+            EntryPointType::L1Handler => gen::EntryPointType::L1Handler,
+        },
+        events,
+        execution_resources: computation_resources(&call_info.resources),
+        messages,
+        result: felts(call_info.execution.retdata.0.iter().copied())?,
+    })
+}
+
+/// State diff in the shape of the spec, built from the writes recorded by
+/// blockifier. Contracts that got a class are split into deployed ones (that
+/// had no class before) and the ones whose class was replaced.
+pub fn state_diff(
+    diff: &StateMaps,
+    is_deployed: impl Fn(&ContractAddress) -> Result<bool, Error>,
+) -> Result<gen::StateDiff, Error> {
+    let mut storage = BTreeMap::<_, Vec<_>>::new();
+    for ((address, key), value) in &diff.storage {
+        storage.entry(*address).or_default().push((*key.0.key(), *value));
+    }
+    let storage_diffs = storage
+        .into_iter()
+        .map(|(address, mut entries)| {
+            entries.sort();
+            Ok(gen::ContractStorageDiffItem {
+                address: self::address(&address)?,
+                storage_entries: entries
+                    .into_iter()
+                    .map(|(key, value)| {
+                        Ok(gen::StorageDiffItem {
+                            key: Some(key.try_into()?),
+                            value: Some(value.try_into()?),
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?,
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut nonces = diff.nonces.iter().collect::<Vec<_>>();
+    nonces.sort();
+    let nonces = nonces
+        .into_iter()
+        .map(|(address, nonce)| {
+            Ok(gen::NonceUpdate {
+                contract_address: Some(gen::Address(self::address(address)?)),
+                nonce: Some(nonce.0.try_into()?),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut class_hashes = diff.class_hashes.iter().collect::<Vec<_>>();
+    class_hashes.sort();
+    let mut deployed_contracts = Vec::new();
+    let mut replaced_classes = Vec::new();
+    for (address, class_hash) in class_hashes {
+        if is_deployed(address)? {
+            deployed_contracts.push(gen::DeployedContractItem {
+                address: self::address(address)?,
+                class_hash: class_hash.0.try_into()?,
+            });
+        } else {
+            replaced_classes.push(gen::ReplacedClass {
+                class_hash: Some(class_hash.0.try_into()?),
+                contract_address: Some(gen::Address(self::address(address)?)),
+            });
+        }
+    }
+
+    let mut compiled = diff.compiled_class_hashes.iter().collect::<Vec<_>>();
+    compiled.sort();
+    let declared_classes = compiled
+        .into_iter()
+        .map(|(class_hash, compiled_class_hash)| {
+            Ok(gen::NewClasses {
+                class_hash: Some(class_hash.0.try_into()?),
+                compiled_class_hash: Some(compiled_class_hash.0.try_into()?),
+            })
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // Cairo 0 classes are declared without a compiled class hash
+    let mut deprecated = diff
+        .declared_contracts
+        .iter()
+        .filter(|(class_hash, declared)| {
+            **declared && !diff.compiled_class_hashes.contains_key(class_hash)
+        })
+        .map(|(class_hash, _)| *class_hash)
+        .collect::<Vec<_>>();
+    deprecated.sort();
+    let deprecated_declared_classes = deprecated
+        .into_iter()
+        .map(|class_hash| class_hash.0.try_into())
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(gen::StateDiff {
+        declared_classes,
+        deployed_contracts,
+        deprecated_declared_classes,
+        nonces,
+        replaced_classes,
+        storage_diffs,
+    })
+}
+
+fn computation_resources(
+    resources: &ExecutionResources,
+) -> gen::ComputationResources {
+    let builtin = |name: BuiltinName| {
+        resources.builtin_instance_counter.get(&name).map(|n| *n as i64)
+    };
+    gen::ComputationResources {
+        bitwise_builtin_applications: builtin(BuiltinName::bitwise),
+        ec_op_builtin_applications: builtin(BuiltinName::ec_op),
+        ecdsa_builtin_applications: builtin(BuiltinName::ecdsa),
+        keccak_builtin_applications: builtin(BuiltinName::keccak),
+        memory_holes: Some(resources.n_memory_holes as i64),
+        pedersen_builtin_applications: builtin(BuiltinName::pedersen),
+        poseidon_builtin_applications: builtin(BuiltinName::poseidon),
+        range_check_builtin_applications: builtin(BuiltinName::range_check),
+        segment_arena_builtin: builtin(BuiltinName::segment_arena),
+        steps: resources.n_steps as i64,
+    }
+}
+
+fn address(address: &ContractAddress) -> Result<gen::Felt, Error> {
+    address.0.key().try_into()
+}
+
+fn felts(
+    felts: impl Iterator<Item = StarkFelt>,
+) -> Result<Vec<gen::Felt>, Error> {
+    felts.map(gen::Felt::try_from).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use blockifier::execution::{
+        call_info::{CallExecution, OrderedEvent, Retdata},
+        entry_point::CallEntryPoint,
+    };
+    use starknet_api::{
+        core::{ClassHash, Nonce},
+        state::StorageKey,
+        transaction::{EventContent, EventData, EventKey},
+    };
+
+    use super::*;
+
+    fn contract(address: u64) -> ContractAddress {
+        ContractAddress(StarkFelt::from(address).try_into().unwrap())
+    }
+
+    fn class(class_hash: u64) -> Option<ClassHash> {
+        Some(ClassHash(StarkFelt::from(class_hash)))
+    }
+
+    #[test]
+    fn test_function_invocation() {
+        let inner = CallInfo {
+            call: CallEntryPoint {
+                class_hash: class(0xc2),
+                storage_address: contract(0x2),
+                caller_address: contract(0x1),
+                call_type: CallType::Delegate,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let call_info = CallInfo {
+            call: CallEntryPoint {
+                class_hash: class(0xc1),
+                storage_address: contract(0x1),
+                entry_point_type: EntryPointType::External,
+                ..Default::default()
+            },
+            execution: CallExecution {
+                retdata: Retdata(vec![StarkFelt::from(42u64)]),
+                events: vec![OrderedEvent {
+                    order: 3,
+                    event: EventContent {
+                        keys: vec![EventKey(StarkFelt::from(7u64))],
+                        data: EventData(vec![StarkFelt::ONE]),
+                    },
+                }],
+                ..Default::default()
+            },
+            inner_calls: vec![inner],
+            ..Default::default()
+        };
+
+        let invocation = function_invocation(&call_info).unwrap();
+        assert_eq!(invocation.function_call.contract_address.0.as_ref(), "0x1");
+        assert_eq!(invocation.result[0].as_ref(), "0x2a");
+        assert_eq!(invocation.events.len(), 1);
+        assert_eq!(invocation.events[0].order, Some(3));
+        assert_eq!(invocation.events[0].event.from_address.0.as_ref(), "0x1");
+        assert_eq!(
+            invocation.events[0].event.event_content.keys[0].as_ref(),
+            "0x7"
+        );
+
+        assert_eq!(invocation.class_hash.as_ref(), "0xc1");
+
+        let nested = &invocation.calls[0].0;
+        assert_eq!(nested.caller_address.as_ref(), "0x1");
+        assert_eq!(nested.class_hash.as_ref(), "0xc2");
+        assert!(matches!(nested.call_type, gen::CallType::LibraryCall));
+
+        // an executed call always resolves its class
+        let call_info = CallInfo::default();
+        assert!(function_invocation(&call_info).is_err());
+    }
+
+    #[test]
+    fn test_state_diff() {
+        let mut diff = StateMaps::default();
+        let key =
+            |key: u64| StorageKey(StarkFelt::from(key).try_into().unwrap());
+        diff.storage.insert((contract(0x2), key(0x2)), StarkFelt::TWO);
+        diff.storage.insert((contract(0x2), key(0x1)), StarkFelt::ONE);
+        diff.storage.insert((contract(0x1), key(0x1)), StarkFelt::THREE);
+        diff.nonces.insert(contract(0x1), Nonce(StarkFelt::ONE));
+        diff.class_hashes.insert(contract(0x4), ClassHash(StarkFelt::TWO));
+        diff.class_hashes.insert(contract(0x3), ClassHash(StarkFelt::ONE));
+
+        // 0x3 had no class before the transaction, 0x4 had one
+        let state_diff =
+            state_diff(&diff, |address| Ok(*address == contract(0x3))).unwrap();
+
+        let storage = &state_diff.storage_diffs;
+        assert_eq!(storage.len(), 2);
+        assert_eq!(storage[0].address.as_ref(), "0x1");
+        assert_eq!(storage[1].address.as_ref(), "0x2");
+        let keys: Vec<_> = storage[1]
+            .storage_entries
+            .iter()
+            .map(|entry| entry.key.as_ref().unwrap().as_ref())
+            .collect();
+        assert_eq!(keys, ["0x1", "0x2"]);
+
+        assert_eq!(state_diff.nonces.len(), 1);
+        assert_eq!(state_diff.deployed_contracts.len(), 1);
+        let deployed = &state_diff.deployed_contracts[0];
+        assert_eq!(deployed.address.as_ref(), "0x3");
+        assert_eq!(deployed.class_hash.as_ref(), "0x1");
+        assert_eq!(state_diff.replaced_classes.len(), 1);
+        let replaced = &state_diff.replaced_classes[0];
+        assert_eq!(
+            replaced.contract_address.as_ref().unwrap().0.as_ref(),
+            "0x4"
+        );
+        assert_eq!(replaced.class_hash.as_ref().unwrap().as_ref(), "0x2");
+    }
+}
@@ -0,0 +1,580 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use blockifier::{
+    execution::contract_class::{ClassInfo, ContractClass},
+    transaction::{
+        account_transaction::AccountTransaction,
+        transaction_execution::Transaction,
+        transactions::{
+            DeclareTransaction, DeployAccountTransaction, InvokeTransaction,
+            L1HandlerTransaction,
+        },
+    },
+};
+use starknet_api::{
+    core::{
+        ClassHash, CompiledClassHash, ContractAddress, EntryPointSelector,
+        Nonce,
+    },
+    data_availability::DataAvailabilityMode,
+    transaction::{
+        AccountDeploymentData, Calldata, ContractAddressSalt, Fee,
+        PaymasterData, Resource, ResourceBounds, ResourceBoundsMapping, Tip,
+        TransactionHash, TransactionSignature, TransactionVersion,
+    },
+};
+use starknet_types_core::felt::Felt as StarkFelt;
+
+use super::{as_u128, err::Error};
+use crate::{
+    gen,
+    hash::{
+        class::{legacy_class_hash, sierra_class_hash},
+        tx::{contract_address, parse_u128, parse_u64, transaction_hash_of},
+    },
+};
+
+/// Convert a broadcasted transaction into the account transaction executed
+/// by blockifier. The hash is computed locally, as the account validates the
+/// signature against it. Query versions (offset by 2**128) mark transactions
+/// that are only simulated and can never be included in a block.
+pub fn account_transaction(
+    txn: gen::BroadcastedTxn,
+    chain_id: &gen::ChainId,
+) -> Result<AccountTransaction, Error> {
+    let chain_id = StarkFelt::from_hex(chain_id.as_ref())
+        .map_err(|_| Error::Custom("invalid chain id"))?;
+    match txn {
+        gen::BroadcastedTxn::BroadcastedInvokeTxn(
+            gen::BroadcastedInvokeTxn(tx),
+        ) => {
+            let txn = gen::Txn::InvokeTxn(tx.clone());
+            invoke(tx, transaction_hash_of(&txn, chain_id)?)
+        }
+        gen::BroadcastedTxn::BroadcastedDeclareTxn(tx) => {
+            let (tx, class) = declared(tx)?;
+            let txn = gen::Txn::DeclareTxn(tx.clone());
+            declare(tx, class, transaction_hash_of(&txn, chain_id)?)
+        }
+        gen::BroadcastedTxn::BroadcastedDeployAccountTxn(
+            gen::BroadcastedDeployAccountTxn(tx),
+        ) => {
+            let txn = gen::Txn::DeployAccountTxn(tx.clone());
+            deploy_account(tx, transaction_hash_of(&txn, chain_id)?)
+        }
+    }
+}
+
+/// Convert a transaction of a block (with its already verified hash) into
+/// the transaction blockifier executes. A declare transaction carries only
+/// the hash of the class, so the declared class must be provided.
+pub fn block_transaction(
+    txn: gen::Txn,
+    tx_hash: &gen::Felt,
+    class: Option<gen::GetClassResult>,
+) -> Result<Transaction, Error> {
+    let tx_hash = felt(tx_hash)?;
+    let tx = match txn {
+        gen::Txn::InvokeTxn(tx) => invoke(tx, tx_hash)?,
+        gen::Txn::DeclareTxn(tx) => {
+            let class = class.ok_or(Error::Custom("declared class missing"))?;
+            declare(tx, class, tx_hash)?
+        }
+        gen::Txn::DeployAccountTxn(tx) => deploy_account(tx, tx_hash)?,
+        gen::Txn::L1HandlerTxn(tx) => {
+            return Ok(Transaction::L1HandlerTransaction(l1_handler(
+                tx, tx_hash,
+            )?))
+        }
+        gen::Txn::DeployTxn(_) => {
+            return Err(Error::Custom("deploy transaction not supported"))
+        }
+    };
+    Ok(Transaction::AccountTransaction(tx))
+}
+
+/// Hash of the class declared by the transaction.
+pub fn declared_class_hash(tx: &gen::DeclareTxn) -> &gen::Felt {
+    match tx {
+        gen::DeclareTxn::DeclareTxnV0(tx) => &tx.class_hash,
+        gen::DeclareTxn::DeclareTxnV1(tx) => &tx.class_hash,
+        gen::DeclareTxn::DeclareTxnV2(tx) => &tx.class_hash,
+        gen::DeclareTxn::DeclareTxnV3(tx) => &tx.class_hash,
+    }
+}
+
+fn invoke(
+    tx: gen::InvokeTxn,
+    tx_hash: StarkFelt,
+) -> Result<AccountTransaction, Error> {
+    let (tx, only_query) = match tx {
+        gen::InvokeTxn::InvokeTxnV0(tx) => (
+            starknet_api::transaction::InvokeTransaction::V0(
+                starknet_api::transaction::InvokeTransactionV0 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    contract_address: address(&tx.contract_address)?,
+                    entry_point_selector: EntryPointSelector(felt(
+                        &tx.entry_point_selector,
+                    )?),
+                    calldata: calldata(&tx.calldata)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::InvokeTxnV0Version::V0x100000000000000000000000000000000
+            ),
+        ),
+        gen::InvokeTxn::InvokeTxnV1(tx) => (
+            starknet_api::transaction::InvokeTransaction::V1(
+                starknet_api::transaction::InvokeTransactionV1 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    sender_address: address(&tx.sender_address)?,
+                    calldata: calldata(&tx.calldata)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::InvokeTxnV1Version::V0x100000000000000000000000000000001
+            ),
+        ),
+        gen::InvokeTxn::InvokeTxnV3(tx) => (
+            starknet_api::transaction::InvokeTransaction::V3(
+                starknet_api::transaction::InvokeTransactionV3 {
+                    resource_bounds: resource_bounds(&tx.resource_bounds)?,
+                    tip: Tip(parse_u64(&tx.tip)?),
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    sender_address: address(&tx.sender_address)?,
+                    calldata: calldata(&tx.calldata)?,
+                    nonce_data_availability_mode: da_mode(
+                        &tx.nonce_data_availability_mode,
+                    ),
+                    fee_data_availability_mode: da_mode(
+                        &tx.fee_data_availability_mode,
+                    ),
+                    paymaster_data: PaymasterData(felts(&tx.paymaster_data)?),
+                    account_deployment_data: AccountDeploymentData(felts(
+                        &tx.account_deployment_data,
+                    )?),
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::InvokeTxnV3Version::V0x100000000000000000000000000000003
+            ),
+        ),
+    };
+    Ok(AccountTransaction::Invoke(InvokeTransaction {
+        tx,
+        tx_hash: TransactionHash(tx_hash),
+        only_query,
+    }))
+}
+
+// The declare transaction as it is hashed (with the hash of the class instead
+// of the class itself), and the declared class.
+fn declared(
+    tx: gen::BroadcastedDeclareTxn,
+) -> Result<(gen::DeclareTxn, gen::GetClassResult), Error> {
+    Ok(match tx {
+        gen::BroadcastedDeclareTxn::BroadcastedDeclareTxnV1(tx) => {
+            use gen::{
+                BroadcastedDeclareTxnV1Version as Broadcasted,
+                DeclareTxnV1Version as Version,
+            };
+            // Only the parsed class is at hand: the one that was broadcasted
+            let class = serde_json::to_value(&tx.contract_class)?;
+            let class_hash = legacy_class_hash(&class)?;
+            let version = match tx.version {
+                Broadcasted::V0x1 => Version::V0x1,
+                Broadcasted::V0x100000000000000000000000000000001 => {
+                    Version::V0x100000000000000000000000000000001
+                }
+            };
+            let declared = gen::DeclareTxn::DeclareTxnV1(gen::DeclareTxnV1 {
+                class_hash: class_hash.try_into()?,
+                max_fee: tx.max_fee,
+                nonce: tx.nonce,
+                r#type: gen::DeclareTxnV1Type::Declare,
+                sender_address: tx.sender_address,
+                signature: tx.signature,
+                version,
+            });
+            let class =
+                gen::GetClassResult::DeprecatedContractClass(tx.contract_class);
+            (declared, class)
+        }
+        gen::BroadcastedDeclareTxn::BroadcastedDeclareTxnV2(tx) => {
+            use gen::{
+                BroadcastedDeclareTxnV2Version as Broadcasted,
+                DeclareTxnV2Version as Version,
+            };
+            let class_hash = sierra_class_hash(&tx.contract_class)?;
+            let version = match tx.version {
+                Broadcasted::V0x2 => Version::V0x2,
+                Broadcasted::V0x100000000000000000000000000000002 => {
+                    Version::V0x100000000000000000000000000000002
+                }
+            };
+            let declared = gen::DeclareTxn::DeclareTxnV2(gen::DeclareTxnV2 {
+                class_hash: class_hash.try_into()?,
+                compiled_class_hash: tx.compiled_class_hash,
+                max_fee: tx.max_fee,
+                nonce: tx.nonce,
+                r#type: gen::DeclareTxnV2Type::Declare,
+                sender_address: tx.sender_address,
+                signature: tx.signature,
+                version,
+            });
+            let class = gen::GetClassResult::ContractClass(tx.contract_class);
+            (declared, class)
+        }
+        gen::BroadcastedDeclareTxn::BroadcastedDeclareTxnV3(tx) => {
+            use gen::{
+                BroadcastedDeclareTxnV3Version as Broadcasted,
+                DeclareTxnV3Version as Version,
+            };
+            let class_hash = sierra_class_hash(&tx.contract_class)?;
+            let version = match tx.version {
+                Broadcasted::V0x3 => Version::V0x3,
+                Broadcasted::V0x100000000000000000000000000000003 => {
+                    Version::V0x100000000000000000000000000000003
+                }
+            };
+            let declared = gen::DeclareTxn::DeclareTxnV3(gen::DeclareTxnV3 {
+                account_deployment_data: tx.account_deployment_data,
+                class_hash: class_hash.try_into()?,
+                compiled_class_hash: tx.compiled_class_hash,
+                fee_data_availability_mode: tx.fee_data_availability_mode,
+                nonce: tx.nonce,
+                nonce_data_availability_mode: tx.nonce_data_availability_mode,
+                paymaster_data: tx.paymaster_data,
+                r#type: gen::DeclareTxnV3Type::Declare,
+                resource_bounds: tx.resource_bounds,
+                sender_address: tx.sender_address,
+                signature: tx.signature,
+                tip: tx.tip,
+                version,
+            });
+            let class = gen::GetClassResult::ContractClass(tx.contract_class);
+            (declared, class)
+        }
+    })
+}
+
+fn declare(
+    tx: gen::DeclareTxn,
+    class: gen::GetClassResult,
+    tx_hash: StarkFelt,
+) -> Result<AccountTransaction, Error> {
+    let (tx, only_query) = match tx {
+        gen::DeclareTxn::DeclareTxnV0(tx) => (
+            starknet_api::transaction::DeclareTransaction::V0(
+                starknet_api::transaction::DeclareTransactionV0V1 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce::default(),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    sender_address: address(&tx.sender_address)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::DeclareTxnV0Version::V0x100000000000000000000000000000000
+            ),
+        ),
+        gen::DeclareTxn::DeclareTxnV1(tx) => (
+            starknet_api::transaction::DeclareTransaction::V1(
+                starknet_api::transaction::DeclareTransactionV0V1 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    sender_address: address(&tx.sender_address)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::DeclareTxnV1Version::V0x100000000000000000000000000000001
+            ),
+        ),
+        gen::DeclareTxn::DeclareTxnV2(tx) => (
+            starknet_api::transaction::DeclareTransaction::V2(
+                starknet_api::transaction::DeclareTransactionV2 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    compiled_class_hash: CompiledClassHash(felt(
+                        &tx.compiled_class_hash,
+                    )?),
+                    sender_address: address(&tx.sender_address)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::DeclareTxnV2Version::V0x100000000000000000000000000000002
+            ),
+        ),
+        gen::DeclareTxn::DeclareTxnV3(tx) => (
+            starknet_api::transaction::DeclareTransaction::V3(
+                starknet_api::transaction::DeclareTransactionV3 {
+                    resource_bounds: resource_bounds(&tx.resource_bounds)?,
+                    tip: Tip(parse_u64(&tx.tip)?),
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    compiled_class_hash: CompiledClassHash(felt(
+                        &tx.compiled_class_hash,
+                    )?),
+                    sender_address: address(&tx.sender_address)?,
+                    nonce_data_availability_mode: da_mode(
+                        &tx.nonce_data_availability_mode,
+                    ),
+                    fee_data_availability_mode: da_mode(
+                        &tx.fee_data_availability_mode,
+                    ),
+                    paymaster_data: PaymasterData(felts(&tx.paymaster_data)?),
+                    account_deployment_data: AccountDeploymentData(felts(
+                        &tx.account_deployment_data,
+                    )?),
+                },
+            ),
+            matches!(
+                tx.version,
+                gen::DeclareTxnV3Version::V0x100000000000000000000000000000003
+            ),
+        ),
+    };
+
+    // Cairo 0 classes have neither a sierra program nor a sized ABI
+    let (sierra_program_length, abi_length) = match &class {
+        gen::GetClassResult::ContractClass(class) => sierra_lengths(class),
+        gen::GetClassResult::DeprecatedContractClass(_) => (0, 0),
+    };
+    let class = ContractClass::try_from(class)?;
+    let class_info = ClassInfo::new(&class, sierra_program_length, abi_length)
+        .map_err(|e| Error::Program(format!("{e}")))?;
+    let tx_hash = TransactionHash(tx_hash);
+    let tx = if only_query {
+        DeclareTransaction::new_for_query(tx, tx_hash, class_info)?
+    } else {
+        DeclareTransaction::new(tx, tx_hash, class_info)?
+    };
+    Ok(AccountTransaction::Declare(tx))
+}
+
+fn deploy_account(
+    tx: gen::DeployAccountTxn,
+    tx_hash: StarkFelt,
+) -> Result<AccountTransaction, Error> {
+    use gen::{DeployAccountTxnV1Version, DeployAccountTxnV3Version};
+    let (tx, only_query) = match tx {
+        gen::DeployAccountTxn::DeployAccountTxnV1(tx) => (
+            starknet_api::transaction::DeployAccountTransaction::V1(
+                starknet_api::transaction::DeployAccountTransactionV1 {
+                    max_fee: fee(&tx.max_fee)?,
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    contract_address_salt: ContractAddressSalt(felt(
+                        &tx.contract_address_salt,
+                    )?),
+                    constructor_calldata: calldata(&tx.constructor_calldata)?,
+                },
+            ),
+            matches!(
+                tx.version,
+                DeployAccountTxnV1Version::V0x100000000000000000000000000000001
+            ),
+        ),
+        gen::DeployAccountTxn::DeployAccountTxnV3(tx) => (
+            starknet_api::transaction::DeployAccountTransaction::V3(
+                starknet_api::transaction::DeployAccountTransactionV3 {
+                    resource_bounds: resource_bounds(&tx.resource_bounds)?,
+                    tip: Tip(parse_u64(&tx.tip)?),
+                    signature: signature(&tx.signature)?,
+                    nonce: Nonce(felt(&tx.nonce)?),
+                    class_hash: ClassHash(felt(&tx.class_hash)?),
+                    contract_address_salt: ContractAddressSalt(felt(
+                        &tx.contract_address_salt,
+                    )?),
+                    constructor_calldata: calldata(&tx.constructor_calldata)?,
+                    nonce_data_availability_mode: da_mode(
+                        &tx.nonce_data_availability_mode,
+                    ),
+                    fee_data_availability_mode: da_mode(
+                        &tx.fee_data_availability_mode,
+                    ),
+                    paymaster_data: PaymasterData(felts(&tx.paymaster_data)?),
+                },
+            ),
+            matches!(
+                tx.version,
+                DeployAccountTxnV3Version::V0x100000000000000000000000000000003
+            ),
+        ),
+    };
+    let deployed = contract_address(
+        tx.contract_address_salt().0,
+        tx.class_hash().0,
+        &tx.constructor_calldata().0,
+    );
+    Ok(AccountTransaction::DeployAccount(DeployAccountTransaction {
+        tx,
+        tx_hash: TransactionHash(tx_hash),
+        contract_address: ContractAddress(deployed.try_into()?),
+        only_query,
+    }))
+}
+
+// The fee paid on L1 is not part of the L2 transaction, so it is NOT
+// verified: blockifier only rejects a zero fee and the amount does not show
+// up in the trace or the state diff, so a placeholder of 1 is passed along.
+fn l1_handler(
+    tx: gen::L1HandlerTxn,
+    tx_hash: StarkFelt,
+) -> Result<L1HandlerTransaction, Error> {
+    let nonce = StarkFelt::from_hex(tx.nonce.as_ref())
+        .map_err(|_| Error::Custom("invalid nonce"))?;
+    let call = &tx.function_call;
+    Ok(L1HandlerTransaction {
+        tx: starknet_api::transaction::L1HandlerTransaction {
+            version: TransactionVersion::ZERO,
+            nonce: Nonce(nonce),
+            contract_address: address(&call.contract_address)?,
+            entry_point_selector: EntryPointSelector(felt(
+                &call.entry_point_selector,
+            )?),
+            calldata: calldata(&call.calldata)?,
+        },
+        tx_hash: TransactionHash(tx_hash),
+        paid_fee_on_l1: Fee(1),
+    })
+}
+
+// Lengths of the sierra program and the ABI, both charged as code size
+fn sierra_lengths(class: &gen::ContractClass) -> (usize, usize) {
+    let abi_length =
+        class.abi.as_ref().map(|abi| abi.len()).unwrap_or_default();
+    (class.sierra_program.len(), abi_length)
+}
+
+fn felt(felt: &gen::Felt) -> Result<StarkFelt, Error> {
+    felt.clone().try_into()
+}
+
+fn felts(felts: &[gen::Felt]) -> Result<Vec<StarkFelt>, Error> {
+    felts.iter().map(felt).collect()
+}
+
+fn address(address: &gen::Address) -> Result<ContractAddress, Error> {
+    Ok(ContractAddress(felt(&address.0)?.try_into()?))
+}
+
+fn calldata(calldata: &[gen::Felt]) -> Result<Calldata, Error> {
+    Ok(Calldata(Arc::new(felts(calldata)?)))
+}
+
+fn signature(signature: &[gen::Felt]) -> Result<TransactionSignature, Error> {
+    Ok(TransactionSignature(felts(signature)?))
+}
+
+fn fee(max_fee: &gen::Felt) -> Result<Fee, Error> {
+    Ok(Fee(as_u128(&felt(max_fee)?)?))
+}
+
+fn resource_bounds(
+    bounds: &gen::ResourceBoundsMapping,
+) -> Result<ResourceBoundsMapping, Error> {
+    let bound = |bounds: &gen::ResourceBounds| -> Result<_, Error> {
+        Ok(ResourceBounds {
+            max_amount: parse_u64(&bounds.max_amount)?,
+            max_price_per_unit: parse_u128(&bounds.max_price_per_unit)?,
+        })
+    };
+    Ok(ResourceBoundsMapping(BTreeMap::from([
+        (Resource::L1Gas, bound(&bounds.l1_gas)?),
+        (Resource::L2Gas, bound(&bounds.l2_gas)?),
+    ])))
+}
+
+fn da_mode(mode: &gen::DaMode) -> DataAvailabilityMode {
+    match mode {
+        gen::DaMode::L1 => DataAvailabilityMode::L1,
+        gen::DaMode::L2 => DataAvailabilityMode::L2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn chain_id() -> gen::ChainId {
+        gen::ChainId::try_new("0x534e5f5345504f4c4941").unwrap()
+    }
+
+    #[test]
+    fn test_invoke_query_version() {
+        let invoke = |version: &str| {
+            serde_json::from_value::<gen::BroadcastedTxn>(json!({
+                "type": "INVOKE",
+                "version": version,
+                "sender_address": "0x1234",
+                "calldata": ["0x1", "0x2"],
+                "max_fee": "0x3e8",
+                "nonce": "0x7",
+                "signature": ["0xa", "0xb"]
+            }))
+            .unwrap()
+        };
+
+        let tx = account_transaction(invoke("0x1"), &chain_id()).unwrap();
+        let AccountTransaction::Invoke(tx) = tx else {
+            panic!("invoke expected");
+        };
+        assert!(!tx.only_query);
+        assert_eq!(tx.tx.nonce(), Nonce(StarkFelt::from(7u64)));
+        assert_eq!(tx.tx.calldata().0.len(), 2);
+
+        let query = account_transaction(
+            invoke("0x100000000000000000000000000000001"),
+            &chain_id(),
+        )
+        .unwrap();
+        let AccountTransaction::Invoke(query) = query else {
+            panic!("invoke expected");
+        };
+        assert!(query.only_query);
+        // The query version is part of the signed hash
+        assert_ne!(query.tx_hash, tx.tx_hash);
+    }
+
+    #[test]
+    fn test_l1_handler_transaction() {
+        let txn = serde_json::from_value::<gen::Txn>(json!({
+            "type": "L1_HANDLER",
+            "version": "0x0",
+            "nonce": "0x2a",
+            "contract_address": "0x1234",
+            "entry_point_selector": "0x5678",
+            "calldata": ["0xabc", "0x1"]
+        }))
+        .unwrap();
+        let tx_hash = gen::Felt::try_new("0x99").unwrap();
+
+        let tx = block_transaction(txn, &tx_hash, None).unwrap();
+        let Transaction::L1HandlerTransaction(tx) = tx else {
+            panic!("l1 handler expected");
+        };
+        assert_eq!(tx.tx.nonce, Nonce(StarkFelt::from(42u64)));
+        assert_eq!(tx.tx.calldata.0.len(), 2);
+        assert_eq!(tx.tx_hash, TransactionHash(StarkFelt::from(0x99u64)));
+        assert_ne!(tx.paid_fee_on_l1, Fee(0));
+    }
+}
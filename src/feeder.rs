@@ -1,18 +1,30 @@
-use eyre::{Context, OptionExt, Result};
+use eyre::{eyre, Context, OptionExt, Result};
+use starknet_crypto::Felt as FieldElement;
 
-use crate::{client::State, gen::Felt};
+use crate::{
+    client::State,
+    gen::{BlockId, BlockTag},
+    hash::{
+        block::{BlockHeader, GasPrice, L1DaMode},
+        receipt::GasConsumed,
+    },
+};
 
+#[derive(Clone)]
 pub struct GatewayClient {
     url: String,
+    pubkey: FieldElement,
     client: reqwest::Client,
 }
 
 impl GatewayClient {
-    pub fn new(url: &str) -> Result<Self> {
+    pub fn new(url: &str, pubkey: &str) -> Result<Self> {
         if url.ends_with('/') {
             eyre::bail!("Gateway URL must not end with '/'.");
         }
-        Ok(Self { url: url.to_owned(), client: reqwest::Client::new() })
+        let pubkey = FieldElement::from_hex(pubkey)
+            .map_err(|_| eyre!("gateway: invalid public key"))?;
+        Ok(Self { url: url.to_owned(), pubkey, client: reqwest::Client::new() })
     }
 
     pub async fn get_pubkey(&self, block_hash: &str) -> Result<String> {
@@ -75,9 +87,41 @@ impl GatewayClient {
         Ok((r, s))
     }
 
-    pub async fn get_state(&self) -> Result<State> {
-        let url =
-            format!("{}/feeder_gateway/get_block?blockNumber=latest", self.url);
+    /// Check the sequencer's signature of the block hash against the pinned
+    /// public key, so that no state is accepted from an unsigned response.
+    pub async fn verify_signature(&self, block_hash: &str) -> Result<()> {
+        let (r, s) = self.get_signature(block_hash).await?;
+        let parse = |hex: &str| {
+            FieldElement::from_hex(hex)
+                .map_err(|_| eyre!("gateway: invalid felt: {hex}"))
+        };
+        let message = parse(block_hash)?;
+        let (r, s) = (parse(&r)?, parse(&s)?);
+
+        let valid = starknet_crypto::verify(&self.pubkey, &message, &r, &s)
+            .map_err(|e| eyre!("gateway: invalid signature: {e}"))?;
+        if !valid {
+            eyre::bail!("gateway: signature mismatch for block {block_hash}");
+        }
+        Ok(())
+    }
+
+    async fn get_block(&self, block_id: &BlockId) -> Result<serde_json::Value> {
+        let query = match block_id {
+            BlockId::BlockNumber { block_number } => {
+                format!("blockNumber={}", block_number.as_ref())
+            }
+            BlockId::BlockHash { block_hash } => {
+                format!("blockHash={}", block_hash.0.as_ref())
+            }
+            BlockId::BlockTag(BlockTag::Latest) => {
+                "blockNumber=latest".to_owned()
+            }
+            BlockId::BlockTag(BlockTag::Pending) => {
+                eyre::bail!("gateway: pending block is not supported")
+            }
+        };
+        let url = format!("{}/feeder_gateway/get_block?{}", self.url, query);
         let json: serde_json::Value = self
             .client
             .get(&url)
@@ -87,28 +131,145 @@ impl GatewayClient {
             .json()
             .await
             .context("failed to receive gateway response")?;
+        Ok(json)
+    }
 
+    /// Fetch the block header and check that the block hash commits to it.
+    pub async fn get_header(&self, block_id: &BlockId) -> Result<BlockHeader> {
+        let json = self.get_block(block_id).await?;
+        let header = parse_header(&json)?;
+        header.verify().map_err(|e| eyre!("gateway: {}", e.message))?;
+        Ok(header)
+    }
+
+    /// Fetch the block header together with the gas consumed by each of the
+    /// transactions of the block, as listed in its receipts.
+    pub async fn get_header_with_gas(
+        &self,
+        block_id: &BlockId,
+    ) -> Result<(BlockHeader, Vec<GasConsumed>)> {
+        let json = self.get_block(block_id).await?;
+        let header = parse_header(&json)?;
+        header.verify().map_err(|e| eyre!("gateway: {}", e.message))?;
+        Ok((header, parse_gas_consumed(&json)))
+    }
+
+    pub async fn get_state(&self) -> Result<State> {
+        let json = self.get_block(&BlockId::BlockTag(BlockTag::Latest)).await?;
         if json["status"].as_str() != Some("ACCEPTED_ON_L2") {
             eyre::bail!("gateway: invalid block status");
         }
 
-        let block_number: u64 = json["block_number"]
+        let header = parse_header(&json)?;
+        header.verify().map_err(|e| eyre!("gateway: {}", e.message))?;
+
+        let block_hash = format!("{:#x}", header.block_hash);
+        self.verify_signature(&block_hash).await?;
+
+        Ok(State::try_from(&header)?)
+    }
+}
+
+fn parse_header(json: &serde_json::Value) -> Result<BlockHeader> {
+    let felt = |name: &str| -> Result<FieldElement> {
+        match json[name].as_str() {
+            Some(hex) => FieldElement::from_hex(hex)
+                .map_err(|_| eyre!("gateway: invalid {name}: {hex}")),
+            // Commitments are not present in the oldest blocks
+            None => Ok(FieldElement::ZERO),
+        }
+    };
+    let gas_price = |name: &str| -> Result<GasPrice> {
+        let price = |unit: &str| match json[name][unit].as_str() {
+            Some(hex) => FieldElement::from_hex(hex)
+                .map_err(|_| eyre!("gateway: invalid {name}: {hex}")),
+            None => Ok(FieldElement::ZERO),
+        };
+        Ok(GasPrice {
+            price_in_wei: price("price_in_wei")?,
+            price_in_fri: price("price_in_fri")?,
+        })
+    };
+
+    let block_number = json["block_number"]
+        .as_u64()
+        .ok_or_eyre("gateway: fetching block_number failed")?;
+    let block_hash = json["block_hash"]
+        .as_str()
+        .ok_or_eyre("gateway: fetching block_hash failed")?;
+    let state_root = json["state_root"]
+        .as_str()
+        .ok_or_eyre("gateway: fetching state_root failed")?;
+    let timestamp = json["timestamp"]
+        .as_u64()
+        .ok_or_eyre("gateway: fetching timestamp failed")?;
+    let transaction_count = json["transactions"]
+        .as_array()
+        .map(|txs| txs.len() as u64)
+        .unwrap_or_default();
+    let event_count = json["transaction_receipts"]
+        .as_array()
+        .map(|receipts| {
+            receipts
+                .iter()
+                .filter_map(|receipt| receipt["events"].as_array())
+                .map(|events| events.len() as u64)
+                .sum()
+        })
+        .unwrap_or_default();
+    let l1_da_mode = match json["l1_da_mode"].as_str() {
+        Some("BLOB") => L1DaMode::Blob,
+        _ => L1DaMode::Calldata,
+    };
+
+    Ok(BlockHeader {
+        block_number,
+        block_hash: FieldElement::from_hex(block_hash)
+            .map_err(|_| eyre!("gateway: invalid block_hash: {block_hash}"))?,
+        parent_hash: felt("parent_block_hash")?,
+        state_root: FieldElement::from_hex(state_root)
+            .map_err(|_| eyre!("gateway: invalid state_root: {state_root}"))?,
+        sequencer_address: felt("sequencer_address")?,
+        timestamp,
+        transaction_count,
+        transaction_commitment: felt("transaction_commitment")?,
+        event_count,
+        event_commitment: felt("event_commitment")?,
+        state_diff_length: json["state_diff_length"]
             .as_u64()
-            .ok_or_eyre("gateway: fetching block_number failed")?;
-        let block_hash = json["block_hash"]
+            .unwrap_or_default(),
+        state_diff_commitment: felt("state_diff_commitment")?,
+        receipt_commitment: felt("receipt_commitment")?,
+        l1_gas_price: gas_price("l1_gas_price")?,
+        l1_data_gas_price: gas_price("l1_data_gas_price")?,
+        l2_gas_price: gas_price("l2_gas_price")?,
+        l1_da_mode,
+        starknet_version: json["starknet_version"]
             .as_str()
-            .map(ToOwned::to_owned)
-            .ok_or_eyre("gateway: fetching block_hash failed")?;
-        let root = json["state_root"]
-            .as_str()
-            .map(ToOwned::to_owned)
-            .ok_or_eyre("gateway: fetching state_root failed")?;
-        Ok(State {
-            block_number,
-            block_hash: Felt::try_new(&block_hash)?,
-            root: Felt::try_new(&root)?,
+            .unwrap_or_default()
+            .to_owned(),
+    })
+}
+
+fn parse_gas_consumed(json: &serde_json::Value) -> Vec<GasConsumed> {
+    json["transaction_receipts"]
+        .as_array()
+        .map(|receipts| {
+            receipts
+                .iter()
+                .map(|receipt| {
+                    let gas =
+                        &receipt["execution_resources"]["total_gas_consumed"];
+                    GasConsumed {
+                        l1_gas: gas["l1_gas"].as_u64().unwrap_or_default(),
+                        l1_data_gas: gas["l1_data_gas"]
+                            .as_u64()
+                            .unwrap_or_default(),
+                    }
+                })
+                .collect()
         })
-    }
+        .unwrap_or_default()
 }
 
 #[cfg(test)]
@@ -120,30 +281,96 @@ mod tests {
 
     use super::*;
 
-    #[tokio::test]
-    async fn test_ok() -> Result<()> {
-        const BLOCK_NUMBER: u64 = 1056427;
-        const BLOCK_HASH: &str =
-            "0x7c7b366f1b31a556ace49e1affe3b4ed3cfb5aa328b85307655ea70dadd0cc6";
-        const STATE_ROOT: &str =
-            "0x33d912445ba4f73ce6d910f3952e722aef1c55ee81278b3039b50243278f561";
+    const BLOCK_NUMBER: u64 = 1056427;
+    const BLOCK_HASH: &str =
+        "0x9923f624930aa779dd18ad382ec0ee0569a20a7f7f9d370af724137d6a535e";
+    const STATE_ROOT: &str =
+        "0x33d912445ba4f73ce6d910f3952e722aef1c55ee81278b3039b50243278f561";
+
+    // Signature of BLOCK_HASH produced with a test-only private key
+    const PUBKEY: &str =
+        "0x18dc7b3f365672efbaa43beaf7fe6c2928e41a33cb359178a53b0924e26918c";
+    const SIGNATURE_R: &str =
+        "0x7c678b1721a82994635f6d4b50831561ce11b87645e247aa27c1edd3987c0c7";
+    const SIGNATURE_S: &str =
+        "0x63a599de1c6c9caa3de822d05fadb223f363635e23afd2c12db55af5def382";
+
+    fn block(state_root: &str) -> serde_json::Value {
+        serde_json::json!({
+            "block_number": BLOCK_NUMBER,
+            "block_hash": BLOCK_HASH,
+            "parent_block_hash":
+                "0x2a8b5d1e7f3c6a09d4e1b2c3f5a6d7e8f9012345678abcdef0123456789abc",
+            "state_root": state_root,
+            "sequencer_address":
+                "0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8",
+            "timestamp": 1720000000,
+            "status": "ACCEPTED_ON_L2",
+            "transaction_commitment": "0x111",
+            "event_commitment": "0x222",
+            "state_diff_commitment": "0x333",
+            "receipt_commitment": "0x444",
+            "state_diff_length": 4,
+            "l1_da_mode": "BLOB",
+            "l1_gas_price": {
+                "price_in_wei": "0x3b9aca00",
+                "price_in_fri": "0x5f5e100"
+            },
+            "l1_data_gas_price": {
+                "price_in_wei": "0x1",
+                "price_in_fri": "0x2"
+            },
+            "starknet_version": "0.13.2",
+            "transactions": [{}, {}],
+            "transaction_receipts": [
+                {
+                    "events": [{}, {}],
+                    "execution_resources": {
+                        "total_gas_consumed": {
+                            "l1_gas": 1234,
+                            "l1_data_gas": 128
+                        }
+                    }
+                },
+                { "events": [{}] }
+            ]
+        })
+    }
 
+    async fn setup(signature: [&str; 2]) -> MockServer {
+        setup_with_block(block(STATE_ROOT), signature).await
+    }
+
+    async fn setup_with_block(
+        block: serde_json::Value,
+        signature: [&str; 2],
+    ) -> MockServer {
         let mock = MockServer::start().await;
         Mock::given(method("GET"))
             .and(path("/feeder_gateway/get_block"))
             .and(query_param("blockNumber", "latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(block))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/feeder_gateway/get_signature"))
+            .and(query_param("blockHash", BLOCK_HASH))
             .respond_with(ResponseTemplate::new(200).set_body_json(
                 serde_json::json!({
-                    "block_number": BLOCK_NUMBER,
                     "block_hash": BLOCK_HASH,
-                    "state_root": STATE_ROOT,
-                    "status": "ACCEPTED_ON_L2"
+                    "signature": signature
                 }),
             ))
             .mount(&mock)
             .await;
+        mock
+    }
 
-        let gateway = GatewayClient::new(mock.uri().as_str())?;
+    #[tokio::test]
+    async fn test_ok() -> Result<()> {
+        let mock = setup([SIGNATURE_R, SIGNATURE_S]).await;
+
+        let gateway = GatewayClient::new(mock.uri().as_str(), PUBKEY)?;
         let state = gateway.get_state().await?;
 
         assert_eq!(state.root.as_ref(), STATE_ROOT);
@@ -151,4 +378,72 @@ mod tests {
         assert_eq!(state.block_hash.as_ref(), BLOCK_HASH);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_invalid_signature() -> Result<()> {
+        let mock = setup([SIGNATURE_S, SIGNATURE_R]).await;
+
+        let gateway = GatewayClient::new(mock.uri().as_str(), PUBKEY)?;
+        assert!(gateway.get_state().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wrong_pubkey() -> Result<()> {
+        let mock = setup([SIGNATURE_R, SIGNATURE_S]).await;
+
+        let gateway = GatewayClient::new(
+            mock.uri().as_str(),
+            crate::config::MAINNET_GATEWAY_PUBKEY,
+        )?;
+        assert!(gateway.get_state().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_block_hash() -> Result<()> {
+        let root = "0x1";
+        let mock =
+            setup_with_block(block(root), [SIGNATURE_R, SIGNATURE_S]).await;
+
+        let gateway = GatewayClient::new(mock.uri().as_str(), PUBKEY)?;
+        assert!(gateway.get_state().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_header() -> Result<()> {
+        let mock = setup([SIGNATURE_R, SIGNATURE_S]).await;
+
+        let gateway = GatewayClient::new(mock.uri().as_str(), PUBKEY)?;
+        let header =
+            gateway.get_header(&BlockId::BlockTag(BlockTag::Latest)).await?;
+
+        assert_eq!(header.block_number, BLOCK_NUMBER);
+        assert_eq!(header.transaction_count, 2);
+        assert_eq!(header.event_count, 3);
+        assert_eq!(header.l1_da_mode, L1DaMode::Blob);
+        assert_eq!(format!("{:#x}", header.block_hash), BLOCK_HASH);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_get_header_with_gas() -> Result<()> {
+        let mock = setup([SIGNATURE_R, SIGNATURE_S]).await;
+
+        let gateway = GatewayClient::new(mock.uri().as_str(), PUBKEY)?;
+        let (header, gas) = gateway
+            .get_header_with_gas(&BlockId::BlockTag(BlockTag::Latest))
+            .await?;
+
+        assert_eq!(header.block_number, BLOCK_NUMBER);
+        assert_eq!(
+            gas,
+            vec![
+                GasConsumed { l1_gas: 1234, l1_data_gas: 128 },
+                GasConsumed::default()
+            ]
+        );
+        Ok(())
+    }
 }
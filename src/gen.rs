@@ -2218,6 +2218,12 @@ pub mod gen {
         pub state_commitment: Option<Felt>,
     }
 
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    pub struct GetClassProofResult {
+        pub class_commitment: Felt,
+        pub class_proof: Proof,
+    }
+
     pub mod error {
         pub const BLOCK_NOT_FOUND: Error = Error(24, "Block not found");
         pub const CLASS_ALREADY_DECLARED: Error =
@@ -2284,6 +2290,13 @@ pub mod gen {
     #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
     #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
     pub trait Rpc {
+        /// Returns a merkle proof of a class in the class trie
+        async fn getClassProof(
+            &self,
+            block_id: BlockId,
+            class_hash: Felt,
+        ) -> std::result::Result<GetClassProofResult, jsonrpc::Error>;
+
         /// Returns merkle proofs of a contract's storage state
         async fn getProof(
             &self,
@@ -2489,6 +2502,51 @@ pub mod gen {
         ) -> std::result::Result<TransactionTrace, jsonrpc::Error>;
     }
 
+    async fn handle_getClassProof<RPC: Rpc>(
+        rpc: &RPC,
+        params: &Value,
+    ) -> jsonrpc::Response {
+        #[derive(Deserialize, Serialize)]
+        struct ArgByPos(BlockId, Felt);
+
+        #[derive(Deserialize, Serialize)]
+        struct ArgByName {
+            block_id: BlockId,
+            class_hash: Felt,
+        }
+
+        let args =
+            serde_json::from_value::<ArgByName>(params.clone()).or_else(|_| {
+                serde_json::from_value::<ArgByPos>(params.clone()).map(
+                    |args_by_pos| {
+                        let ArgByPos(block_id, class_hash) = args_by_pos;
+                        ArgByName { block_id, class_hash }
+                    },
+                )
+            });
+
+        let args: ArgByName = match args {
+            Ok(args) => args,
+            Err(error) => {
+                tracing::debug!(?error, "failed to parse request params");
+                return jsonrpc::Response::error(-32602, "Invalid params");
+            }
+        };
+
+        let ArgByName { block_id, class_hash } = args;
+
+        match rpc.getClassProof(block_id, class_hash).await {
+            Ok(ret) => match serde_json::to_value(ret) {
+                Ok(ret) => jsonrpc::Response::result(ret),
+                Err(error) => {
+                    tracing::debug!(?error, "failed to parse response object");
+                    jsonrpc::Response::error(-32603, "Internal error")
+                }
+            },
+            Err(e) => jsonrpc::Response::error(e.code, &e.message),
+        }
+    }
+
     async fn handle_getProof<RPC: Rpc>(
         rpc: &RPC,
         params: &Value,
@@ -3745,6 +3803,9 @@ pub mod gen {
         let params = &req.params.clone().unwrap_or_default();
 
         let response = match req.method.as_str() {
+            "pathfinder_getClassProof" => {
+                handle_getClassProof(rpc, params).await
+            }
             "pathfinder_getProof" => handle_getProof(rpc, params).await,
             "pathfinder_getTxStatus" => handle_getTxStatus(rpc, params).await,
             "pathfinder_version" => handle_version(rpc, params).await,
@@ -3826,6 +3887,13 @@ pub mod gen {
     pub mod blocking {
         use super::*;
         pub trait Rpc {
+            /// Returns a merkle proof of a class in the class trie
+            fn getClassProof(
+                &self,
+                block_id: BlockId,
+                class_hash: Felt,
+            ) -> std::result::Result<GetClassProofResult, jsonrpc::Error>;
+
             /// Returns merkle proofs of a contract's storage state
             fn getProof(
                 &self,
@@ -4033,6 +4101,54 @@ pub mod gen {
             ) -> std::result::Result<TransactionTrace, jsonrpc::Error>;
         }
 
+        fn handle_getClassProof<RPC: Rpc>(
+            rpc: &RPC,
+            params: &Value,
+        ) -> jsonrpc::Response {
+            #[derive(Deserialize, Serialize)]
+            struct ArgByPos(BlockId, Felt);
+
+            #[derive(Deserialize, Serialize)]
+            struct ArgByName {
+                block_id: BlockId,
+                class_hash: Felt,
+            }
+
+            let args = serde_json::from_value::<ArgByName>(params.clone())
+                .or_else(|_| {
+                    serde_json::from_value::<ArgByPos>(params.clone()).map(
+                        |args_by_pos| {
+                            let ArgByPos(block_id, class_hash) = args_by_pos;
+                            ArgByName { block_id, class_hash }
+                        },
+                    )
+                });
+
+            let args: ArgByName = match args {
+                Ok(args) => args,
+                Err(error) => {
+                    tracing::debug!(?error, "failed to parse request params");
+                    return jsonrpc::Response::error(-32602, "Invalid params");
+                }
+            };
+
+            let ArgByName { block_id, class_hash } = args;
+
+            match rpc.getClassProof(block_id, class_hash) {
+                Ok(ret) => match serde_json::to_value(ret) {
+                    Ok(ret) => jsonrpc::Response::result(ret),
+                    Err(error) => {
+                        tracing::debug!(
+                            ?error,
+                            "failed to parse response object"
+                        );
+                        jsonrpc::Response::error(-32603, "Internal error")
+                    }
+                },
+                Err(e) => jsonrpc::Response::error(e.code, &e.message),
+            }
+        }
+
         fn handle_getProof<RPC: Rpc>(
             rpc: &RPC,
             params: &Value,
@@ -5378,6 +5494,7 @@ pub mod gen {
             let params = &req.params.clone().unwrap_or_default();
 
             let response = match req.method.as_str() {
+                "pathfinder_getClassProof" => handle_getClassProof(rpc, params),
                 "pathfinder_getProof" => handle_getProof(rpc, params),
                 "pathfinder_getTxStatus" => handle_getTxStatus(rpc, params),
                 "pathfinder_version" => handle_version(rpc, params),
@@ -5490,6 +5607,58 @@ pub mod gen {
         #[cfg_attr(target_arch = "wasm32", async_trait::async_trait(?Send))]
         #[cfg_attr(not(target_arch = "wasm32"), async_trait::async_trait)]
         impl<HTTP: HttpClient> super::Rpc for Client<HTTP> {
+            async fn getClassProof(
+                &self,
+                block_id: BlockId,
+                class_hash: Felt,
+            ) -> std::result::Result<GetClassProofResult, jsonrpc::Error>
+            {
+                let args = (block_id, class_hash);
+
+                let params: serde_json::Value = serde_json::to_value(args)
+                    .map_err(|e| {
+                        jsonrpc::Error::new(
+                            4001,
+                            format!("Invalid params: {e}."),
+                        )
+                    })?;
+                let req = jsonrpc::Request::new(
+                    "pathfinder_getClassProof".to_string(),
+                    params,
+                )
+                .with_id(jsonrpc::Id::Number(1));
+
+                tracing::debug!(request=?req, "processing");
+                let mut res: jsonrpc::Response =
+                    self.http.post(&self.url, &req).await?;
+                tracing::debug!(response=?res, "processing");
+
+                if let Some(err) = res.error.take() {
+                    tracing::error!(error=?err, "failed");
+                    return Err(err);
+                }
+
+                if let Some(value) = res.result.take() {
+                    let ret: GetClassProofResult =
+                        serde_json::from_value(value).map_err(|e| {
+                            jsonrpc::Error::new(
+                                5002,
+                                format!("Invalid response object: {e}."),
+                            )
+                        })?;
+
+                    tracing::debug!(result=?ret, "ready");
+
+                    Ok(ret)
+                } else {
+                    tracing::error!("both error and result are missing");
+                    Err(jsonrpc::Error::new(
+                        5003,
+                        "Response missing".to_string(),
+                    ))
+                }
+            }
+
             async fn getProof(
                 &self,
                 block_id: BlockId,
@@ -7106,6 +7275,58 @@ pub mod gen {
             }
 
             impl<HTTP: HttpClient> super::super::blocking::Rpc for Client<HTTP> {
+                fn getClassProof(
+                    &self,
+                    block_id: BlockId,
+                    class_hash: Felt,
+                ) -> std::result::Result<GetClassProofResult, jsonrpc::Error>
+                {
+                    let args = (block_id, class_hash);
+
+                    let params: serde_json::Value = serde_json::to_value(args)
+                        .map_err(|e| {
+                            jsonrpc::Error::new(
+                                4001,
+                                format!("Invalid params: {e}."),
+                            )
+                        })?;
+                    let req = jsonrpc::Request::new(
+                        "pathfinder_getClassProof".to_string(),
+                        params,
+                    )
+                    .with_id(jsonrpc::Id::Number(1));
+
+                    tracing::debug!(request=?req, "processing");
+                    let mut res: jsonrpc::Response =
+                        self.http.post(&self.url, &req)?;
+                    tracing::debug!(response=?res, "processing");
+
+                    if let Some(err) = res.error.take() {
+                        tracing::error!(error=?err, "failed");
+                        return Err(err);
+                    }
+
+                    if let Some(value) = res.result.take() {
+                        let ret: GetClassProofResult =
+                            serde_json::from_value(value).map_err(|e| {
+                                jsonrpc::Error::new(
+                                    5002,
+                                    format!("Invalid response object: {e}."),
+                                )
+                            })?;
+
+                        tracing::debug!(result=?ret, "ready");
+
+                        Ok(ret)
+                    } else {
+                        tracing::error!("both error and result are missing");
+                        Err(jsonrpc::Error::new(
+                            5003,
+                            "Response missing".to_string(),
+                        ))
+                    }
+                }
+
                 fn getProof(
                     &self,
                     block_id: BlockId,
@@ -0,0 +1,291 @@
+use iamgroot::jsonrpc;
+use starknet_crypto::{poseidon_hash_many, Felt as FieldElement};
+
+use super::{ascii_as_felt, pedersen_hash_array};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum L1DaMode {
+    #[default]
+    Calldata,
+    Blob,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GasPrice {
+    pub price_in_wei: FieldElement,
+    pub price_in_fri: FieldElement,
+}
+
+/// Block header with all the fields the block hash commits to.
+#[derive(Debug, Clone, Default)]
+pub struct BlockHeader {
+    pub block_number: u64,
+    pub block_hash: FieldElement,
+    pub parent_hash: FieldElement,
+    pub state_root: FieldElement,
+    pub sequencer_address: FieldElement,
+    pub timestamp: u64,
+    pub transaction_count: u64,
+    pub transaction_commitment: FieldElement,
+    pub event_count: u64,
+    pub event_commitment: FieldElement,
+    pub state_diff_length: u64,
+    pub state_diff_commitment: FieldElement,
+    pub receipt_commitment: FieldElement,
+    pub l1_gas_price: GasPrice,
+    pub l1_data_gas_price: GasPrice,
+    pub l2_gas_price: GasPrice,
+    pub l1_da_mode: L1DaMode,
+    pub starknet_version: String,
+}
+
+impl BlockHeader {
+    /// Compute the block hash using the formula matching `starknet_version`.
+    pub fn compute_hash(&self) -> FieldElement {
+        let version = parse_version(&self.starknet_version);
+        if version < [0, 13, 2, 0] {
+            self.compute_pedersen_hash()
+        } else {
+            self.compute_poseidon_hash(version)
+        }
+    }
+
+    /// Check that the declared block hash commits to the header fields.
+    pub fn verify(&self) -> Result<(), jsonrpc::Error> {
+        let computed = self.compute_hash();
+        if computed != self.block_hash {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Block hash invalid:\nprovided-hash -> {:#x}\ncomputed-hash -> {:#x}\n",
+                    self.block_hash, computed
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    // H(block_number, state_root, sequencer_address, timestamp,
+    //   transaction_count, transaction_commitment, event_count,
+    //   event_commitment, 0, 0, parent_hash)
+    fn compute_pedersen_hash(&self) -> FieldElement {
+        pedersen_hash_array(&[
+            self.block_number.into(),
+            self.state_root,
+            self.sequencer_address,
+            self.timestamp.into(),
+            self.transaction_count.into(),
+            self.transaction_commitment,
+            self.event_count.into(),
+            self.event_commitment,
+            FieldElement::ZERO,
+            FieldElement::ZERO,
+            self.parent_hash,
+        ])
+    }
+
+    fn compute_poseidon_hash(&self, version: [u64; 4]) -> FieldElement {
+        let (prefix, gas_prices) = if version < [0, 13, 4, 0] {
+            (
+                "STARKNET_BLOCK_HASH0",
+                vec![
+                    self.l1_gas_price.price_in_wei,
+                    self.l1_gas_price.price_in_fri,
+                    self.l1_data_gas_price.price_in_wei,
+                    self.l1_data_gas_price.price_in_fri,
+                ],
+            )
+        } else {
+            (
+                "STARKNET_BLOCK_HASH1",
+                vec![poseidon_hash_many(&[
+                    ascii_as_felt("STARKNET_GAS_PRICES0"),
+                    self.l1_gas_price.price_in_wei,
+                    self.l1_gas_price.price_in_fri,
+                    self.l1_data_gas_price.price_in_wei,
+                    self.l1_data_gas_price.price_in_fri,
+                    self.l2_gas_price.price_in_wei,
+                    self.l2_gas_price.price_in_fri,
+                ])],
+            )
+        };
+
+        let mut data = vec![
+            ascii_as_felt(prefix),
+            self.block_number.into(),
+            self.state_root,
+            self.sequencer_address,
+            self.timestamp.into(),
+            self.concat_counts(),
+            self.state_diff_commitment,
+            self.transaction_commitment,
+            self.event_commitment,
+            self.receipt_commitment,
+        ];
+        data.extend(gas_prices);
+        data.extend([
+            ascii_as_felt(&self.starknet_version),
+            FieldElement::ZERO,
+            self.parent_hash,
+        ]);
+        poseidon_hash_many(&data)
+    }
+
+    // A single felt: transaction_count (64 bits) | event_count (64 bits) |
+    // state_diff_length (64 bits) | L1 DA mode (1 bit) | zero padding
+    fn concat_counts(&self) -> FieldElement {
+        let mut bytes = [0u8; 32];
+        bytes[0..8].copy_from_slice(&self.transaction_count.to_be_bytes());
+        bytes[8..16].copy_from_slice(&self.event_count.to_be_bytes());
+        bytes[16..24].copy_from_slice(&self.state_diff_length.to_be_bytes());
+        if self.l1_da_mode == L1DaMode::Blob {
+            bytes[24] = 0b1000_0000;
+        }
+        FieldElement::from_bytes_be(&bytes)
+    }
+}
+
+/// Parse Starknet version ("0.13.1.1") into comparable components.
+/// Missing or malformed version (early blocks) is treated as the oldest one.
+pub fn parse_version(version: &str) -> [u64; 4] {
+    let mut ret = [0u64; 4];
+    for (i, part) in version.split('.').take(4).enumerate() {
+        match part.parse() {
+            Ok(n) => ret[i] = n,
+            Err(_) => return [0; 4],
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felt(hex: &str) -> FieldElement {
+        FieldElement::from_hex(hex).unwrap()
+    }
+
+    fn header(starknet_version: &str) -> BlockHeader {
+        BlockHeader {
+            block_number: 42,
+            block_hash: FieldElement::ZERO,
+            parent_hash: felt("0x1234"),
+            state_root: felt("0x5678"),
+            sequencer_address: felt("0x9abc"),
+            timestamp: 1700000000,
+            transaction_count: 3,
+            transaction_commitment: felt("0x111"),
+            event_count: 5,
+            event_commitment: felt("0x222"),
+            state_diff_length: 7,
+            state_diff_commitment: felt("0x333"),
+            receipt_commitment: felt("0x444"),
+            l1_gas_price: GasPrice {
+                price_in_wei: felt("0x10"),
+                price_in_fri: felt("0x11"),
+            },
+            l1_data_gas_price: GasPrice {
+                price_in_wei: felt("0x12"),
+                price_in_fri: felt("0x13"),
+            },
+            l2_gas_price: GasPrice {
+                price_in_wei: felt("0x14"),
+                price_in_fri: felt("0x15"),
+            },
+            l1_da_mode: L1DaMode::Blob,
+            starknet_version: starknet_version.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.13.2"), [0, 13, 2, 0]);
+        assert_eq!(parse_version("0.13.1.1"), [0, 13, 1, 1]);
+        assert_eq!(parse_version(""), [0, 0, 0, 0]);
+        assert!(parse_version("0.13.1.1") < parse_version("0.13.2"));
+        assert!(parse_version("0.13.10") > parse_version("0.13.2"));
+    }
+
+    #[test]
+    fn test_concat_counts() {
+        let header = BlockHeader {
+            transaction_count: 4,
+            event_count: 3,
+            state_diff_length: 2,
+            l1_da_mode: L1DaMode::Blob,
+            ..Default::default()
+        };
+        assert_eq!(
+            header.concat_counts(),
+            felt("0x0000000000000004000000000000000300000000000000028000000000000000")
+        );
+    }
+
+    #[test]
+    fn test_pedersen_block_hash() {
+        let header = header("0.13.1");
+        assert_eq!(
+            header.compute_hash(),
+            felt("0x758d534bffbff8dc3ab27177767f98f3582870a990dbb6dc16b45744d52abd2")
+        );
+    }
+
+    #[test]
+    fn test_pedersen_block_hash_mainnet() {
+        // Mainnet block 183862 (0.12.1)
+        let header = BlockHeader {
+            block_number: 183862,
+            parent_hash: felt("0x55f803cd7af981ed03d1ede9d52e5f5ba48cefb24e26ce181baeec3ae45c9dc"),
+            state_root: felt("0x6c4171ece740d153a40106b18545f147d62c513a9cb67eb7b06f83a2508b3a4"),
+            sequencer_address: felt("0x1176a1bd84444c89232ec27754698e5d2e7e1a7f1539f12027f28b23ec9f3d8"),
+            timestamp: 1693484880,
+            transaction_count: 332,
+            transaction_commitment: felt("0x3ef487dcb7c114e91ffba49b91567a5719a188abe7a03466943588a4b06c453"),
+            event_count: 1561,
+            event_commitment: felt("0x4ca88a5447b4885563a074af8316c6cd0e4e8949ba8f532f08770d2f986fc91"),
+            l1_gas_price: GasPrice {
+                price_in_wei: felt("0x41ab3fdb5"),
+                price_in_fri: FieldElement::ZERO,
+            },
+            starknet_version: "0.12.1".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            header.compute_hash(),
+            felt("0x395daa726c74f1a96119796725d82b6b72452200cb62055c28e04202e07136f")
+        );
+    }
+
+    // No mainnet header of 0.13.2 or later is available offline: the two
+    // synthetic vectors below only guard the Poseidon formulas against
+    // regressions, and real headers of both formulas are checked against
+    // the gateway by `test_block_hash_mainnet_headers` in `tests/rpc.rs`.
+    #[test]
+    fn test_poseidon_block_hash_regression() {
+        let header = header("0.13.2");
+        assert_eq!(
+            header.compute_hash(),
+            felt("0x51962c2d33d342c6afdca30973b6fb6e9a09e9b7f6f7433b3cca12e78be5393")
+        );
+    }
+
+    #[test]
+    fn test_poseidon_block_hash_with_l2_gas_regression() {
+        let header = header("0.13.4");
+        assert_eq!(
+            header.compute_hash(),
+            felt("0x422bb6a224469e95397d9fb285c7083bd3e960bbf78b798510310dde6632469")
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut header = header("0.13.2");
+        assert!(header.verify().is_err());
+        header.block_hash = header.compute_hash();
+        assert!(header.verify().is_ok());
+        header.state_root = felt("0x5679");
+        assert!(header.verify().is_err());
+    }
+}
@@ -0,0 +1,406 @@
+use iamgroot::jsonrpc;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use starknet_crypto::{poseidon_hash_many, Felt as FieldElement};
+
+use super::{as_felt, ascii_as_felt, pedersen_hash_array, starknet_keccak};
+use crate::gen::{
+    ContractClass, DeprecatedCairoEntryPoint,
+    DeprecatedContractClassEntryPointsByType, Felt, SierraEntryPoint,
+};
+
+// Version of the legacy (Cairo 0) class hash scheme
+const API_VERSION: FieldElement = FieldElement::ZERO;
+
+/// Check that the class, as received from the node, hashes to the expected
+/// class hash, and parse it. Legacy classes are hashed over the received JSON:
+/// their ABI may carry fields that the parsed class does not keep.
+pub fn verified<T: DeserializeOwned>(
+    class: Value,
+    class_hash: &Felt,
+) -> Result<T, jsonrpc::Error> {
+    if class.get("sierra_program").is_some() {
+        let class = ContractClass::deserialize(&class).map_err(invalid)?;
+        verify_sierra(&class, class_hash)?;
+    } else {
+        verify_legacy(&class, class_hash)?;
+    }
+    serde_json::from_value(class).map_err(invalid)
+}
+
+/// Check that the class hashes to the expected class hash.
+pub fn verify_sierra(
+    class: &ContractClass,
+    class_hash: &Felt,
+) -> Result<(), jsonrpc::Error> {
+    check(sierra_class_hash(class)?, class_hash)
+}
+
+/// Check that the legacy class (its JSON) hashes to the expected class hash.
+pub fn verify_legacy(
+    class: &Value,
+    class_hash: &Felt,
+) -> Result<(), jsonrpc::Error> {
+    check(legacy_class_hash(class)?, class_hash)
+}
+
+fn check(
+    computed: FieldElement,
+    class_hash: &Felt,
+) -> Result<(), jsonrpc::Error> {
+    if as_felt(class_hash)? != computed {
+        return Err(jsonrpc::Error::new(
+            -32700,
+            format!(
+                "Class hash invalid:\nprovided-hash -> {}\ncomputed-hash -> {:#x}\n",
+                class_hash.as_ref(),
+                computed
+            ),
+        ));
+    }
+    Ok(())
+}
+
+pub fn sierra_class_hash(
+    class: &ContractClass,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let version = format!("CONTRACT_CLASS_V{}", class.contract_class_version);
+    let entry_points = &class.entry_points_by_type;
+    let abi = class.abi.as_deref().unwrap_or_default();
+    let program = class
+        .sierra_program
+        .iter()
+        .map(as_felt)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(poseidon_hash_many(&[
+        ascii_as_felt(&version),
+        hash_sierra_entry_points(&entry_points.external)?,
+        hash_sierra_entry_points(&entry_points.l1_handler)?,
+        hash_sierra_entry_points(&entry_points.constructor)?,
+        starknet_keccak(abi.as_bytes()),
+        poseidon_hash_many(&program),
+    ]))
+}
+
+fn hash_sierra_entry_points(
+    entry_points: &[SierraEntryPoint],
+) -> Result<FieldElement, jsonrpc::Error> {
+    let mut data = Vec::with_capacity(entry_points.len() * 2);
+    for entry_point in entry_points {
+        data.push(as_felt(&entry_point.selector)?);
+        data.push(FieldElement::from(entry_point.function_idx as u64));
+    }
+    Ok(poseidon_hash_many(&data))
+}
+
+pub fn legacy_class_hash(
+    class: &Value,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let program =
+        class["program"].as_str().ok_or_else(|| invalid("missing program"))?;
+    let program = crate::exe::map::decode_program(program)?;
+    let program: Value = serde_json::from_str(&program).map_err(invalid)?;
+
+    let entry_points = DeprecatedContractClassEntryPointsByType::deserialize(
+        &class["entry_points_by_type"],
+    )
+    .map_err(invalid)?;
+    let builtins = program["builtins"]
+        .as_array()
+        .ok_or_else(|| invalid("missing builtins"))?
+        .iter()
+        .map(|name| name.as_str().map(ascii_as_felt))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| invalid("invalid builtins"))?;
+    let bytecode = program["data"]
+        .as_array()
+        .ok_or_else(|| invalid("missing data"))?
+        .iter()
+        .map(|felt| {
+            felt.as_str()
+                .and_then(|hex| FieldElement::from_hex(hex).ok())
+                .ok_or_else(|| invalid("invalid data"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let abi = match &class["abi"] {
+        Value::Null => Value::Array(vec![]),
+        abi => abi.clone(),
+    };
+    let hinted_class_hash = hinted_class_hash(abi, program)?;
+
+    Ok(pedersen_hash_array(&[
+        API_VERSION,
+        hash_legacy_entry_points(entry_points.external.as_deref())?,
+        hash_legacy_entry_points(entry_points.l1_handler.as_deref())?,
+        hash_legacy_entry_points(entry_points.constructor.as_deref())?,
+        pedersen_hash_array(&builtins),
+        hinted_class_hash,
+        pedersen_hash_array(&bytecode),
+    ]))
+}
+
+fn hash_legacy_entry_points(
+    entry_points: Option<&[DeprecatedCairoEntryPoint]>,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let entry_points = entry_points.unwrap_or_default();
+    let mut data = Vec::with_capacity(entry_points.len() * 2);
+    for entry_point in entry_points {
+        data.push(as_felt(&entry_point.selector)?);
+        let offset = FieldElement::from_hex(entry_point.offset.as_ref())
+            .map_err(|_| invalid("invalid entry point offset"))?;
+        data.push(offset);
+    }
+    Ok(pedersen_hash_array(&data))
+}
+
+// Keccak of the artifact `{"abi": .., "program": ..}` without debug info,
+// serialized the way Python's `json.dumps(.., sort_keys=True)` does.
+fn hinted_class_hash(
+    abi: Value,
+    mut program: Value,
+) -> Result<FieldElement, jsonrpc::Error> {
+    program["debug_info"] = Value::Null;
+
+    let skip_attributes = program["attributes"]
+        .as_array()
+        .map(|attributes| attributes.is_empty())
+        .unwrap_or(true);
+    if let Some(program) = program.as_object_mut() {
+        if skip_attributes {
+            program.remove("attributes");
+        } else if let Some(Value::Array(attributes)) =
+            program.get_mut("attributes")
+        {
+            for attribute in attributes.iter_mut() {
+                let Some(attribute) = attribute.as_object_mut() else {
+                    continue;
+                };
+                if attribute.get("accessible_scopes")
+                    == Some(&Value::Array(vec![]))
+                {
+                    attribute.remove("accessible_scopes");
+                }
+                if attribute.get("flow_tracking_data") == Some(&Value::Null) {
+                    attribute.remove("flow_tracking_data");
+                }
+            }
+        }
+    }
+
+    // Compilers before 0.10.0 (no `compiler_version`) formatted Cairo types
+    // as "(a : felt)", the hash is computed over that representation.
+    if program.get("compiler_version").is_none() {
+        if let Some(Value::Object(identifiers)) = program.get_mut("identifiers")
+        {
+            for identifier in identifiers.values_mut() {
+                add_type_spaces(identifier.get_mut("cairo_type"));
+                if let Some(Value::Object(members)) =
+                    identifier.get_mut("members")
+                {
+                    for member in members.values_mut() {
+                        add_type_spaces(member.get_mut("cairo_type"));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut json = String::new();
+    write_pythonic(
+        &serde_json::json!({
+            "abi": abi,
+            "program": program,
+        }),
+        &mut json,
+    );
+    Ok(starknet_keccak(json.as_bytes()))
+}
+
+fn add_type_spaces(cairo_type: Option<&mut Value>) {
+    if let Some(Value::String(cairo_type)) = cairo_type {
+        *cairo_type = cairo_type.replace(": ", " : ");
+    }
+}
+
+// Equivalent of `json.dumps(value, sort_keys=True)`: keys are sorted, items
+// are separated by ", " and ": ", and non-ASCII characters are escaped.
+fn write_pythonic(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(flag) => out.push_str(if *flag { "true" } else { "false" }),
+        Value::Number(number) => out.push_str(&number.to_string()),
+        Value::String(string) => write_pythonic_string(string, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_pythonic(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_pythonic_string(key, out);
+                out.push_str(": ");
+                write_pythonic(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_pythonic_string(string: &str, out: &mut String) {
+    out.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            c if c.is_ascii() && !c.is_ascii_control() => out.push(c),
+            c => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    out.push_str(&format!("\\u{unit:04x}"));
+                }
+            }
+        }
+    }
+    out.push('"');
+}
+
+fn invalid(e: impl std::fmt::Display) -> jsonrpc::Error {
+    jsonrpc::Error::new(-32700, format!("Invalid class: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gen::{DeprecatedContractClass, GetClassResult};
+
+    #[test]
+    fn test_starknet_keccak() {
+        assert_eq!(
+            starknet_keccak(b"balanceOf"),
+            FieldElement::from_hex(
+                "0x2e4263afad30923c891518314c3c95dbe830a16874e8abc5777a9a20b54c76e"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_write_pythonic() {
+        let value = serde_json::json!({
+            "b": [1, "x"],
+            "a": {"d": null, "c": true},
+            "e": "caf\u{e9} \"q\"\n",
+        });
+        let mut json = String::new();
+        write_pythonic(&value, &mut json);
+        assert_eq!(
+            json,
+            r#"{"a": {"c": true, "d": null}, "b": [1, "x"], "e": "caf\u00e9 \"q\"\n"}"#
+        );
+    }
+
+    #[test]
+    fn test_sierra_class_hash() {
+        let class: ContractClass = serde_json::from_value(serde_json::json!({
+            "abi": "[]",
+            "contract_class_version": "0.1.0",
+            "entry_points_by_type": {
+                "CONSTRUCTOR": [],
+                "EXTERNAL": [
+                    {"selector": "0x1", "function_idx": 0},
+                    {"selector": "0x2", "function_idx": 1}
+                ],
+                "L1_HANDLER": []
+            },
+            "sierra_program": ["0x1", "0x2", "0x3"]
+        }))
+        .unwrap();
+        let expected =
+            "0x335e10bbc87cac3bda759bbc989557b3b59eb67c777624e587242cc8944e77b";
+        assert_eq!(
+            format!("{:#x}", sierra_class_hash(&class).unwrap()),
+            expected
+        );
+        assert!(
+            verify_sierra(&class, &Felt::try_new(expected).unwrap()).is_ok()
+        );
+        assert!(verify_sierra(&class, &Felt::try_new("0x1").unwrap()).is_err());
+    }
+
+    // Gzipped and base64-encoded program with two builtins and two words
+    const PROGRAM: &str = "H4sIAAAAAAACA5VQ22rDMAz9leLnEWQ26NivjGEcR07FEjk48sgo/ffJKt2eZ/CDjs7NvrooUmlsgrt7O71/PJ3c2GgRYpvdhhPWHdnpokaeMaQLpk/XiamsGy1Yw5dSqLAKHAwehufOnqJEs4DjBc6vAP6cc+63b+Hw5jHh2OZAnItyuS2LYhdi6enXmw40IQtl0ogOuRDWSBzCkG2U7w17bm6cpHdQyZYUATNPpUYp9f62W/cz9Z7KXfZwM1ml1UA4tO3f8R7+d7x9FmasyAk1geOM1er+oo9GPxsB7oWBAQAA";
+
+    fn legacy_class(abi: Value) -> Value {
+        serde_json::json!({
+            "abi": abi,
+            "entry_points_by_type": {
+                "CONSTRUCTOR": [],
+                "EXTERNAL": [{"selector": "0x1", "offset": "0x2"}],
+                "L1_HANDLER": []
+            },
+            "program": PROGRAM
+        })
+    }
+
+    #[test]
+    fn test_legacy_class_hash() {
+        let class = legacy_class(serde_json::json!([]));
+        let expected =
+            "0x6155ba72a61b33ad667eb206878401a18b86f70b74ccf5820adf9582cd6295";
+        assert_eq!(
+            format!("{:#x}", legacy_class_hash(&class).unwrap()),
+            expected
+        );
+        assert!(
+            verify_legacy(&class, &Felt::try_new(expected).unwrap()).is_ok()
+        );
+        assert!(verify_legacy(&class, &Felt::try_new("0x1").unwrap()).is_err());
+
+        // A missing ABI is hashed as an empty one
+        let class = legacy_class(Value::Null);
+        assert_eq!(
+            format!("{:#x}", legacy_class_hash(&class).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_legacy_class_hash_raw_abi() {
+        // A field of the ABI that the parsed class does not keep
+        let class = legacy_class(serde_json::json!([{
+            "inputs": [],
+            "name": "get",
+            "outputs": [{"name": "value", "type": "felt"}],
+            "type": "function",
+            "customAttribute": "view"
+        }]));
+        let parsed: DeprecatedContractClass =
+            serde_json::from_value(class.clone()).unwrap();
+        let reserialized = serde_json::to_value(&parsed).unwrap();
+        let class_hash = legacy_class_hash(&class).unwrap();
+        assert_ne!(class_hash, legacy_class_hash(&reserialized).unwrap());
+
+        let class_hash = Felt::try_new(&format!("{class_hash:#x}")).unwrap();
+        let result: GetClassResult = verified(class, &class_hash).unwrap();
+        assert!(matches!(result, GetClassResult::DeprecatedContractClass(_)));
+        assert!(verified::<GetClassResult>(reserialized, &class_hash).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use starknet_crypto::Felt as FieldElement;
+
+// Height of the Patricia trees the block commitments are built from
+const TREE_HEIGHT: u8 = 64;
+
+type Hash = fn(&FieldElement, &FieldElement) -> FieldElement;
+
+/// Root of the height-64 Patricia tree with the values stored under the keys
+/// 0, 1, 2, ... (transaction, event and receipt commitments of a block).
+pub fn calculate_root(values: &[FieldElement], hash: Hash) -> FieldElement {
+    if values.is_empty() {
+        return FieldElement::ZERO;
+    }
+    let leaves: Vec<(u64, FieldElement)> =
+        (0u64..).zip(values.iter().copied()).collect();
+    get_hash(&leaves, 0, hash)
+}
+
+fn get_hash(
+    leaves: &[(u64, FieldElement)],
+    height: u8,
+    hash: Hash,
+) -> FieldElement {
+    if height == TREE_HEIGHT {
+        return leaves[0].1;
+    }
+    let partition_point = leaves.partition_point(|(key, _)| !bit(*key, height));
+    if partition_point < leaves.len() {
+        let zero_hash = get_hash(&leaves[..partition_point], height + 1, hash);
+        let one_hash = get_hash(&leaves[partition_point..], height + 1, hash);
+        return hash(&zero_hash, &one_hash);
+    }
+
+    // All the leaves share a prefix of zeros: an edge node
+    let last = leaves[leaves.len() - 1].0;
+    let mut n_zeros = 1;
+    while height + n_zeros < TREE_HEIGHT && !bit(last, height + n_zeros) {
+        n_zeros += 1;
+    }
+    let child_hash = get_hash(leaves, height + n_zeros, hash);
+    hash(&child_hash, &FieldElement::ZERO) + FieldElement::from(n_zeros)
+}
+
+// Bit of the key at the given height, counting from the most significant one
+fn bit(key: u64, height: u8) -> bool {
+    (key >> (TREE_HEIGHT - 1 - height)) & 1 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_crypto::{pedersen_hash, poseidon_hash};
+
+    use super::*;
+
+    fn felt(hex: &str) -> FieldElement {
+        FieldElement::from_hex(hex).unwrap()
+    }
+
+    fn poseidon(x: &FieldElement, y: &FieldElement) -> FieldElement {
+        poseidon_hash(*x, *y)
+    }
+
+    #[test]
+    fn test_empty_root() {
+        assert_eq!(calculate_root(&[], poseidon), FieldElement::ZERO);
+    }
+
+    #[test]
+    fn test_edge_root() {
+        let values = [felt("0x1")];
+        assert_eq!(
+            calculate_root(&values, poseidon),
+            felt(
+                "0x7752582c54a42fe0fa35c40f07293bb7d8efe90e21d8d2c06a7db52d7d9b7e1"
+            )
+        );
+    }
+
+    #[test]
+    fn test_binary_root() {
+        let values = [felt("0x1"), felt("0x2")];
+        assert_eq!(
+            calculate_root(&values, poseidon),
+            felt(
+                "0x1c1ba983ee0a0de87d87d67ea3cbee7023aa65f6b7bcf71259f122ea3af80bf"
+            )
+        );
+    }
+
+    #[test]
+    fn test_root() {
+        let values = [felt("0x1"), felt("0x2"), felt("0x3")];
+        assert_eq!(
+            calculate_root(&values, poseidon),
+            felt(
+                "0x3b5cc7f1292eb3847c3f902d048a7e5dc7702d1c191ccd17c2d33f797e6fc32"
+            )
+        );
+        assert_eq!(
+            calculate_root(&values, pedersen_hash),
+            felt(
+                "0x231e110514ca3a27707cd6c365e00685142d43b03d26f6274db51cbfa91aa1c"
+            )
+        );
+    }
+}
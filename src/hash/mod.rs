@@ -0,0 +1,45 @@
+use iamgroot::jsonrpc;
+use starknet_crypto::{pedersen_hash, Felt as FieldElement};
+
+use crate::gen::Felt;
+
+pub mod block;
+pub mod class;
+pub mod commitment;
+pub mod receipt;
+pub mod tx;
+
+/// Pedersen hash chain over the elements, followed by the length.
+pub fn pedersen_hash_array(data: &[FieldElement]) -> FieldElement {
+    let hash = data
+        .iter()
+        .fold(FieldElement::ZERO, |hash, item| pedersen_hash(&hash, item));
+    pedersen_hash(&hash, &FieldElement::from(data.len() as u64))
+}
+
+/// Encode a short ASCII string (at most 31 characters) as a felt.
+pub fn ascii_as_felt(value: &str) -> FieldElement {
+    FieldElement::from_bytes_be_slice(value.as_bytes())
+}
+
+/// Keccak256 truncated to 250 bits, as used for selectors and class hashes.
+pub fn starknet_keccak(data: &[u8]) -> FieldElement {
+    let mut hash = alloy_primitives::keccak256(data).0;
+    hash[0] &= 0b0000_0011;
+    FieldElement::from_bytes_be(&hash)
+}
+
+pub(crate) fn as_felt(felt: &Felt) -> Result<FieldElement, jsonrpc::Error> {
+    FieldElement::from_hex(felt.as_ref()).map_err(|_| {
+        jsonrpc::Error::new(
+            -32701,
+            "Failed to create Field Element".to_string(),
+        )
+    })
+}
+
+pub(crate) fn felts(
+    data: &[Felt],
+) -> Result<Vec<FieldElement>, jsonrpc::Error> {
+    data.iter().map(as_felt).collect()
+}
@@ -0,0 +1,237 @@
+use iamgroot::jsonrpc;
+use starknet_crypto::{
+    pedersen_hash, poseidon_hash, poseidon_hash_many, Felt as FieldElement,
+};
+
+use super::{as_felt, commitment, felts, pedersen_hash_array, starknet_keccak};
+use crate::gen::{
+    CommonReceiptProperties, Event, ResultCommonReceiptProperties,
+    TransactionAndReceipt, TxnReceipt,
+};
+
+/// Gas consumed by a transaction. The receipt commitment includes it, but the
+/// RPC receipts do not carry it, so it is read from the feeder gateway.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasConsumed {
+    pub l1_gas: u64,
+    pub l1_data_gas: u64,
+}
+
+/// Commitment of a block to the events emitted by its transactions, in the
+/// scheme of the Starknet version of the block.
+pub fn event_commitment(
+    transactions: &[TransactionAndReceipt],
+    version: [u64; 4],
+) -> Result<FieldElement, jsonrpc::Error> {
+    let mut leaves = Vec::new();
+    for tx in transactions {
+        let receipt = common_properties(&tx.receipt);
+        let transaction_hash = as_felt(&receipt.transaction_hash.0)?;
+        for event in &receipt.events {
+            leaves.push(event_hash(event, transaction_hash, version)?);
+        }
+    }
+    if version < [0, 13, 2, 0] {
+        Ok(commitment::calculate_root(&leaves, pedersen_hash))
+    } else {
+        Ok(commitment::calculate_root(&leaves, |x, y| poseidon_hash(*x, *y)))
+    }
+}
+
+/// Hash of an event: the transaction hash is included since 0.13.2.
+pub fn event_hash(
+    event: &Event,
+    transaction_hash: FieldElement,
+    version: [u64; 4],
+) -> Result<FieldElement, jsonrpc::Error> {
+    let from_address = as_felt(&event.from_address.0)?;
+    let keys = felts(&event.event_content.keys)?;
+    let data = felts(&event.event_content.data)?;
+
+    if version < [0, 13, 2, 0] {
+        return Ok(pedersen_hash_array(&[
+            from_address,
+            pedersen_hash_array(&keys),
+            pedersen_hash_array(&data),
+        ]));
+    }
+    let mut elements = vec![from_address, transaction_hash];
+    elements.push(FieldElement::from(keys.len() as u64));
+    elements.extend(keys);
+    elements.push(FieldElement::from(data.len() as u64));
+    elements.extend(data);
+    Ok(poseidon_hash_many(&elements))
+}
+
+/// Commitment of a block to the receipts of its transactions (since 0.13.2).
+pub fn receipt_commitment(
+    transactions: &[TransactionAndReceipt],
+    gas_consumed: &[GasConsumed],
+) -> Result<FieldElement, jsonrpc::Error> {
+    if transactions.len() != gas_consumed.len() {
+        return Err(jsonrpc::Error::new(
+            -32700,
+            format!(
+                "Receipts mismatch: receipts={} gas={}",
+                transactions.len(),
+                gas_consumed.len()
+            ),
+        ));
+    }
+    let leaves = transactions
+        .iter()
+        .zip(gas_consumed)
+        .map(|(tx, gas)| receipt_hash(common_properties(&tx.receipt), gas))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(commitment::calculate_root(&leaves, |x, y| poseidon_hash(*x, *y)))
+}
+
+// H(transaction_hash, actual_fee, messages_hash, revert_reason_hash,
+//   l2_gas, l1_gas, l1_data_gas)
+pub fn receipt_hash(
+    receipt: &CommonReceiptProperties,
+    gas_consumed: &GasConsumed,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let mut messages =
+        vec![FieldElement::from(receipt.messages_sent.len() as u64)];
+    for message in &receipt.messages_sent {
+        messages.push(as_felt(&message.from_address)?);
+        messages.push(as_felt(&message.to_address)?);
+        messages.push(FieldElement::from(message.payload.len() as u64));
+        messages.extend(felts(&message.payload)?);
+    }
+    let revert_reason_hash = match &receipt.result_common_receipt_properties {
+        ResultCommonReceiptProperties::SuccessfulCommonReceiptProperties(_) => {
+            FieldElement::ZERO
+        }
+        ResultCommonReceiptProperties::RevertedCommonReceiptProperties(
+            reverted,
+        ) => starknet_keccak(reverted.revert_reason.as_bytes()),
+    };
+
+    Ok(poseidon_hash_many(&[
+        as_felt(&receipt.transaction_hash.0)?,
+        as_felt(&receipt.actual_fee.amount)?,
+        poseidon_hash_many(&messages),
+        revert_reason_hash,
+        FieldElement::ZERO,
+        FieldElement::from(gas_consumed.l1_gas),
+        FieldElement::from(gas_consumed.l1_data_gas),
+    ]))
+}
+
+pub fn common_properties(receipt: &TxnReceipt) -> &CommonReceiptProperties {
+    match receipt {
+        TxnReceipt::InvokeTxnReceipt(r) => &r.common_receipt_properties,
+        TxnReceipt::L1HandlerTxnReceipt(r) => &r.common_receipt_properties,
+        TxnReceipt::DeclareTxnReceipt(r) => &r.common_receipt_properties,
+        TxnReceipt::DeployTxnReceipt(r) => &r.common_receipt_properties,
+        TxnReceipt::DeployAccountTxnReceipt(r) => &r.common_receipt_properties,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn felt(hex: &str) -> FieldElement {
+        FieldElement::from_hex(hex).unwrap()
+    }
+
+    fn event() -> serde_json::Value {
+        json!({"from_address": "0x1234", "keys": ["0x99"], "data": ["0x1", "0x2"]})
+    }
+
+    fn transaction(receipt: serde_json::Value) -> TransactionAndReceipt {
+        serde_json::from_value(json!({
+            "transaction": {
+                "type": "INVOKE",
+                "version": "0x1",
+                "sender_address": "0x1234",
+                "calldata": [],
+                "max_fee": "0x0",
+                "nonce": "0x0",
+                "signature": []
+            },
+            "receipt": receipt
+        }))
+        .unwrap()
+    }
+
+    fn receipt() -> serde_json::Value {
+        json!({
+            "type": "INVOKE",
+            "transaction_hash": "0xabc",
+            "actual_fee": {"amount": "0x3e8", "unit": "WEI"},
+            "execution_status": "SUCCEEDED",
+            "finality_status": "ACCEPTED_ON_L2",
+            "messages_sent": [
+                {"from_address": "0x1234", "to_address": "0x5678", "payload": ["0x7"]}
+            ],
+            "events": [event(), event()],
+            "execution_resources": {"steps": 100}
+        })
+    }
+
+    const GAS: GasConsumed = GasConsumed { l1_gas: 1234, l1_data_gas: 128 };
+
+    #[test]
+    fn test_event_hash() {
+        let event: Event = serde_json::from_value(event()).unwrap();
+        assert_eq!(
+            event_hash(&event, felt("0xabc"), [0, 13, 1, 0]).unwrap(),
+            felt("0x746cae78f094450274d1742aa579f8865030a9ed7083feb47d50e4cfeb9c744")
+        );
+        assert_eq!(
+            event_hash(&event, felt("0xabc"), [0, 13, 2, 0]).unwrap(),
+            felt("0x694eb3d14445621f9f3b2a8f4530f7ff50ce384d21ca83516651c8095c88a43")
+        );
+    }
+
+    #[test]
+    fn test_event_commitment() {
+        let transactions = [transaction(receipt())];
+        assert_eq!(
+            event_commitment(&transactions, [0, 13, 1, 0]).unwrap(),
+            felt("0x3eb2befbb225f3b04dcf2f0cbd4c224baee540a69705aa660cf7bd6e8a5a464")
+        );
+        assert_eq!(
+            event_commitment(&transactions, [0, 13, 2, 0]).unwrap(),
+            felt("0x22df3da0c4d7efb78c8b3566925ec23576bf37f46449f0b0967d065b0401f78")
+        );
+    }
+
+    #[test]
+    fn test_receipt_hash() {
+        let tx = transaction(receipt());
+        assert_eq!(
+            receipt_hash(common_properties(&tx.receipt), &GAS).unwrap(),
+            felt("0x5218b576cb88de54cbf7f6784bb6dae624da1c455095f6922ba56e0b2e4fcb4")
+        );
+    }
+
+    #[test]
+    fn test_reverted_receipt_hash() {
+        let mut receipt = receipt();
+        receipt["execution_status"] = json!("REVERTED");
+        receipt["revert_reason"] = json!("boom");
+        receipt["messages_sent"] = json!([]);
+        let tx = transaction(receipt);
+        assert_eq!(
+            receipt_hash(common_properties(&tx.receipt), &GAS).unwrap(),
+            felt("0x2ed33f373ba8a5b74bc56b621d20112184d1fddc68f662247a9314bd3bd6ab2")
+        );
+    }
+
+    #[test]
+    fn test_receipt_commitment() {
+        let transactions = [transaction(receipt())];
+        assert_eq!(
+            receipt_commitment(&transactions, &[GAS]).unwrap(),
+            felt("0x246c5be302a56e679612bca4e6c6f8d22fc44818b08311649c60388c6a01ac5")
+        );
+        assert!(receipt_commitment(&transactions, &[]).is_err());
+    }
+}
@@ -0,0 +1,913 @@
+use iamgroot::jsonrpc;
+use serde::Serialize;
+use starknet_crypto::{
+    pedersen_hash, poseidon_hash, poseidon_hash_many, Felt as FieldElement,
+};
+
+use super::{as_felt, ascii_as_felt, commitment, felts, pedersen_hash_array};
+use crate::gen::{
+    DaMode, DeclareTxn, DeployAccountTxn, DeployTxn, Felt, InvokeTxn,
+    L1HandlerTxn, ResourceBounds, ResourceBoundsMapping, Txn, U128, U64,
+};
+
+// Selector of the `constructor` entry point
+const CONSTRUCTOR_ENTRY_POINT_SELECTOR: FieldElement =
+    FieldElement::from_hex_unchecked(
+        "0x28ffe4ff0f226a9107253e17a904099aa4f63a02a5621de0576e5aa71bc5194",
+    );
+
+// 2**251 - 256
+const L2_ADDRESS_UPPER_BOUND: FieldElement = FieldElement::from_hex_unchecked(
+    "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff00",
+);
+
+/// Check that the transaction hashes to the expected hash. Transactions from
+/// the early blocks may also match one of the deprecated hash schemes.
+pub fn verify(
+    txn: &Txn,
+    chain_id: FieldElement,
+    transaction_hash: &Felt,
+) -> Result<(), jsonrpc::Error> {
+    let expected = as_felt(transaction_hash)?;
+    let computed = transaction_hash_of(txn, chain_id)?;
+    if computed == expected
+        || deprecated_transaction_hashes(txn, chain_id)?.contains(&expected)
+    {
+        return Ok(());
+    }
+    Err(jsonrpc::Error::new(
+        -32700,
+        format!(
+            "Transaction hash invalid:\nprovided-hash -> {}\ncomputed-hash -> {:#x}\n",
+            transaction_hash.as_ref(),
+            computed
+        ),
+    ))
+}
+
+/// Compute the hash of a transaction according to its type and version.
+pub fn transaction_hash_of(
+    txn: &Txn,
+    chain_id: FieldElement,
+) -> Result<FieldElement, jsonrpc::Error> {
+    match txn {
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV0(tx)) => {
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("invoke"),
+                version(&tx.version)?,
+                as_felt(&tx.contract_address.0)?,
+                as_felt(&tx.entry_point_selector)?,
+                pedersen_hash_array(&felts(&tx.calldata)?),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+            ]))
+        }
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV1(tx)) => {
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("invoke"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                FieldElement::ZERO,
+                pedersen_hash_array(&felts(&tx.calldata)?),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+                as_felt(&tx.nonce)?,
+            ]))
+        }
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV3(tx)) => {
+            Ok(poseidon_hash_many(&[
+                ascii_as_felt("invoke"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                tip_resource_bounds_hash(&tx.tip, &tx.resource_bounds)?,
+                poseidon_hash_many(&felts(&tx.paymaster_data)?),
+                chain_id,
+                as_felt(&tx.nonce)?,
+                data_availability_modes(
+                    &tx.nonce_data_availability_mode,
+                    &tx.fee_data_availability_mode,
+                ),
+                poseidon_hash_many(&felts(&tx.account_deployment_data)?),
+                poseidon_hash_many(&felts(&tx.calldata)?),
+            ]))
+        }
+        Txn::L1HandlerTxn(tx) => Ok(pedersen_hash_array(&[
+            ascii_as_felt("l1_handler"),
+            version(&tx.version)?,
+            as_felt(&tx.function_call.contract_address.0)?,
+            as_felt(&tx.function_call.entry_point_selector)?,
+            pedersen_hash_array(&felts(&tx.function_call.calldata)?),
+            FieldElement::ZERO,
+            chain_id,
+            l1_handler_nonce(tx)?,
+        ])),
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV0(tx)) => {
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("declare"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                FieldElement::ZERO,
+                pedersen_hash_array(&[]),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+                as_felt(&tx.class_hash)?,
+            ]))
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV1(tx)) => {
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("declare"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                FieldElement::ZERO,
+                pedersen_hash_array(&[as_felt(&tx.class_hash)?]),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+                as_felt(&tx.nonce)?,
+            ]))
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV2(tx)) => {
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("declare"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                FieldElement::ZERO,
+                pedersen_hash_array(&[as_felt(&tx.class_hash)?]),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+                as_felt(&tx.nonce)?,
+                as_felt(&tx.compiled_class_hash)?,
+            ]))
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV3(tx)) => {
+            Ok(poseidon_hash_many(&[
+                ascii_as_felt("declare"),
+                version(&tx.version)?,
+                as_felt(&tx.sender_address.0)?,
+                tip_resource_bounds_hash(&tx.tip, &tx.resource_bounds)?,
+                poseidon_hash_many(&felts(&tx.paymaster_data)?),
+                chain_id,
+                as_felt(&tx.nonce)?,
+                data_availability_modes(
+                    &tx.nonce_data_availability_mode,
+                    &tx.fee_data_availability_mode,
+                ),
+                poseidon_hash_many(&felts(&tx.account_deployment_data)?),
+                as_felt(&tx.class_hash)?,
+                as_felt(&tx.compiled_class_hash)?,
+            ]))
+        }
+        Txn::DeployTxn(tx) => deploy_transaction_hash(tx, chain_id, false),
+        Txn::DeployAccountTxn(DeployAccountTxn::DeployAccountTxnV1(tx)) => {
+            let class_hash = as_felt(&tx.class_hash)?;
+            let salt = as_felt(&tx.contract_address_salt)?;
+            let constructor_calldata = felts(&tx.constructor_calldata)?;
+            let mut calldata = vec![class_hash, salt];
+            calldata.extend_from_slice(&constructor_calldata);
+            Ok(pedersen_hash_array(&[
+                ascii_as_felt("deploy_account"),
+                version(&tx.version)?,
+                contract_address(salt, class_hash, &constructor_calldata),
+                FieldElement::ZERO,
+                pedersen_hash_array(&calldata),
+                as_felt(&tx.max_fee)?,
+                chain_id,
+                as_felt(&tx.nonce)?,
+            ]))
+        }
+        Txn::DeployAccountTxn(DeployAccountTxn::DeployAccountTxnV3(tx)) => {
+            let class_hash = as_felt(&tx.class_hash)?;
+            let salt = as_felt(&tx.contract_address_salt)?;
+            let constructor_calldata = felts(&tx.constructor_calldata)?;
+            Ok(poseidon_hash_many(&[
+                ascii_as_felt("deploy_account"),
+                version(&tx.version)?,
+                contract_address(salt, class_hash, &constructor_calldata),
+                tip_resource_bounds_hash(&tx.tip, &tx.resource_bounds)?,
+                poseidon_hash_many(&felts(&tx.paymaster_data)?),
+                chain_id,
+                as_felt(&tx.nonce)?,
+                data_availability_modes(
+                    &tx.nonce_data_availability_mode,
+                    &tx.fee_data_availability_mode,
+                ),
+                poseidon_hash_many(&constructor_calldata),
+                class_hash,
+                salt,
+            ]))
+        }
+    }
+}
+
+// Hash schemes used before the transaction version was part of the hash
+// (invoke and deploy) and before L1 handlers had their own prefix and nonce.
+fn deprecated_transaction_hashes(
+    txn: &Txn,
+    chain_id: FieldElement,
+) -> Result<Vec<FieldElement>, jsonrpc::Error> {
+    Ok(match txn {
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV0(tx)) => {
+            vec![pedersen_hash_array(&[
+                ascii_as_felt("invoke"),
+                as_felt(&tx.contract_address.0)?,
+                as_felt(&tx.entry_point_selector)?,
+                pedersen_hash_array(&felts(&tx.calldata)?),
+                chain_id,
+            ])]
+        }
+        Txn::L1HandlerTxn(tx) => {
+            let contract_address =
+                as_felt(&tx.function_call.contract_address.0)?;
+            let selector = as_felt(&tx.function_call.entry_point_selector)?;
+            let calldata_hash =
+                pedersen_hash_array(&felts(&tx.function_call.calldata)?);
+            vec![
+                pedersen_hash_array(&[
+                    ascii_as_felt("invoke"),
+                    contract_address,
+                    selector,
+                    calldata_hash,
+                    chain_id,
+                ]),
+                pedersen_hash_array(&[
+                    ascii_as_felt("l1_handler"),
+                    contract_address,
+                    selector,
+                    calldata_hash,
+                    chain_id,
+                    l1_handler_nonce(tx)?,
+                ]),
+            ]
+        }
+        Txn::DeployTxn(tx) => {
+            vec![deploy_transaction_hash(tx, chain_id, true)?]
+        }
+        _ => vec![],
+    })
+}
+
+fn deploy_transaction_hash(
+    tx: &DeployTxn,
+    chain_id: FieldElement,
+    deprecated: bool,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let constructor_calldata = felts(&tx.constructor_calldata)?;
+    let address = contract_address(
+        as_felt(&tx.contract_address_salt)?,
+        as_felt(&tx.class_hash)?,
+        &constructor_calldata,
+    );
+    let calldata_hash = pedersen_hash_array(&constructor_calldata);
+    let data = if deprecated {
+        vec![
+            ascii_as_felt("deploy"),
+            address,
+            CONSTRUCTOR_ENTRY_POINT_SELECTOR,
+            calldata_hash,
+            chain_id,
+        ]
+    } else {
+        vec![
+            ascii_as_felt("deploy"),
+            as_felt(&tx.version)?,
+            address,
+            CONSTRUCTOR_ENTRY_POINT_SELECTOR,
+            calldata_hash,
+            FieldElement::ZERO,
+            chain_id,
+        ]
+    };
+    Ok(pedersen_hash_array(&data))
+}
+
+/// Address of a contract deployed from the zero address (deploy and deploy
+/// account transactions).
+pub fn contract_address(
+    salt: FieldElement,
+    class_hash: FieldElement,
+    constructor_calldata: &[FieldElement],
+) -> FieldElement {
+    let address = pedersen_hash_array(&[
+        ascii_as_felt("STARKNET_CONTRACT_ADDRESS"),
+        FieldElement::ZERO,
+        salt,
+        class_hash,
+        pedersen_hash_array(constructor_calldata),
+    ]);
+    if address.to_bytes_be() >= L2_ADDRESS_UPPER_BOUND.to_bytes_be() {
+        address - L2_ADDRESS_UPPER_BOUND
+    } else {
+        address
+    }
+}
+
+/// Commitment of a block to its transactions and their signatures, in the
+/// scheme of the Starknet version of the block.
+pub fn transaction_commitment<'a>(
+    transactions: impl IntoIterator<Item = (&'a Txn, &'a Felt)>,
+    version: [u64; 4],
+) -> Result<FieldElement, jsonrpc::Error> {
+    let leaves = transactions
+        .into_iter()
+        .map(|(txn, hash)| transaction_leaf(txn, as_felt(hash)?, version))
+        .collect::<Result<Vec<_>, _>>()?;
+    if version < [0, 13, 2, 0] {
+        Ok(commitment::calculate_root(&leaves, pedersen_hash))
+    } else {
+        Ok(commitment::calculate_root(&leaves, |x, y| poseidon_hash(*x, *y)))
+    }
+}
+
+/// Leaf of the transaction commitment tree: the transaction hash together with
+/// its signature (the scheme depends on the Starknet version of the block).
+pub fn transaction_leaf(
+    txn: &Txn,
+    transaction_hash: FieldElement,
+    version: [u64; 4],
+) -> Result<FieldElement, jsonrpc::Error> {
+    let signature: &[Felt] = match txn {
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV0(tx)) => &tx.signature,
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV1(tx)) => &tx.signature,
+        Txn::InvokeTxn(InvokeTxn::InvokeTxnV3(tx)) => &tx.signature,
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV0(tx))
+            if version >= [0, 11, 1, 0] =>
+        {
+            &tx.signature
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV1(tx))
+            if version >= [0, 11, 1, 0] =>
+        {
+            &tx.signature
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV2(tx))
+            if version >= [0, 11, 1, 0] =>
+        {
+            &tx.signature
+        }
+        Txn::DeclareTxn(DeclareTxn::DeclareTxnV3(tx)) => &tx.signature,
+        Txn::DeployAccountTxn(DeployAccountTxn::DeployAccountTxnV1(tx))
+            if version >= [0, 11, 1, 0] =>
+        {
+            &tx.signature
+        }
+        Txn::DeployAccountTxn(DeployAccountTxn::DeployAccountTxnV3(tx)) => {
+            &tx.signature
+        }
+        _ => &[],
+    };
+    let signature = felts(signature)?;
+
+    if version < [0, 13, 2, 0] {
+        return Ok(pedersen_hash(
+            &transaction_hash,
+            &pedersen_hash_array(&signature),
+        ));
+    }
+    let mut data = vec![transaction_hash];
+    if signature.is_empty() {
+        data.push(FieldElement::ZERO);
+    } else {
+        data.extend(signature);
+    }
+    Ok(poseidon_hash_many(&data))
+}
+
+// H(tip, [0 | "L1_GAS" | max_amount | max_price_per_unit], [.. "L2_GAS" ..])
+fn tip_resource_bounds_hash(
+    tip: &U64,
+    resource_bounds: &ResourceBoundsMapping,
+) -> Result<FieldElement, jsonrpc::Error> {
+    Ok(poseidon_hash_many(&[
+        FieldElement::from(parse_u64(tip)?),
+        resource(b"L1_GAS", &resource_bounds.l1_gas)?,
+        resource(b"L2_GAS", &resource_bounds.l2_gas)?,
+    ]))
+}
+
+fn resource(
+    name: &[u8; 6],
+    bounds: &ResourceBounds,
+) -> Result<FieldElement, jsonrpc::Error> {
+    let mut bytes = [0u8; 32];
+    bytes[2..8].copy_from_slice(name);
+    bytes[8..16].copy_from_slice(&parse_u64(&bounds.max_amount)?.to_be_bytes());
+    bytes[16..32].copy_from_slice(
+        &parse_u128(&bounds.max_price_per_unit)?.to_be_bytes(),
+    );
+    Ok(FieldElement::from_bytes_be(&bytes))
+}
+
+// nonce_mode (32 bits) | fee_mode (32 bits)
+fn data_availability_modes(
+    nonce_mode: &DaMode,
+    fee_mode: &DaMode,
+) -> FieldElement {
+    let index = |mode: &DaMode| match mode {
+        DaMode::L1 => 0u64,
+        DaMode::L2 => 1u64,
+    };
+    FieldElement::from((index(nonce_mode) << 32) + index(fee_mode))
+}
+
+fn l1_handler_nonce(tx: &L1HandlerTxn) -> Result<FieldElement, jsonrpc::Error> {
+    FieldElement::from_hex(tx.nonce.as_ref()).map_err(|_| {
+        jsonrpc::Error::new(
+            -32701,
+            "Failed to create Field Element".to_string(),
+        )
+    })
+}
+
+// The version enums serialize to the hex string of the version felt.
+fn version<T: Serialize>(version: &T) -> Result<FieldElement, jsonrpc::Error> {
+    let value = serde_json::to_value(version).map_err(|e| {
+        jsonrpc::Error::new(-32701, format!("Invalid version: {e}"))
+    })?;
+    let hex = value.as_str().ok_or_else(|| {
+        jsonrpc::Error::new(-32701, format!("Invalid version: {value}"))
+    })?;
+    FieldElement::from_hex(hex).map_err(|_| {
+        jsonrpc::Error::new(-32701, format!("Invalid version: {hex}"))
+    })
+}
+
+pub(crate) fn parse_u64(value: &U64) -> Result<u64, jsonrpc::Error> {
+    u64::from_str_radix(value.as_ref().trim_start_matches("0x"), 16)
+        .map_err(|e| jsonrpc::Error::new(-32701, format!("Invalid u64: {e}")))
+}
+
+pub(crate) fn parse_u128(value: &U128) -> Result<u128, jsonrpc::Error> {
+    u128::from_str_radix(value.as_ref().trim_start_matches("0x"), 16)
+        .map_err(|e| jsonrpc::Error::new(-32701, format!("Invalid u128: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn felt(hex: &str) -> FieldElement {
+        FieldElement::from_hex(hex).unwrap()
+    }
+
+    fn chain_id() -> FieldElement {
+        ascii_as_felt("SN_MAIN")
+    }
+
+    fn txn(value: serde_json::Value) -> Txn {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn signed(signature: &[&str]) -> Txn {
+        txn(json!({
+            "type": "INVOKE",
+            "version": "0x1",
+            "sender_address": "0x1234",
+            "calldata": [],
+            "max_fee": "0x0",
+            "nonce": "0x0",
+            "signature": signature
+        }))
+    }
+
+    // Mainnet block 206864
+    #[test]
+    fn test_invoke_v0_hash() {
+        let tx = txn(json!({
+            "type": "INVOKE",
+            "version": "0x0",
+            "calldata": [
+                "0x1",
+                "0x16ba3e98d91e73aa2037bf80fa55f82be38211530632dc7e9ac47223688e34e",
+                "0xf2f7c15cbe06c8d94597cd91fd7f3369eae842359235712def5584f8d270cd",
+                "0x0",
+                "0x1",
+                "0x1",
+                "0x33434ad846cdd5f23eb73ff09fe6fddd568284a0fb7d1be20ee482f044dabe2",
+                "0x0"
+            ],
+            "contract_address": "0x16ba3e98d91e73aa2037bf80fa55f82be38211530632dc7e9ac47223688e34e",
+            "entry_point_selector": "0x15d40a3d6ca2ac30f4031e42be28da9b056fef9bb7357ac5e85627ee876e5ad",
+            "max_fee": "0x892a33adefe4",
+            "signature": [
+                "0x4d9f6a54e158cf0cfb086e8cdb30c0dd1e29809e504130ae5065b3c56f4d1d4",
+                "0x49e753a978b34ade8b32c319e2f225121b5bb1f12c040046c85549ef7ab1344"
+            ]
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x7b199bef92f4d2dc7713c53927060b3388ccd6873dc084ee7c12cdac4209d3b")
+        );
+    }
+
+    // Mainnet block 636864
+    #[test]
+    fn test_invoke_v1_hash() {
+        let tx = txn(json!({
+            "type": "INVOKE",
+            "version": "0x1",
+            "calldata": [
+                "0x2",
+                "0x68f5c6a61780768455de69077e07e89787839bf8166decfbf92b645209c0fb8",
+                "0x219209e083275171774dab1df80982e9df2096516f06319c5c6d71ae0a8480c",
+                "0x3",
+                "0x1114c7103e12c2b2ecbd3a2472ba9c48ddcbf702b1c242dd570057e26212111",
+                "0xb67495",
+                "0x0",
+                "0x1114c7103e12c2b2ecbd3a2472ba9c48ddcbf702b1c242dd570057e26212111",
+                "0x15543c3708653cda9d418b4ccd3be11368e40636c10c44b18cfe756b6d88b29",
+                "0x7",
+                "0x30baaaf1b243f6e74c656f98dcb24b98687dcbe783d25f35854148c4c602d41",
+                "0x0",
+                "0xb67495",
+                "0x0",
+                "0x1",
+                "0x3a1045717884ca9abbc2e",
+                "0x0"
+            ],
+            "max_fee": "0x7f49b0d6d7c",
+            "nonce": "0x62",
+            "sender_address": "0x6f7afd58d20aedbdb694ff539d3280ae497c1a510caddcc6a06c97eebd001dc",
+            "signature": [
+                "0x1",
+                "0xd8744b5d5c0da02d6562bd48d4271e95ac7753aae044c8e736862875ce2092",
+                "0x4f24eb09e7e8c2105e204dfeaa737948cf9c5c4fb2749074c73fdbb2400a68f"
+            ]
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x215b2e7efdedc5d9c056dd6a691b8117f292997d2cc8c15a9cebfa90620e35")
+        );
+    }
+
+    // Mainnet block 636864
+    #[test]
+    fn test_invoke_v3_hash() {
+        let tx = txn(json!({
+            "type": "INVOKE",
+            "version": "0x3",
+            "account_deployment_data": [],
+            "calldata": [
+                "0x1",
+                "0x4c0a5193d58f74fbace4b74dcf65481e734ed1714121bdc571da345540efa05",
+                "0x3943907ef0ef6f9d2e2408b05e520a66daaf74293dbf665e5a20b117676170e",
+                "0x2",
+                "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7",
+                "0x16345785d8a0000"
+            ],
+            "fee_data_availability_mode": "L1",
+            "nonce": "0x9d",
+            "nonce_data_availability_mode": "L1",
+            "paymaster_data": [],
+            "sender_address": "0x69c0f9bcd79697bdceaf7748e3ff8f34aa39e4063ce44896af664c0c96f6c10",
+            "signature": [
+                "0x1",
+                "0x1c3a868705399362140bf5ae33f955f1c946a8f836d4c052f2aff397bcfe80a",
+                "0x78163ce5979e2bc8a944ba353a63c194ca4f63d393bbaaa95857daa9223e93c"
+            ],
+            "tip": "0x0",
+            "resource_bounds": {
+                "l1_gas": {
+                    "max_amount": "0xa9e",
+                    "max_price_per_unit": "0x7f2a1ad4f2f1"
+                },
+                "l2_gas": {
+                    "max_amount": "0x0",
+                    "max_price_per_unit": "0x0"
+                }
+            }
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x1d4735f4ba73a67be2f648d9b21cab3783383b8c229566b46b027c46012219")
+        );
+    }
+
+    // Mainnet block 2700
+    #[test]
+    fn test_declare_v0_hash() {
+        let tx = txn(json!({
+            "type": "DECLARE",
+            "version": "0x0",
+            "class_hash": "0x7319e2f01b0947afd86c0bb0e95029551b32f6dc192c47b2e8b08415eebbc25",
+            "max_fee": "0x0",
+            "sender_address": "0x1",
+            "signature": []
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x2f2ef64daffdc72bf33b34ad024891691b8eb1d0ab70cc7f8fb71f6fd5e1f22")
+        );
+    }
+
+    // Mainnet block 346864
+    #[test]
+    fn test_declare_v1_hash() {
+        let tx = txn(json!({
+            "type": "DECLARE",
+            "version": "0x1",
+            "class_hash": "0x4dae654c7b6707667a178729b512d61494fe590ab4accc46923d6409b97e617",
+            "max_fee": "0x1f04d0693837",
+            "nonce": "0x15",
+            "sender_address": "0x3e2a6434cebce4475cdf8843859f2137259918171eba8d462e3d34c5c4fd110",
+            "signature": [
+                "0x72d657c8bed37c254615966b94178bbaeb87edbe9ebd176657b80d538c8d7c5",
+                "0x2fd512374a9eb0277338bf95fbf29315d449a35120a0880dfeff5fd39e7fe80"
+            ]
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x70fb3ea4b786b752817a32d4835eaba19da495392df90f7f827cd9b60b2c925")
+        );
+    }
+
+    // Mainnet block 446864
+    #[test]
+    fn test_declare_v2_hash() {
+        let tx = txn(json!({
+            "type": "DECLARE",
+            "version": "0x2",
+            "class_hash": "0x4d90a3b52871831b34bc936d9aee304b7205202e649dceef5ee4392659ab33",
+            "compiled_class_hash": "0x3c1296b5f7e6a30bc0167bf30e0700eebb2e9a06228e24cc3ad386502125bcf",
+            "max_fee": "0xb48df232e93750",
+            "nonce": "0x1f9",
+            "sender_address": "0x75341b8090a4257f22dafffe3a4cb882006bd26302720d6a80a1fde154a3430",
+            "signature": [
+                "0x10fe9fe438cd3c5bd2c4aa94ea8723d1d0a7a2da63ea42a328bf4f8eb3812d7",
+                "0x40c359c942fd1ce5f8fc3effe53c8943273df95418ecadef1cd71c1c667d016"
+            ]
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x7debe525c66a929048236c8f6da5903e4f141e5cb5e6cb23e9af33ecaabe062")
+        );
+    }
+
+    // Mainnet block 630721
+    #[test]
+    fn test_declare_v3_hash() {
+        let tx = txn(json!({
+            "type": "DECLARE",
+            "version": "0x3",
+            "account_deployment_data": [],
+            "class_hash": "0x7a9d1cd5dcf3d47b10e638eb1330d101f7d471f08eff9664b42dfa90f6973bf",
+            "compiled_class_hash": "0x4fc6444f59a2fb0b2c67e1e22caba0edc3b48727e8a9cc19e59e5c3e5dc8270",
+            "fee_data_availability_mode": "L1",
+            "nonce": "0x3",
+            "nonce_data_availability_mode": "L1",
+            "paymaster_data": [],
+            "sender_address": "0x46d3a562c606077c14c3479946e4145b7a372538875eb4e635e758fcd1d2c80",
+            "signature": [
+                "0x14bdaa66720b2f7cb5d55041de921f38bca2b8133e903f446a43e78943a1d05",
+                "0x6ed9788306ec1f51157fdeeacb1094b2c1e001bcc7c4eaf31d2b66f02cdc5b0"
+            ],
+            "tip": "0x0",
+            "resource_bounds": {
+                "l1_gas": {
+                    "max_amount": "0xe38c2",
+                    "max_price_per_unit": "0x24e2649cc098"
+                },
+                "l2_gas": {
+                    "max_amount": "0x0",
+                    "max_price_per_unit": "0x0"
+                }
+            }
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x50ca961fd1b5f7f1ea1e8620ac980cc3c3fb4e434f0661c3a2d50893082b9fb")
+        );
+    }
+
+    // Mainnet block 636864
+    #[test]
+    fn test_deploy_account_v1_hash() {
+        let tx = txn(json!({
+            "type": "DEPLOY_ACCOUNT",
+            "version": "0x1",
+            "class_hash": "0x13bfe114fb1cf405bfc3a7f8dbe2d91db146c17521d40dcf57e16d6b59fa8e6",
+            "constructor_calldata": [
+                "0x54c617a2e91df5344958e0eb2c30c58a1134b3f8e59e88deba60a24f95c0a2c"
+            ],
+            "contract_address_salt": "0x54c617a2e91df5344958e0eb2c30c58a1134b3f8e59e88deba60a24f95c0a2c",
+            "max_fee": "0x3a23c71d8b9",
+            "nonce": "0x0",
+            "signature": [
+                "0x19bba00834026bc1a1045a71bfa909e6c6d41bc872b5bfa1364f1229144ac4a",
+                "0x48f9fd27ec3a8cb2420a930c72cb81e47c69790519cbc963c320ed2c9b36456",
+                "0x816dd0297efc55dc1e7559020a3a825e81ef734b558f03c83325d4da7e6253",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x534e5f4d41494e",
+                "0x1d474323258a89e5b5de7ef0bfc162f85357c8d5140388fcf33c0f93993f7cf",
+                "0x7f86bb20596368f23ca9a720e54dc05bf487f1c45b66da0d94ffab186ee1ac6"
+            ]
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x40e7ac7efc374f3d1241c6f991de2ea534d84e8be307420658353527226c5e4")
+        );
+    }
+
+    // Mainnet block 636864
+    #[test]
+    fn test_deploy_account_v3_hash() {
+        let tx = txn(json!({
+            "type": "DEPLOY_ACCOUNT",
+            "version": "0x3",
+            "class_hash": "0x13bfe114fb1cf405bfc3a7f8dbe2d91db146c17521d40dcf57e16d6b59fa8e6",
+            "constructor_calldata": [
+                "0x1f218cfa725d1679ff028c53d094a765dfd1de632787cab37ffe10f3915dcc7"
+            ],
+            "contract_address_salt": "0x1f218cfa725d1679ff028c53d094a765dfd1de632787cab37ffe10f3915dcc7",
+            "fee_data_availability_mode": "L1",
+            "nonce": "0x0",
+            "nonce_data_availability_mode": "L1",
+            "paymaster_data": [],
+            "signature": [
+                "0x516550aaa957b0f931b1f9347304bb557e29036b5056c0b97b047778c1c8fec",
+                "0x782d87c2df96241acb2d88260e6ae2f18ad36fd68bd1036cfb2166a30bb9e14",
+                "0x816dd0297efc55dc1e7559020a3a825e81ef734b558f03c83325d4da7e6253",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x0",
+                "0x534e5f4d41494e",
+                "0x3d4d482132b50ef44c8308b7102ca9a21dcaec5b24a8a00b363f6b4824d35f9",
+                "0x18099527f0007975481b3e34ea897c07d3f137117d382e49859436f8eb25140"
+            ],
+            "tip": "0x0",
+            "resource_bounds": {
+                "l1_gas": {
+                    "max_amount": "0x4c",
+                    "max_price_per_unit": "0x8ab967606cb9"
+                },
+                "l2_gas": {
+                    "max_amount": "0x0",
+                    "max_price_per_unit": "0x0"
+                }
+            }
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x25cbbc1e197e1e6dd8886b4b359c2dbe26245a282547624d7e54e7c3e2cf15")
+        );
+    }
+
+    // Mainnet block 6864
+    #[test]
+    fn test_deploy_hash() {
+        let tx = txn(json!({
+            "type": "DEPLOY",
+            "version": "0x0",
+            "class_hash": "0x25ec026985a3bf9d0cc1fe17326b245dfdc3ff89b8fde106542a3ea56c5a918",
+            "constructor_calldata": [
+                "0x3e327de1c40540b98d05cbcb13552008e36f0ec8d61d46956d2f9752c294328",
+                "0x79dc0da7c54b95f10aa182ad0a46400db63156920adb65eca2654c0945a463",
+                "0x2",
+                "0x77d0eb177b763933debc8596399528e720951662ad078752302f9c447fa59ef",
+                "0x0"
+            ],
+            "contract_address_salt": "0x77d0eb177b763933debc8596399528e720951662ad078752302f9c447fa59ef"
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x44ce170a27953fec1809b3d2a03a44ad6b475355748a5f1c01db972f20b295e")
+        );
+    }
+
+    // Mainnet block 546864
+    #[test]
+    fn test_l1_handler_hash() {
+        let tx = txn(json!({
+            "type": "L1_HANDLER",
+            "version": "0x0",
+            "calldata": [
+                "0xae0ee0a63a2ce6baeeffe56e7714fb4efe48d419",
+                "0x455448",
+                "0xc27947400e26e534e677afc2e9b2ec1bab14fc89",
+                "0x4af4754baf89f1b8b449215a8ea7ce558824a33a5393eaa3829658549f2bfa2",
+                "0x9184e72a000",
+                "0x0"
+            ],
+            "contract_address": "0x73314940630fd6dcda0d772d4c972c4e0a9946bef9dabf4ef84eda8ef542b82",
+            "entry_point_selector": "0x1b64b1b3b690b43b9b514fb81377518f4039cd3e4f4914d8a6bdf01d679fb19",
+            "nonce": "0x18e94d"
+        }));
+        assert_eq!(
+            transaction_hash_of(&tx, chain_id()).unwrap(),
+            felt("0x439e12f67962c353182d72b4af12c3f11eaba4b36e552aebcdcd6db66971bdb")
+        );
+    }
+
+    // Mainnet block 1470, hashed with a deprecated scheme
+    #[test]
+    fn test_verify_deprecated_deploy_hash() {
+        let tx = txn(json!({
+            "type": "DEPLOY",
+            "version": "0x0",
+            "class_hash": "0x71c3c99f5cf76fc19945d4b8b7d34c7c5528f22730d56192b50c6bbfd338a64",
+            "constructor_calldata": [
+                "0x5f28c66afd8a6799ddbe1933bce2c144625031aafa881fa38fa830790eff204"
+            ],
+            "contract_address_salt": "0x1cb8f5514ceac8a6abdf232cd24ea9eacfb310eaba427432784b80b5f95bc8d"
+        }));
+        let hash = Felt::try_new(
+            "0x2b30ab254aaac75326e5d6a1ed6f040ddd61902155381a754a849e9aa616153",
+        )
+        .unwrap();
+        assert!(verify(&tx, chain_id(), &hash).is_ok());
+    }
+
+    // Mainnet block 1470, hashed with a deprecated scheme
+    #[test]
+    fn test_verify_deprecated_invoke_v0_hash() {
+        let tx = txn(json!({
+            "type": "INVOKE",
+            "version": "0x0",
+            "calldata": [
+                "0x1cb8f5514ceac8a6abdf232cd24ea9eacfb310eaba427432784b80b5f95bc8d",
+                "0x0"
+            ],
+            "contract_address": "0x67b664b0ea8df2687352cbd636cd0782bf8f563a0072b233853e3c5a70b5897",
+            "entry_point_selector": "0x79dc0da7c54b95f10aa182ad0a46400db63156920adb65eca2654c0945a463",
+            "max_fee": "0x0",
+            "signature": []
+        }));
+        let hash = Felt::try_new(
+            "0x1d7b514ed5f8d37c36bbb70db9df33994e0e77bb8d6f62790894f0b56462e62",
+        )
+        .unwrap();
+        assert!(verify(&tx, chain_id(), &hash).is_ok());
+    }
+
+    // Mainnet block 1367, hashed with a deprecated scheme
+    #[test]
+    fn test_verify_deprecated_l1_handler_hash() {
+        let tx = txn(json!({
+            "type": "L1_HANDLER",
+            "version": "0x0",
+            "calldata": [
+                "0x142273bcbfca76512b2a05aed21f134c4495208",
+                "0xd9aa5ef3a8b31910ad5f26fcd782ce18bcfcfc45",
+                "0x2",
+                "0x453b0310bcdfa50d3c2e7f757e284ac6cd4171933a4e67d1bdcfdbc7f3cbc93"
+            ],
+            "contract_address": "0xda8054260ec00606197a4103eb2ef08d6c8af0b6a808b610152d1ce498f8c3",
+            "entry_point_selector": "0xe3f5e9e1456ffa52a3fbc7e8c296631d4cc2120c0be1e2829301c0d8fa026b",
+            "nonce": "0x6"
+        }));
+        let hash = Felt::try_new(
+            "0x76e77f01890b45e0b0e90e50e5e30cf6e401e7110ffb67ca9a55716d9bc1c0e",
+        )
+        .unwrap();
+        assert!(verify(&tx, chain_id(), &hash).is_ok());
+    }
+
+    #[test]
+    fn test_verify_invalid_hash() {
+        let hash = Felt::try_new("0x1").unwrap();
+        assert!(verify(&signed(&[]), chain_id(), &hash).is_err());
+    }
+
+    // Vectors from the reference implementation (starknet_api)
+    #[test]
+    fn test_transaction_leaf() {
+        let hash = FieldElement::ONE;
+        assert_eq!(
+            transaction_leaf(&signed(&["0x2", "0x3"]), hash, [0, 13, 2, 0])
+                .unwrap(),
+            felt("0x2f0d8840bcf3bc629598d8a6cc80cb7c0d9e52d93dab244bbf9cd0dca0ad082")
+        );
+        assert_eq!(
+            transaction_leaf(&signed(&[]), hash, [0, 13, 2, 0]).unwrap(),
+            felt("0xa93bf5e58b9378d093aa86ddc2f61a3295a1d1e665bd0ef3384dd07b30e033")
+        );
+    }
+
+    #[test]
+    fn test_transaction_commitment() {
+        let txn = signed(&["0x2", "0x3"]);
+        let hash = Felt::try_new("0x1").unwrap();
+        assert_eq!(
+            transaction_commitment(
+                [(&txn, &hash), (&txn, &hash)],
+                [0, 13, 2, 0]
+            )
+            .unwrap(),
+            felt("0x282b635972328bd1cfa86496fe920d20bd9440cd78ee8dc90ae2b383d664dcf")
+        );
+    }
+}
@@ -0,0 +1,460 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use eyre::Result;
+use tokio::sync::RwLock;
+
+use crate::{
+    client::{State, LINEAGE_WALK_LIMIT},
+    exe::cache,
+    feeder::GatewayClient,
+    gen::{BlockHash, BlockId, Felt},
+    hash::block::BlockHeader,
+};
+
+// Blocks of the canonical chain kept to find the common ancestor of a new head
+const TRACKED_BLOCKS: usize = 128;
+
+// Reorgs kept for inspection
+const TRACKED_REORGS: usize = 16;
+
+/// Verified headers that are available without asking the gateway (e.g. the
+/// ones stored on disk).
+pub trait KnownHeaders: Send + Sync {
+    /// Hash and parent hash of the block, when it is known.
+    fn link(&self, block_number: u64) -> Option<(Felt, Felt)>;
+}
+
+// Headers fetched by an earlier walk (e.g. the one linking the head to the
+// checkpoint), so that the tracker does not fetch them again.
+impl KnownHeaders for Vec<BlockHeader> {
+    fn link(&self, block_number: u64) -> Option<(Felt, Felt)> {
+        let header =
+            self.iter().find(|header| header.block_number == block_number)?;
+        let block_hash = Felt::try_new(&format!("{:#x}", header.block_hash));
+        let parent_hash = Felt::try_new(&format!("{:#x}", header.parent_hash));
+        Some((block_hash.ok()?, parent_hash.ok()?))
+    }
+}
+
+/// Blocks passed by a walk along the parent links, and where it stopped.
+#[derive(Debug, Default)]
+pub struct Walk {
+    /// Headers fetched from the gateway, highest first.
+    pub fetched: Vec<BlockHeader>,
+    /// Blocks passed, fetched or known, highest first.
+    pub passed: Vec<(u64, Felt)>,
+    /// The block the walk stopped at: the one accepted by `stop`, or the next
+    /// one to fetch when the limit was reached. `None` past the genesis.
+    pub end: Option<(u64, Felt)>,
+}
+
+/// Follow the parent links down from the given block until `stop` accepts a
+/// block. Each header is taken from `known` when it is there and fetched from
+/// the gateway otherwise, and it must be the very block that was asked for,
+/// so only verified links are followed. At most `limit` headers are fetched.
+pub async fn walk_parents(
+    gateway: &GatewayClient,
+    known: Option<&dyn KnownHeaders>,
+    (mut block_number, mut block_hash): (u64, Felt),
+    limit: usize,
+    mut stop: impl FnMut(u64, &Felt) -> bool,
+) -> Result<Walk> {
+    let mut walk = Walk::default();
+    loop {
+        if stop(block_number, &block_hash) {
+            break;
+        }
+        let parent_hash = match known.and_then(|known| known.link(block_number))
+        {
+            Some((hash, parent_hash))
+                if hash.as_ref() == block_hash.as_ref() =>
+            {
+                parent_hash
+            }
+            _ if walk.fetched.len() == limit => break,
+            _ => {
+                let header =
+                    fetch_header(gateway, block_number, &block_hash).await?;
+                let parent_hash =
+                    Felt::try_new(&format!("{:#x}", header.parent_hash))?;
+                walk.fetched.push(header);
+                parent_hash
+            }
+        };
+        walk.passed.push((block_number, block_hash));
+        if block_number == 0 {
+            return Ok(walk);
+        }
+        block_number -= 1;
+        block_hash = parent_hash;
+    }
+    walk.end = Some((block_number, block_hash));
+    Ok(walk)
+}
+
+/// Fetch the header of the block by its hash and check that the gateway
+/// returned that very block.
+async fn fetch_header(
+    gateway: &GatewayClient,
+    block_number: u64,
+    block_hash: &Felt,
+) -> Result<BlockHeader> {
+    let block_id =
+        BlockId::BlockHash { block_hash: BlockHash(block_hash.clone()) };
+    let header = gateway.get_header(&block_id).await?;
+    if header.block_number != block_number {
+        eyre::bail!(
+            "Block number mismatch: expected={} actual={}",
+            block_number,
+            header.block_number
+        );
+    }
+    if format!("{:#x}", header.block_hash) != block_hash.as_ref().to_lowercase()
+    {
+        eyre::bail!(
+            "Block hash mismatch: expected={} actual={:#x}",
+            block_hash.as_ref(),
+            header.block_hash
+        );
+    }
+    Ok(header)
+}
+
+/// Chain reorganization: the blocks above the common ancestor were replaced
+/// by the chain leading to the new head.
+#[derive(Debug, Clone)]
+pub struct Reorg {
+    pub ancestor: u64,
+    pub dropped: Vec<(u64, Felt)>,
+    pub head: State,
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    Unchanged,
+    Advanced(State),
+    Reorg(Reorg),
+}
+
+/// Follows the head of the chain by verified parent links. The head moves
+/// forward when the new head extends it, and is replaced (possibly by a lower
+/// block) only with an explicit reorg.
+#[derive(Debug)]
+pub struct HeadTracker {
+    head: State,
+    blocks: BTreeMap<u64, Felt>,
+    reorgs: Arc<RwLock<Vec<Reorg>>>,
+}
+
+impl HeadTracker {
+    pub fn new(head: State) -> Self {
+        let blocks =
+            BTreeMap::from([(head.block_number, head.block_hash.clone())]);
+        Self { head, blocks, reorgs: Default::default() }
+    }
+
+    pub fn head(&self) -> &State {
+        &self.head
+    }
+
+    /// Most recent reorgs, the latest one last. The list is shared, so it can
+    /// be read while the tracker is being updated elsewhere.
+    pub fn reorgs(&self) -> Arc<RwLock<Vec<Reorg>>> {
+        self.reorgs.clone()
+    }
+
+    /// Move to the new head. When the new head does not descend from any of
+    /// the tracked blocks (a reorg deeper than the tracked blocks), the
+    /// tracker is reset to the new head on top of `anchor`: a trusted block
+    /// (the checkpoint or the state finalized on L1) that the new head is
+    /// already known to descend from. Without an anchor such a head fails.
+    /// Headers are taken from `known` when they are there.
+    pub async fn update(
+        &mut self,
+        gateway: &GatewayClient,
+        known: Option<&dyn KnownHeaders>,
+        head: State,
+        anchor: Option<&State>,
+    ) -> Result<Update> {
+        if head == self.head {
+            return Ok(Update::Unchanged);
+        }
+        if self.is_known(head.block_number, &head.block_hash) {
+            // A lagging provider returned a block of the canonical chain
+            tracing::warn!(
+                head = self.head.block_number,
+                received = head.block_number,
+                "stale head ignored"
+            );
+            return Ok(Update::Unchanged);
+        }
+
+        let oldest = self.blocks.keys().next().copied().unwrap_or_default();
+        let walk = walk_parents(
+            gateway,
+            known,
+            (head.block_number, head.block_hash.clone()),
+            LINEAGE_WALK_LIMIT,
+            |block_number, block_hash| {
+                block_number <= oldest
+                    || self.is_known(block_number, block_hash)
+            },
+        )
+        .await?;
+        let ancestor = match (walk.end, anchor) {
+            (Some((block_number, block_hash)), _)
+                if self.is_known(block_number, &block_hash) =>
+            {
+                block_number
+            }
+            (_, Some(anchor)) if anchor.block_number <= head.block_number => {
+                tracing::warn!(
+                    head = head.block_number,
+                    oldest,
+                    anchor = anchor.block_number,
+                    "no common ancestor, tracker reset to the anchor"
+                );
+                let mut blocks = BTreeMap::from([(
+                    anchor.block_number,
+                    anchor.block_hash.clone(),
+                )]);
+                std::mem::swap(&mut self.blocks, &mut blocks);
+                // Tracked blocks below the anchor are dropped too, but they
+                // are not reported: the chain below the anchor is trusted
+                let dropped = blocks.split_off(&(anchor.block_number + 1));
+                return self
+                    .advance(head, anchor.block_number, dropped, walk.passed)
+                    .await;
+            }
+            _ => eyre::bail!(
+                "No common ancestor with the new head: head={} oldest={}",
+                head.block_number,
+                oldest
+            ),
+        };
+        let dropped = self.blocks.split_off(&(ancestor + 1));
+        self.advance(head, ancestor, dropped, walk.passed).await
+    }
+
+    async fn advance(
+        &mut self,
+        head: State,
+        ancestor: u64,
+        dropped: BTreeMap<u64, Felt>,
+        passed: Vec<(u64, Felt)>,
+    ) -> Result<Update> {
+        let dropped = dropped.into_iter().collect::<Vec<_>>();
+        self.blocks.extend(passed);
+        while self.blocks.len() > TRACKED_BLOCKS {
+            self.blocks.pop_first();
+        }
+        self.head = head.clone();
+
+        if dropped.is_empty() {
+            return Ok(Update::Advanced(head));
+        }
+        for (_, block_hash) in &dropped {
+            cache::invalidate(block_hash);
+        }
+        let reorg = Reorg { ancestor, dropped, head };
+        tracing::warn!(
+            ancestor,
+            dropped = reorg.dropped.len(),
+            head = reorg.head.block_number,
+            "reorg detected"
+        );
+        let mut reorgs = self.reorgs.write().await;
+        reorgs.push(reorg.clone());
+        if reorgs.len() > TRACKED_REORGS {
+            reorgs.remove(0);
+        }
+        Ok(Update::Reorg(reorg))
+    }
+
+    fn is_known(&self, block_number: u64, block_hash: &Felt) -> bool {
+        self.blocks
+            .get(&block_number)
+            .map(|known| known.as_ref() == block_hash.as_ref())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use starknet_crypto::Felt as FieldElement;
+    use wiremock::{
+        matchers::{path, query_param},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+    use crate::config::MAINNET_GATEWAY_PUBKEY;
+
+    fn header(block_number: u64, parent_hash: FieldElement) -> BlockHeader {
+        let mut header = BlockHeader {
+            block_number,
+            parent_hash,
+            state_root: FieldElement::from(block_number),
+            sequencer_address: FieldElement::from_hex_unchecked("0x6"),
+            timestamp: 1,
+            starknet_version: "0.13.1".to_owned(),
+            ..Default::default()
+        };
+        header.block_hash = header.compute_hash();
+        header
+    }
+
+    fn state(header: &BlockHeader) -> State {
+        State::try_from(header).unwrap()
+    }
+
+    async fn mount(server: &MockServer, header: &BlockHeader) {
+        let block_hash = format!("{:#x}", header.block_hash);
+        Mock::given(path("/feeder_gateway/get_block"))
+            .and(query_param("blockHash", block_hash.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "block_number": header.block_number,
+                    "block_hash": block_hash,
+                    "parent_block_hash": format!("{:#x}", header.parent_hash),
+                    "state_root": format!("{:#x}", header.state_root),
+                    "sequencer_address": "0x6",
+                    "timestamp": header.timestamp,
+                    "starknet_version": "0.13.1",
+                    "status": "ACCEPTED_ON_L2",
+                    "transactions": [],
+                    "transaction_receipts": []
+                }),
+            ))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_head_tracker() {
+        let block_10 = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, block_10.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+        let fork_11 = header(11, block_10.block_hash + FieldElement::ONE);
+        let mut uncle_11 = header(11, block_10.block_hash);
+        uncle_11.timestamp = 2;
+        uncle_11.block_hash = uncle_11.compute_hash();
+
+        let server = MockServer::start().await;
+        for header in [&block_11, &block_12, &fork_11, &uncle_11] {
+            mount(&server, header).await;
+        }
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let mut tracker = HeadTracker::new(state(&block_10));
+        let update = tracker
+            .update(&gateway, None, state(&block_12), None)
+            .await
+            .unwrap();
+        assert!(
+            matches!(update, Update::Advanced(head) if head.block_number == 12)
+        );
+
+        // The head never moves backwards along the canonical chain
+        let update = tracker
+            .update(&gateway, None, state(&block_11), None)
+            .await
+            .unwrap();
+        assert!(matches!(update, Update::Unchanged));
+        assert_eq!(tracker.head().block_number, 12);
+
+        // A lower block on a competing branch replaces the head by a reorg
+        let update = tracker
+            .update(&gateway, None, state(&uncle_11), None)
+            .await
+            .unwrap();
+        let Update::Reorg(reorg) = update else {
+            panic!("reorg expected");
+        };
+        assert_eq!(reorg.ancestor, 10);
+        assert_eq!(
+            reorg.dropped.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            vec![11, 12]
+        );
+        assert_eq!(tracker.head().block_number, 11);
+        assert_eq!(tracker.reorgs().read().await.len(), 1);
+
+        // The parent of the new head is not on the tracked chain
+        assert!(tracker
+            .update(&gateway, None, state(&fork_11), None)
+            .await
+            .is_err());
+        assert_eq!(tracker.head().block_number, 11);
+    }
+
+    #[tokio::test]
+    async fn test_head_tracker_known_headers() {
+        let block_10 = header(10, FieldElement::from(9u64));
+        let block_11 = header(11, block_10.block_hash);
+        let block_12 = header(12, block_11.block_hash);
+
+        // Nothing is mounted: every header has to come from the known ones
+        let server = MockServer::start().await;
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        let known = vec![block_12.clone(), block_11];
+        let mut tracker = HeadTracker::new(state(&block_10));
+        let update = tracker
+            .update(&gateway, Some(&known), state(&block_12), None)
+            .await
+            .unwrap();
+        assert!(
+            matches!(update, Update::Advanced(head) if head.block_number == 12)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_head_tracker_reset() {
+        let block_10 = header(10, FieldElement::from(9u64));
+        let fork_11 = header(11, block_10.block_hash + FieldElement::ONE);
+        let fork_12 = header(12, fork_11.block_hash);
+
+        let server = MockServer::start().await;
+        for header in [&fork_11, &fork_12] {
+            mount(&server, header).await;
+        }
+        let gateway =
+            GatewayClient::new(&server.uri(), MAINNET_GATEWAY_PUBKEY).unwrap();
+
+        // The new head forked below the oldest tracked block
+        let mut tracker = HeadTracker::new(state(&block_10));
+        let update =
+            tracker.update(&gateway, None, state(&fork_11), None).await;
+        assert!(update.is_err());
+        assert_eq!(tracker.head().block_number, 10);
+
+        // The trusted anchor takes the place of the common ancestor
+        let felt = Felt::try_new("0x9").unwrap();
+        let anchor = State::new(9, felt.clone(), felt);
+        let update = tracker
+            .update(&gateway, None, state(&fork_11), Some(&anchor))
+            .await
+            .unwrap();
+        let Update::Reorg(reorg) = update else {
+            panic!("reorg expected");
+        };
+        assert_eq!(reorg.ancestor, 9);
+        assert_eq!(
+            reorg.dropped.iter().map(|(n, _)| *n).collect::<Vec<_>>(),
+            vec![10]
+        );
+        assert_eq!(tracker.head().block_number, 11);
+        assert_eq!(tracker.reorgs().read().await.len(), 1);
+
+        // The tracker follows the new chain from there
+        let update = tracker
+            .update(&gateway, None, state(&fork_12), None)
+            .await
+            .unwrap();
+        assert!(
+            matches!(update, Update::Advanced(head) if head.block_number == 12)
+        );
+    }
+}
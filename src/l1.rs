@@ -0,0 +1,213 @@
+use alloy_primitives::U256;
+use eyre::{Context, OptionExt, Result};
+
+use crate::{client::State, gen::Felt};
+
+pub const MAINNET_CORE_CONTRACT: &str =
+    "0xc662c410C0ECf747543f5bA90660f6ABeBD9C8c4";
+pub const SEPOLIA_CORE_CONTRACT: &str =
+    "0xE2Bb56ee936fd6433DC0F6e7e3b8365C906AA057";
+
+// Selectors of the Starknet core contract getters (keccak of the signature)
+const STATE_ROOT: &str = "0x9588eca2"; // stateRoot()
+const STATE_BLOCK_NUMBER: &str = "0x35befa5d"; // stateBlockNumber()
+const STATE_BLOCK_HASH: &str = "0x382d83e3"; // stateBlockHash()
+
+/// Client reading the Starknet state committed to the core contract on L1.
+pub struct EthereumClient {
+    url: String,
+    contract: String,
+    client: reqwest::Client,
+}
+
+impl EthereumClient {
+    pub fn new(url: &str, contract: &str) -> Self {
+        Self {
+            url: url.to_owned(),
+            contract: contract.to_owned(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Return the latest Starknet state that is finalized on Ethereum.
+    pub async fn get_state(&self) -> Result<State> {
+        // All the getters read the same L1 block, so that the values belong
+        // to the same state update even if a new one is finalized meanwhile
+        let block = self.finalized_block().await?;
+        let block_number = self.call(STATE_BLOCK_NUMBER, &block).await?;
+        let block_number: u64 = block_number
+            .try_into()
+            .map_err(|_| eyre::eyre!("ethereum: invalid block number"))?;
+        let block_hash = self.call(STATE_BLOCK_HASH, &block).await?;
+        let root = self.call(STATE_ROOT, &block).await?;
+        Ok(State::new(block_number, as_felt(block_hash)?, as_felt(root)?))
+    }
+
+    async fn finalized_block(&self) -> Result<String> {
+        let params = serde_json::json!(["finalized", false]);
+        let block = self.request("eth_getBlockByNumber", params).await?;
+        let block_number = block["number"]
+            .as_str()
+            .ok_or_eyre("ethereum: finalized block missing")?;
+        Ok(block_number.to_owned())
+    }
+
+    async fn call(&self, selector: &str, block: &str) -> Result<U256> {
+        let params = serde_json::json!([
+            {
+                "to": self.contract,
+                "data": selector,
+            },
+            block
+        ]);
+        let result = self.request("eth_call", params).await?;
+        let result = result.as_str().ok_or_eyre("ethereum: result missing")?;
+        let word = result.strip_prefix("0x").unwrap_or(result);
+        if word.len() != 64 {
+            eyre::bail!("ethereum: invalid result: {result}");
+        }
+        U256::from_str_radix(word, 16)
+            .map_err(|_| eyre::eyre!("ethereum: invalid result: {result}"))
+    }
+
+    async fn request(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let mut response: serde_json::Value = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": method,
+                "params": params,
+                "id": 0
+            }))
+            .send()
+            .await
+            .context("failed to send ethereum request")?
+            .json()
+            .await
+            .context("failed to receive ethereum response")?;
+
+        if let Some(error) = response["error"].as_object() {
+            let error = serde_json::to_string(error)?;
+            eyre::bail!("ethereum: rpc error: {error}");
+        }
+        Ok(response["result"].take())
+    }
+}
+
+fn as_felt(value: U256) -> Result<Felt> {
+    let felt = Felt::try_new(&format!("{value:#x}"))?;
+    Ok(felt)
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::{
+        matchers::{body_string_contains, method},
+        Mock, MockServer, ResponseTemplate,
+    };
+
+    use super::*;
+
+    const BLOCK_NUMBER: u64 = 1056300;
+    const BLOCK_HASH: &str =
+        "0x5a3e3e5b59b1a0d3b2fa5d8e6a5e2b5a28e1a07c5b39f92fc1ab5ecde4b4e27";
+    const ROOT: &str =
+        "0x4a1d5e2b0ab3cbba7fef9adf5c41a01e39a0b6f4f89ba6b7e1ff5b18d2e3c1c";
+    const FINALIZED_BLOCK: &str = "0x13a0f2c";
+
+    fn word(value: &str) -> String {
+        let value = value.trim_start_matches("0x");
+        format!("0x{value:0>64}")
+    }
+
+    async fn mock_finalized_block(mock: &MockServer) {
+        Mock::given(method("POST"))
+            .and(body_string_contains("eth_getBlockByNumber"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": { "number": FINALIZED_BLOCK }
+                }),
+            ))
+            .expect(1)
+            .mount(mock)
+            .await;
+    }
+
+    // Only the calls made at the finalized block are answered
+    async fn mock_call(mock: &MockServer, selector: &str, result: String) {
+        Mock::given(method("POST"))
+            .and(body_string_contains(selector))
+            .and(body_string_contains(FINALIZED_BLOCK))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "result": result
+                }),
+            ))
+            .mount(mock)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_get_state() -> Result<()> {
+        let mock = MockServer::start().await;
+        mock_finalized_block(&mock).await;
+        mock_call(&mock, STATE_ROOT, word(ROOT)).await;
+        mock_call(&mock, STATE_BLOCK_HASH, word(BLOCK_HASH)).await;
+        mock_call(
+            &mock,
+            STATE_BLOCK_NUMBER,
+            word(&format!("{BLOCK_NUMBER:x}")),
+        )
+        .await;
+
+        let client = EthereumClient::new(&mock.uri(), MAINNET_CORE_CONTRACT);
+        let state = client.get_state().await?;
+
+        assert_eq!(state.block_number, BLOCK_NUMBER);
+        assert_eq!(state.block_hash.as_ref(), BLOCK_HASH);
+        assert_eq!(state.root.as_ref(), ROOT);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_result() -> Result<()> {
+        let mock = MockServer::start().await;
+        mock_finalized_block(&mock).await;
+        mock_call(&mock, STATE_BLOCK_NUMBER, "0x2a".to_owned()).await;
+
+        let client = EthereumClient::new(&mock.uri(), MAINNET_CORE_CONTRACT);
+        assert!(client.get_state().await.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rpc_error() -> Result<()> {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": 0,
+                    "error": {
+                        "code": -32000,
+                        "message": "execution reverted"
+                    }
+                }),
+            ))
+            .mount(&mock)
+            .await;
+
+        let client = EthereumClient::new(&mock.uri(), MAINNET_CORE_CONTRACT);
+        assert!(client.get_state().await.is_err());
+        Ok(())
+    }
+}
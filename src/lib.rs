@@ -6,9 +6,15 @@ pub mod feeder;
 #[cfg(not(tarpaulin_include))] // exclude from code-coverage report
 pub mod gen;
 
+pub mod hash;
+pub mod head;
+
+pub mod l1;
 pub mod proof;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod rpc;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod store;
 
 pub mod util;
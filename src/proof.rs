@@ -1,11 +1,12 @@
+use bitvec::prelude::{BitVec, Msb0};
 use iamgroot::jsonrpc;
 use starknet_crypto::{
-    pedersen_hash, poseidon_hash_many, Felt as FieldElement,
+    pedersen_hash, poseidon_hash, poseidon_hash_many, Felt as FieldElement,
 };
 
 use crate::gen::{
     Address, BinaryNode, BinaryNodeBinary, ContractData, EdgeNode,
-    EdgeNodeEdge, Felt, GetProofResult, Node, StorageKey,
+    EdgeNodeEdge, Felt, GetClassProofResult, GetProofResult, Node, StorageKey,
 };
 
 use crate::util::{felt_from_bits, felt_to_bits};
@@ -34,42 +35,177 @@ impl GetProofResult {
         key: StorageKey,
         value: Felt,
     ) -> Result<(), jsonrpc::Error> {
-        let contract_data = self.contract_data.as_ref().ok_or(
-            jsonrpc::Error::new(-32700, "No contract data found".to_string()),
-        )?;
-        self.verify_storage_proofs(contract_data, key, value)?;
+        self.verify_many(global_root, contract_address, &[key], &[value])
+    }
+
+    /// Verify the values of many storage keys of the same contract (in the
+    /// order they were requested) with a single proof.
+    pub fn verify_many(
+        &self,
+        global_root: Felt,
+        contract_address: Address,
+        keys: &[StorageKey],
+        values: &[Felt],
+    ) -> Result<(), jsonrpc::Error> {
+        let Some(contract_data) = self.contract_data.as_ref() else {
+            // all storage of a contract that is not deployed is empty
+            if let Some(value) = values.iter().find(|value| {
+                FieldElement::from_hex(value.as_ref()).ok()
+                    != Some(FieldElement::ZERO)
+            }) {
+                return Err(jsonrpc::Error::new(
+                    -32700,
+                    format!(
+                        "No contract data found for value -> {}",
+                        value.as_ref()
+                    ),
+                ));
+            }
+            return self.verify_contract_leaf(
+                Felt::try_new("0x0")?,
+                global_root,
+                contract_address,
+            );
+        };
+        self.verify_storage_proofs(contract_data, keys, values)?;
         self.verify_contract_proof(contract_data, global_root, contract_address)
     }
 
+    /// Read the values of the storage keys (in the order they were requested)
+    /// from the leaves of the storage proofs, so that they do not have to be
+    /// fetched one by one. The values still have to be verified.
+    pub fn storage_values(
+        &self,
+        keys: &[StorageKey],
+    ) -> Result<Vec<Felt>, jsonrpc::Error> {
+        let Some(contract_data) = self.contract_data.as_ref() else {
+            // all storage of a contract that is not deployed is empty
+            return Ok(vec![Felt::try_new("0x0")?; keys.len()]);
+        };
+        let storage_proofs = contract_data.storage_proofs.as_ref().ok_or(
+            jsonrpc::Error::new(-32700, "No storage proof found".to_string()),
+        )?;
+        if storage_proofs.len() != keys.len() {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Storage proofs mismatch: proofs={} keys={}",
+                    storage_proofs.len(),
+                    keys.len()
+                ),
+            ));
+        }
+        keys.iter()
+            .zip(storage_proofs.iter())
+            .map(|(key, storage_proof)| leaf_value(key.as_ref(), storage_proof))
+            .collect()
+    }
+
+    /// Verify the contract leaf (class hash, storage root and nonce) against
+    /// the global root and return the proven contract data, or `None` when
+    /// the proof shows that no contract is deployed at the address.
+    pub fn verify_contract(
+        &self,
+        global_root: Felt,
+        contract_address: Address,
+    ) -> Result<Option<&ContractData>, jsonrpc::Error> {
+        let Some(contract_data) = self.contract_data.as_ref() else {
+            self.verify_contract_leaf(
+                Felt::try_new("0x0")?,
+                global_root,
+                contract_address,
+            )?;
+            return Ok(None);
+        };
+        self.verify_contract_proof(
+            contract_data,
+            global_root,
+            contract_address,
+        )?;
+        Ok(Some(contract_data))
+    }
+
+    /// Verify the class commitment against the global root and return it.
+    /// The storage commitment is the hash of the first node in the contract
+    /// proof, so the contract itself does not need to exist.
+    pub fn verify_class_commitment(
+        &self,
+        global_root: Felt,
+    ) -> Result<Felt, jsonrpc::Error> {
+        let class_commitment = self.class_commitment.as_ref().ok_or(
+            jsonrpc::Error::new(-32700, "No class commitment".to_string()),
+        )?;
+        let root_node = self.contract_proof.first().ok_or(
+            jsonrpc::Error::new(-32700, "Empty contract proof".to_string()),
+        )?;
+        let storage_commitment = Self::calculate_node_hash(root_node)?;
+        let parsed_global_root =
+            Self::calculate_global_root(class_commitment, storage_commitment)?;
+        if global_root.as_ref() != parsed_global_root.as_ref() {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Proof invalid:\nparsed global root -> {}\nglobal root -> {}\n",
+                    parsed_global_root.as_ref(),
+                    global_root.as_ref()
+                ),
+            ));
+        }
+        Ok(class_commitment.clone())
+    }
+
     fn verify_storage_proofs(
         &self,
         contract_data: &ContractData,
-        key: StorageKey,
-        value: Felt,
+        keys: &[StorageKey],
+        values: &[Felt],
     ) -> Result<(), jsonrpc::Error> {
         let root = &contract_data.root;
-        let storage_proofs = &contract_data.storage_proofs.as_ref().ok_or(
+        let storage_proofs = contract_data.storage_proofs.as_ref().ok_or(
             jsonrpc::Error::new(-32700, "No storage proof found".to_string()),
-        )?[0];
+        )?;
+        if storage_proofs.len() != keys.len() || keys.len() != values.len() {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Storage proofs mismatch: proofs={} keys={} values={}",
+                    storage_proofs.len(),
+                    keys.len(),
+                    values.len()
+                ),
+            ));
+        }
 
-        match Self::parse_proof(key.as_ref(), value, storage_proofs)? {
-            Some(computed_root) if computed_root.as_ref() == root.as_ref() => {
-                Ok(())
-            }
-            Some(computed_root)  => {
-                Err(jsonrpc::Error::new(
+        for ((key, value), storage_proof) in
+            keys.iter().zip(values.iter()).zip(storage_proofs.iter())
+        {
+            let computed_root = match Self::parse_proof(
+                key.as_ref(),
+                value.clone(),
+                storage_proof,
+            )? {
+                Some(computed_root) => computed_root,
+                None => {
+                    return Err(jsonrpc::Error::new(
+                        -32700,
+                        format!(
+                            "Proof invalid for root -> {}\n",
+                            root.as_ref()
+                        ),
+                    ))
+                }
+            };
+            if computed_root.as_ref() != root.as_ref() {
+                return Err(jsonrpc::Error::new(
                     -32700,
                     format!(
                         "Proof invalid:\nprovided-root -> {}\ncomputed-root -> {}\n",
                         root.as_ref(), computed_root.as_ref()
                     ),
-                ))
-            },
-            None => Err(jsonrpc::Error::new(
-                -32700,
-                format!("Proof invalid for root -> {}\n", root.as_ref()),
-            )),
+                ));
+            }
         }
+        Ok(())
     }
 
     fn verify_contract_proof(
@@ -79,7 +215,16 @@ impl GetProofResult {
         contract_address: Address,
     ) -> Result<(), jsonrpc::Error> {
         let state_hash = Self::calculate_contract_state_hash(contract_data)?;
+        self.verify_contract_leaf(state_hash, global_root, contract_address)
+    }
 
+    // A zero leaf proves that no contract is deployed at the address.
+    fn verify_contract_leaf(
+        &self,
+        state_hash: Felt,
+        global_root: Felt,
+        contract_address: Address,
+    ) -> Result<(), jsonrpc::Error> {
         match Self::parse_proof(
             contract_address.0.as_ref(),
             state_hash,
@@ -205,119 +350,344 @@ impl GetProofResult {
         })
     }
 
+    fn calculate_node_hash(node: &Node) -> Result<Felt, jsonrpc::Error> {
+        let felt = |felt: &Felt| {
+            FieldElement::from_hex(felt.as_ref()).map_err(|_| {
+                jsonrpc::Error::new(
+                    -32701,
+                    "Failed to create Field Element".to_string(),
+                )
+            })
+        };
+        let hash = match node {
+            Node::EdgeNode(EdgeNode { edge: EdgeNodeEdge { child, path } }) => {
+                pedersen_hash(&felt(child)?, &felt(&path.value)?)
+                    + FieldElement::from(path.len as u64)
+            }
+            Node::BinaryNode(BinaryNode {
+                binary: BinaryNodeBinary { left, right },
+            }) => pedersen_hash(&felt(left)?, &felt(right)?),
+        };
+        Felt::try_new(&format!("0x{:x}", hash)).map_err(|_| {
+            jsonrpc::Error::new(
+                -32701,
+                "Failed to create Field Element".to_string(),
+            )
+        })
+    }
+
     fn parse_proof(
         key: impl Into<String>,
         value: Felt,
         proof: &[Node],
     ) -> Result<Option<Felt>, jsonrpc::Error> {
-        let key = FieldElement::from_hex(&key.into()).map_err(|_| {
+        parse_proof(key, value, proof, pedersen_hash)
+    }
+}
+
+impl GetClassProofResult {
+    /// Verify that the class trie under the (already proven) class commitment
+    /// maps the class hash to the given compiled class hash.
+    pub fn verify(
+        &self,
+        class_commitment: Felt,
+        class_hash: Felt,
+        compiled_class_hash: Felt,
+    ) -> Result<(), jsonrpc::Error> {
+        let leaf = Self::calculate_class_leaf_hash(compiled_class_hash)?;
+        self.verify_leaf(class_commitment, class_hash, leaf)
+    }
+
+    /// Verify that the class hash has no leaf in the class trie: the class
+    /// is either not declared or a Cairo 0 class (which is never compiled).
+    pub fn verify_absent(
+        &self,
+        class_commitment: Felt,
+        class_hash: Felt,
+    ) -> Result<(), jsonrpc::Error> {
+        self.verify_leaf(class_commitment, class_hash, Felt::try_new("0x0")?)
+    }
+
+    fn verify_leaf(
+        &self,
+        class_commitment: Felt,
+        class_hash: Felt,
+        leaf: Felt,
+    ) -> Result<(), jsonrpc::Error> {
+        if self.class_commitment.as_ref() != class_commitment.as_ref() {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Class commitment invalid:\nprovided -> {}\nexpected -> {}\n",
+                    self.class_commitment.as_ref(),
+                    class_commitment.as_ref()
+                ),
+            ));
+        }
+        match parse_proof(
+            class_hash.as_ref(),
+            leaf,
+            &self.class_proof,
+            |x, y| poseidon_hash(*x, *y),
+        )? {
+            Some(computed_root)
+                if computed_root.as_ref() == class_commitment.as_ref() =>
+            {
+                Ok(())
+            }
+            Some(computed_root) => Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Proof invalid:\nprovided-root -> {}\ncomputed-root -> {}\n",
+                    class_commitment.as_ref(),
+                    computed_root.as_ref()
+                ),
+            )),
+            None => Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Proof invalid for root -> {}\n",
+                    class_commitment.as_ref()
+                ),
+            )),
+        }
+    }
+
+    fn calculate_class_leaf_hash(
+        compiled_class_hash: Felt,
+    ) -> Result<Felt, jsonrpc::Error> {
+        // The class leaf is defined as H(CONTRACT_CLASS_LEAF_V0, compiled_class_hash)
+        let leaf_ver =
+            FieldElement::from_bytes_be_slice(b"CONTRACT_CLASS_LEAF_V0");
+        let hash = poseidon_hash(
+            leaf_ver,
+            FieldElement::from_hex(compiled_class_hash.as_ref()).map_err(
+                |_| {
+                    jsonrpc::Error::new(
+                        -32701,
+                        "Failed to create Field Element".to_string(),
+                    )
+                },
+            )?,
+        );
+        Felt::try_new(&format!("0x{:x}", hash)).map_err(|_| {
             jsonrpc::Error::new(
                 -32701,
                 "Failed to create Field Element".to_string(),
             )
-        })?;
-        let key = felt_to_bits(&key.to_bytes_be());
-        if key.len() != 251 {
-            return Ok(None);
-        }
-        let value = FieldElement::from_hex(value.as_ref()).map_err(|_| {
+        })
+    }
+}
+
+/// Follow the proof from the root along the path of the key and return the
+/// leaf it ends in: the child of the node at the bottom of the trie, or zero
+/// when an edge leaves the path of the key (or the trie is empty).
+fn leaf_value(key: &str, proof: &[Node]) -> Result<Felt, jsonrpc::Error> {
+    let felt = |felt: &str| {
+        FieldElement::from_hex(felt).map_err(|_| {
             jsonrpc::Error::new(
                 -32701,
                 "Failed to create Field Element".to_string(),
             )
-        })?;
-        // initialized to the value so if the last node
-        // in the proof is a binary node we can still verify
-        let (mut hold, mut path_len) = (value, 0);
-        // reverse the proof in order to hash from the leaf towards the root
-        for (i, node) in proof.iter().rev().enumerate() {
-            match node {
-                Node::EdgeNode(EdgeNode {
-                    edge: EdgeNodeEdge { child, path },
-                }) => {
-                    // calculate edge hash given by provider
-                    let child_felt = FieldElement::from_hex(child.as_ref())
-                        .map_err(|_| {
-                            jsonrpc::Error::new(
-                                -32701,
-                                "Failed to create Field Element".to_string(),
-                            )
-                        })?;
-                    let path_value = FieldElement::from_hex(
-                        path.value.as_ref(),
-                    )
+        })
+    };
+    let invalid = || {
+        jsonrpc::Error::new(-32700, format!("Proof invalid for key -> {key}"))
+    };
+    let key = felt_to_bits(&felt(key)?.to_bytes_be());
+    if key.len() != 251 {
+        return Err(invalid());
+    }
+    let (mut depth, mut leaf) = (0, None);
+    for node in proof {
+        if leaf.is_some() {
+            return Err(invalid());
+        }
+        let child = match node {
+            Node::EdgeNode(EdgeNode { edge: EdgeNodeEdge { child, path } }) => {
+                let len = path.len as usize;
+                if depth + len > 251 {
+                    return Err(invalid());
+                }
+                let mut bits = BitVec::<u8, Msb0>::repeat(false, 251);
+                bits[251 - len..].copy_from_bitslice(&key[depth..depth + len]);
+                let key_path =
+                    felt_from_bits(&bits, None).map_err(|_| invalid())?;
+                if key_path != felt(path.value.as_ref())? {
+                    // the key is not in the trie
+                    return Felt::try_new("0x0");
+                }
+                depth += len;
+                child
+            }
+            Node::BinaryNode(BinaryNode {
+                binary: BinaryNodeBinary { left, right },
+            }) => {
+                if depth == 251 {
+                    return Err(invalid());
+                }
+                let child = match Direction::from(key[depth]) {
+                    Direction::Left => left,
+                    Direction::Right => right,
+                };
+                depth += 1;
+                child
+            }
+        };
+        if depth == 251 {
+            leaf = Some(child.clone());
+        }
+    }
+    match leaf {
+        Some(value) => Ok(value),
+        None if proof.is_empty() => Felt::try_new("0x0"),
+        None => Err(invalid()),
+    }
+}
+
+/// Walk the proof from the leaf towards the root, hashing trie nodes with
+/// `hash` (Pedersen for the contract and storage tries, Poseidon for the
+/// class trie), and return the computed root. A zero value is proven by
+/// an edge node whose path diverges from the key (non-membership).
+fn parse_proof(
+    key: impl Into<String>,
+    value: Felt,
+    proof: &[Node],
+    hash: fn(&FieldElement, &FieldElement) -> FieldElement,
+) -> Result<Option<Felt>, jsonrpc::Error> {
+    let key = FieldElement::from_hex(&key.into()).map_err(|_| {
+        jsonrpc::Error::new(
+            -32701,
+            "Failed to create Field Element".to_string(),
+        )
+    })?;
+    let key = felt_to_bits(&key.to_bytes_be());
+    if key.len() != 251 {
+        return Ok(None);
+    }
+    let value = FieldElement::from_hex(value.as_ref()).map_err(|_| {
+        jsonrpc::Error::new(
+            -32701,
+            "Failed to create Field Element".to_string(),
+        )
+    })?;
+    // initialized to the value so if the last node
+    // in the proof is a binary node we can still verify
+    let (mut hold, mut path_len) = (value, 0);
+    // reverse the proof in order to hash from the leaf towards the root
+    for (i, node) in proof.iter().rev().enumerate() {
+        match node {
+            Node::EdgeNode(EdgeNode { edge: EdgeNodeEdge { child, path } }) => {
+                // calculate edge hash given by provider
+                let child_felt = FieldElement::from_hex(child.as_ref())
                     .map_err(|_| {
                         jsonrpc::Error::new(
                             -32701,
                             "Failed to create Field Element".to_string(),
                         )
                     })?;
-                    let provided_hash = pedersen_hash(&child_felt, &path_value)
-                        + FieldElement::from(path.len as u64);
-                    if i == 0 {
-                        // mask storage key
-                        let computed_hash = match felt_from_bits(
-                            &key,
-                            Some(251 - path.len as usize),
-                        ) {
-                            Ok(masked_key) => {
-                                pedersen_hash(&value, &masked_key)
-                                    + FieldElement::from(path.len as u64)
+                let path_value = FieldElement::from_hex(path.value.as_ref())
+                    .map_err(|_| {
+                        jsonrpc::Error::new(
+                            -32701,
+                            "Failed to create Field Element".to_string(),
+                        )
+                    })?;
+                let provided_hash = hash(&child_felt, &path_value)
+                    + FieldElement::from(path.len as u64);
+                if i == 0 && value == FieldElement::ZERO {
+                    // non-membership: the last edge must diverge from the key
+                    let depth: usize = proof
+                        .iter()
+                        .rev()
+                        .skip(1)
+                        .map(|node| match node {
+                            Node::EdgeNode(EdgeNode { edge }) => {
+                                edge.path.len as usize
                             }
-                            Err(_) => return Ok(None),
-                        };
-                        // verify computed hash against provided hash
-                        if provided_hash != computed_hash {
-                            return Ok(None);
-                        };
+                            Node::BinaryNode(_) => 1,
+                        })
+                        .sum();
+                    let len = path.len as usize;
+                    if depth + len > 251 {
+                        return Ok(None);
+                    }
+                    let mut bits = BitVec::<u8, Msb0>::repeat(false, 251);
+                    bits[251 - len..]
+                        .copy_from_bitslice(&key[depth..depth + len]);
+                    match felt_from_bits(&bits, None) {
+                        Ok(key_path) if key_path != path_value => (),
+                        _ => return Ok(None),
                     }
 
-                    // walk up the remaining path
-                    path_len += path.len;
+                    // walk up from the depth where the edge starts
+                    path_len = (251 - depth) as i64;
                     hold = provided_hash;
+                    continue;
                 }
-                Node::BinaryNode(BinaryNode {
-                    binary: BinaryNodeBinary { left, right },
-                }) => {
-                    path_len += 1;
-                    let left = FieldElement::from_hex(left.as_ref()).map_err(
-                        |_| {
-                            jsonrpc::Error::new(
-                                -32701,
-                                "Failed to create Field Element".to_string(),
-                            )
-                        },
-                    )?;
-                    let right = FieldElement::from_hex(right.as_ref())
-                        .map_err(|_| {
-                            jsonrpc::Error::new(
-                                -32701,
-                                "Failed to create Field Element".to_string(),
-                            )
-                        })?;
-                    // identify path direction for this node
-                    let expected_hash =
-                        match Direction::from(key[251 - path_len as usize]) {
-                            Direction::Left => pedersen_hash(&hold, &right),
-                            Direction::Right => pedersen_hash(&left, &hold),
-                        };
-
-                    hold = pedersen_hash(&left, &right);
-                    // verify calculated hash vs provided hash for the node
-                    if hold != expected_hash {
+                if i == 0 {
+                    // mask storage key
+                    let computed_hash = match felt_from_bits(
+                        &key,
+                        Some(251 - path.len as usize),
+                    ) {
+                        Ok(masked_key) => {
+                            hash(&value, &masked_key)
+                                + FieldElement::from(path.len as u64)
+                        }
+                        Err(_) => return Ok(None),
+                    };
+                    // verify computed hash against provided hash
+                    if provided_hash != computed_hash {
                         return Ok(None);
                     };
                 }
-            };
-        }
 
-        Ok(Some(Felt::try_new(&format!("0x{:x}", hold))?))
+                // walk up the remaining path
+                path_len += path.len;
+                hold = provided_hash;
+            }
+            Node::BinaryNode(BinaryNode {
+                binary: BinaryNodeBinary { left, right },
+            }) => {
+                path_len += 1;
+                let left =
+                    FieldElement::from_hex(left.as_ref()).map_err(|_| {
+                        jsonrpc::Error::new(
+                            -32701,
+                            "Failed to create Field Element".to_string(),
+                        )
+                    })?;
+                let right =
+                    FieldElement::from_hex(right.as_ref()).map_err(|_| {
+                        jsonrpc::Error::new(
+                            -32701,
+                            "Failed to create Field Element".to_string(),
+                        )
+                    })?;
+                // identify path direction for this node
+                let expected_hash =
+                    match Direction::from(key[251 - path_len as usize]) {
+                        Direction::Left => hash(&hold, &right),
+                        Direction::Right => hash(&left, &hold),
+                    };
+
+                hold = hash(&left, &right);
+                // verify calculated hash vs provided hash for the node
+                if hold != expected_hash {
+                    return Ok(None);
+                };
+            }
+        };
     }
+
+    Ok(Some(Felt::try_new(&format!("0x{:x}", hold))?))
 }
 #[cfg(test)]
 mod tests {
     use crate::gen::{
-        Address, ContractData, Felt, GetProofResult, Node, StorageKey,
+        Address, ContractData, Felt, GetClassProofResult, GetProofResult, Node,
+        StorageKey,
     };
 
     #[test]
@@ -522,7 +892,7 @@ mod tests {
         let contract_data = storage_proof.contract_data.as_ref().unwrap();
 
         assert!(storage_proof
-            .verify_storage_proofs(contract_data, key, value)
+            .verify_storage_proofs(contract_data, &[key], &[value])
             .is_ok());
     }
 
@@ -558,10 +928,187 @@ mod tests {
         let contract_data = storage_proof.contract_data.as_ref().unwrap();
 
         assert!(storage_proof
-            .verify_storage_proofs(contract_data, key, value)
+            .verify_storage_proofs(contract_data, &[key], &[value])
             .is_err());
     }
 
+    #[test]
+    fn verify_many_storage_proofs() {
+        let keys = vec![
+            StorageKey::try_new(
+                "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9",
+            )
+            .unwrap(),
+            StorageKey::try_new(
+                "0x744c3e5f7a9b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1",
+            )
+            .unwrap(),
+        ];
+        let values = vec![
+            Felt::try_new("0x64").unwrap(),
+            Felt::try_new("0xc8").unwrap(),
+        ];
+        let first_proof_string = r#"[{
+            "binary": {
+                "left": "0x2a63d589c5fdb78c343451caf4840ad8155fc69450f34282dd90c9a5ba5ab59",
+                "right": "0x4696df08de2bd6172f759d26e7a8288a2c544334ccc428193576d9fbe8d8d7e"
+            }
+        }, {
+            "edge": {
+                "child": "0x64",
+                "path": {
+                    "len": 250,
+                    "value": "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9"
+                }
+            }
+        }]"#;
+        let second_proof_string = r#"[{
+            "binary": {
+                "left": "0x2a63d589c5fdb78c343451caf4840ad8155fc69450f34282dd90c9a5ba5ab59",
+                "right": "0x4696df08de2bd6172f759d26e7a8288a2c544334ccc428193576d9fbe8d8d7e"
+            }
+        }, {
+            "edge": {
+                "child": "0xc8",
+                "path": {
+                    "len": 250,
+                    "value": "0x344c3e5f7a9b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1"
+                }
+            }
+        }]"#;
+
+        let storage_proof = GetProofResult {
+            contract_data: Some(ContractData {
+                root: Felt::try_new(
+                    "0x51322cc896bfe33158a72463c2ad7c28d49a1b050e7910d201de00c9402d3fd",
+                )
+                .unwrap(),
+                storage_proofs: Some(vec![
+                    serde_json::from_str(first_proof_string).unwrap(),
+                    serde_json::from_str(second_proof_string).unwrap(),
+                ]),
+                class_hash: Felt::try_new("0x0").unwrap(),
+                contract_state_hash_version: Felt::try_new("0x0").unwrap(),
+                nonce: Felt::try_new("0x0").unwrap(),
+            }),
+            class_commitment: Some(Felt::try_new("0x0").unwrap()),
+            contract_proof: vec![],
+            state_commitment: Some(Felt::try_new("0x0").unwrap()),
+        };
+        let contract_data = storage_proof.contract_data.as_ref().unwrap();
+
+        assert!(storage_proof
+            .verify_storage_proofs(contract_data, &keys, &values)
+            .is_ok());
+
+        let swapped = vec![values[1].clone(), values[0].clone()];
+        assert!(storage_proof
+            .verify_storage_proofs(contract_data, &keys, &swapped)
+            .is_err());
+
+        assert!(storage_proof
+            .verify_storage_proofs(contract_data, &keys[..1], &values[..1])
+            .is_err());
+
+        let leaves = storage_proof.storage_values(&keys).unwrap();
+        assert_eq!(leaves[0].as_ref(), "0x64");
+        assert_eq!(leaves[1].as_ref(), "0xc8");
+        assert!(storage_proof.storage_values(&keys[..1]).is_err());
+    }
+
+    #[test]
+    fn valid_non_membership_parse_proof() {
+        // single leaf trie: the root edge diverges from the requested key
+        let key = "0x744c3e5f7a9b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1";
+        let edge_node_string = r#"[{
+            "edge": {
+                "child": "0x64",
+                "path": {
+                    "len": 251,
+                    "value": "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9"
+                }
+            }
+        }]"#;
+        let proof: Vec<Node> = serde_json::from_str(edge_node_string).unwrap();
+
+        let value = Felt::try_new("0x0").unwrap();
+        let ret_val = GetProofResult::parse_proof(key, value, &proof).unwrap();
+        assert_eq!(
+            ret_val.unwrap().as_ref(),
+            "0x2a63d589c5fdb78c343451caf4840ad8155fc69450f34282dd90c9a5ba5ab5a"
+        );
+
+        // the key of the existing leaf cannot be proven empty
+        let key = "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9";
+        let value = Felt::try_new("0x0").unwrap();
+        let ret_val = GetProofResult::parse_proof(key, value, &proof).unwrap();
+        assert!(ret_val.is_none());
+    }
+
+    #[test]
+    fn valid_non_membership_verify_storage_proof() {
+        // the last edge diverges from the key below the binary root node
+        let key = StorageKey::try_new(
+            "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a8",
+        )
+        .unwrap();
+        let proof_string = r#"[{
+            "binary": {
+                "left": "0x2a63d589c5fdb78c343451caf4840ad8155fc69450f34282dd90c9a5ba5ab59",
+                "right": "0x4696df08de2bd6172f759d26e7a8288a2c544334ccc428193576d9fbe8d8d7e"
+            }
+        }, {
+            "edge": {
+                "child": "0x64",
+                "path": {
+                    "len": 250,
+                    "value": "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9"
+                }
+            }
+        }]"#;
+
+        let storage_proof = GetProofResult {
+            contract_data: Some(ContractData {
+                root: Felt::try_new(
+                    "0x51322cc896bfe33158a72463c2ad7c28d49a1b050e7910d201de00c9402d3fd",
+                )
+                .unwrap(),
+                storage_proofs: Some(vec![
+                    serde_json::from_str(proof_string).unwrap()
+                ]),
+                class_hash: Felt::try_new("0x0").unwrap(),
+                contract_state_hash_version: Felt::try_new("0x0").unwrap(),
+                nonce: Felt::try_new("0x0").unwrap(),
+            }),
+            class_commitment: Some(Felt::try_new("0x0").unwrap()),
+            contract_proof: vec![],
+            state_commitment: Some(Felt::try_new("0x0").unwrap()),
+        };
+        let contract_data = storage_proof.contract_data.as_ref().unwrap();
+
+        let zero = Felt::try_new("0x0").unwrap();
+        assert!(storage_proof
+            .verify_storage_proofs(contract_data, &[key.clone()], &[zero])
+            .is_ok());
+
+        // a non-zero value for a missing key is still rejected
+        let value = Felt::try_new("0x64").unwrap();
+        assert!(storage_proof
+            .verify_storage_proofs(contract_data, &[key.clone()], &[value])
+            .is_err());
+
+        let leaves = storage_proof.storage_values(&[key]).unwrap();
+        assert_eq!(leaves[0].as_ref(), "0x0");
+    }
+
+    #[test]
+    fn empty_storage_proves_zero_value() {
+        let key = "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9";
+        let value = Felt::try_new("0x0").unwrap();
+        let ret_val = GetProofResult::parse_proof(key, value, &[]).unwrap();
+        assert_eq!(ret_val.unwrap().as_ref(), "0x0");
+    }
+
     #[test]
     fn contract_state_hash_is_valid() {
         let contract_data = ContractData {
@@ -642,6 +1189,168 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn verify_contract_returns_proven_nonce() {
+        let edge_node_string = r#"[{
+            "edge": {
+                "child": "0x538a7653ef22e217f93066ac54784c0159a5e1e37d808f83c82d1b42d57457d",
+                "path": {
+                    "len": 229,
+                    "value": "0x4a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0"
+                }
+            }
+        }]"#;
+        let mut proof = GetProofResult {
+            contract_proof: serde_json::from_str(edge_node_string).unwrap(),
+            state_commitment: Some(
+                Felt::try_new("0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54")
+                    .unwrap(),
+            ),
+            contract_data: Some(ContractData {
+                class_hash: Felt::try_new(
+                    "0x4e635d495504b31ec191cbfc3d99b5d109bfcae4d0d9e16f4909a43b2e24c07",
+                )
+                .unwrap(),
+                root: Felt::try_new(
+                    "0x5826149cbab3f8538d346301869ba2742a159d1542463ce19a60a927b826a2f",
+                )
+                .unwrap(),
+                nonce: Felt::try_new("0x0").unwrap(),
+                contract_state_hash_version: Felt::try_new("0x0").unwrap(),
+                storage_proofs: None,
+            }),
+            class_commitment: Some(Felt::try_new("0x0").unwrap()),
+        };
+        let global_root = Felt::try_new(
+            "0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54",
+        )
+        .unwrap();
+        let contract_address = Address(Felt::try_new("0x6a05844a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0")
+                .unwrap());
+
+        let contract_data = proof
+            .verify_contract(global_root.clone(), contract_address.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(contract_data.nonce.as_ref(), "0x0");
+
+        // a different nonce changes the contract leaf
+        proof.contract_data.as_mut().unwrap().nonce =
+            Felt::try_new("0x1").unwrap();
+        assert!(proof.verify_contract(global_root, contract_address).is_err());
+    }
+
+    #[test]
+    fn verify_contract_rejects_swapped_class_hash() {
+        let edge_node_string = r#"[{
+            "edge": {
+                "child": "0x538a7653ef22e217f93066ac54784c0159a5e1e37d808f83c82d1b42d57457d",
+                "path": {
+                    "len": 229,
+                    "value": "0x4a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0"
+                }
+            }
+        }]"#;
+        let mut proof = GetProofResult {
+            contract_proof: serde_json::from_str(edge_node_string).unwrap(),
+            state_commitment: Some(
+                Felt::try_new("0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54")
+                    .unwrap(),
+            ),
+            contract_data: Some(ContractData {
+                class_hash: Felt::try_new(
+                    "0x4e635d495504b31ec191cbfc3d99b5d109bfcae4d0d9e16f4909a43b2e24c07",
+                )
+                .unwrap(),
+                root: Felt::try_new(
+                    "0x5826149cbab3f8538d346301869ba2742a159d1542463ce19a60a927b826a2f",
+                )
+                .unwrap(),
+                nonce: Felt::try_new("0x0").unwrap(),
+                contract_state_hash_version: Felt::try_new("0x0").unwrap(),
+                storage_proofs: None,
+            }),
+            class_commitment: Some(Felt::try_new("0x0").unwrap()),
+        };
+        let global_root = Felt::try_new(
+            "0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54",
+        )
+        .unwrap();
+        let contract_address = Address(Felt::try_new("0x6a05844a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0")
+                .unwrap());
+
+        let contract_data = proof
+            .verify_contract(global_root.clone(), contract_address.clone())
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            contract_data.class_hash.as_ref(),
+            "0x4e635d495504b31ec191cbfc3d99b5d109bfcae4d0d9e16f4909a43b2e24c07"
+        );
+
+        // the contract leaf commits to the class hash
+        proof.contract_data.as_mut().unwrap().class_hash =
+            Felt::try_new("0x123").unwrap();
+        assert!(proof.verify_contract(global_root, contract_address).is_err());
+    }
+
+    #[test]
+    fn verify_contract_proves_undeployed_contract() {
+        let edge_node_string = r#"[{
+            "edge": {
+                "child": "0x538a7653ef22e217f93066ac54784c0159a5e1e37d808f83c82d1b42d57457d",
+                "path": {
+                    "len": 229,
+                    "value": "0x4a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0"
+                }
+            }
+        }]"#;
+        let proof = GetProofResult {
+            contract_proof: serde_json::from_str(edge_node_string).unwrap(),
+            state_commitment: Some(
+                Felt::try_new("0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54")
+                    .unwrap(),
+            ),
+            contract_data: None,
+            class_commitment: Some(Felt::try_new("0x0").unwrap()),
+        };
+        let global_root = Felt::try_new(
+            "0x1e2a7a7ee40c1d897c8c0a9515720ea02c8075ee9e00db277f5f8c3e4edcb54",
+        )
+        .unwrap();
+
+        // the only edge of the trie diverges from the address
+        let contract_address = Address(Felt::try_new("0x1").unwrap());
+        assert!(proof
+            .verify_contract(global_root.clone(), contract_address.clone())
+            .unwrap()
+            .is_none());
+        let key = StorageKey::try_new("0x2").unwrap();
+        assert!(proof
+            .verify(
+                global_root.clone(),
+                contract_address.clone(),
+                key.clone(),
+                Felt::try_new("0x0").unwrap()
+            )
+            .is_ok());
+        assert!(proof
+            .verify(
+                global_root.clone(),
+                contract_address,
+                key,
+                Felt::try_new("0x1").unwrap()
+            )
+            .is_err());
+
+        // an address on the path of the edge cannot be proven missing
+        let contract_address = Address(
+            Felt::try_new("0x1280eee79d111e78ca63d51c168d659aac105034f6375e5f07db71274000000")
+                .unwrap(),
+        );
+        assert!(proof.verify_contract(global_root, contract_address).is_err());
+    }
+
     #[test]
     fn invalid_verify_contract_proof() {
         let invalid_storage_proof = GetProofResult {
@@ -664,4 +1373,125 @@ mod tests {
             .verify_contract_proof(contract_data, global_root, contract_address)
             .is_err());
     }
+
+    fn class_proof() -> GetClassProofResult {
+        let class_proof_string = r#"[{
+            "binary": {
+                "left": "0xc4da4317b00ad2aba729554ef3285483472f77c330409ac12bbe80a68b6c5f",
+                "right": "0x1e89fcfc59b65cee5f07aa968d78664c36dcec470f587e81f7446e5b4ea87aa"
+            }
+        }, {
+            "edge": {
+                "child": "0x47a643fc51b5defd1c7ea5aa0fb5d45c62eda6e5da40f66a58f75b74c6156c0",
+                "path": {
+                    "len": 250,
+                    "value": "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9"
+                }
+            }
+        }]"#;
+        GetClassProofResult {
+            class_commitment: Felt::try_new(
+                "0x4390e701e650dd310c95f98b194ffea7f17f58543088dd21c817725f8069d31",
+            )
+            .unwrap(),
+            class_proof: serde_json::from_str(class_proof_string).unwrap(),
+        }
+    }
+
+    #[test]
+    fn valid_verify_class_proof() {
+        let class_proof = class_proof();
+        let class_commitment = class_proof.class_commitment.clone();
+        let class_hash = Felt::try_new(
+            "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9",
+        )
+        .unwrap();
+        let compiled_class_hash = Felt::try_new("0x1234abcd").unwrap();
+        assert!(class_proof
+            .verify(class_commitment, class_hash, compiled_class_hash)
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid_verify_class_proof() {
+        let class_proof = class_proof();
+        let class_commitment = class_proof.class_commitment.clone();
+        let class_hash = Felt::try_new(
+            "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9",
+        )
+        .unwrap();
+
+        // compiled class hash of the sibling leaf
+        let compiled_class_hash = Felt::try_new("0x5678ef01").unwrap();
+        assert!(class_proof
+            .verify(
+                class_commitment,
+                class_hash.clone(),
+                compiled_class_hash.clone()
+            )
+            .is_err());
+
+        let compiled_class_hash = Felt::try_new("0x1234abcd").unwrap();
+        let class_commitment = Felt::try_new("0x1").unwrap();
+        assert!(class_proof
+            .verify(class_commitment, class_hash, compiled_class_hash)
+            .is_err());
+    }
+
+    #[test]
+    fn verify_absent_class() {
+        let class_proof = class_proof();
+        let class_commitment = class_proof.class_commitment.clone();
+
+        // the edge below the root diverges from the class hash
+        let class_hash = Felt::try_new(
+            "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a8",
+        )
+        .unwrap();
+        assert!(class_proof
+            .verify_absent(class_commitment.clone(), class_hash)
+            .is_ok());
+
+        // a declared class cannot be proven absent
+        let class_hash = Felt::try_new(
+            "0x2a7b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d1e3f5a7b9c1d3e5f7a9",
+        )
+        .unwrap();
+        assert!(class_proof
+            .verify_absent(class_commitment, class_hash)
+            .is_err());
+    }
+
+    #[test]
+    fn verify_class_commitment_against_global_root() {
+        let binary_node_string = r#"[{
+            "binary": {
+                "left": "0x1",
+                "right": "0x2"
+            }
+        }]"#;
+        let storage_proof = GetProofResult {
+            contract_proof: serde_json::from_str(binary_node_string).unwrap(),
+            state_commitment: None,
+            contract_data: None,
+            class_commitment: Some(
+                Felt::try_new("0x4390e701e650dd310c95f98b194ffea7f17f58543088dd21c817725f8069d31")
+                    .unwrap(),
+            ),
+        };
+
+        let global_root = Felt::try_new(
+            "0x728587ca078260de2279b954324732ffde00f9e5e4a642409b287642afba239",
+        )
+        .unwrap();
+        let class_commitment =
+            storage_proof.verify_class_commitment(global_root).unwrap();
+        assert_eq!(
+            class_commitment.as_ref(),
+            "0x4390e701e650dd310c95f98b194ffea7f17f58543088dd21c817725f8069d31"
+        );
+
+        let global_root = Felt::try_new("0x1").unwrap();
+        assert!(storage_proof.verify_class_commitment(global_root).is_err());
+    }
 }
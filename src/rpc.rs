@@ -2,7 +2,7 @@ use axum::{
     extract::State, response::IntoResponse, routing::post, Json, Router,
 };
 use iamgroot::jsonrpc;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::{
     net::{TcpListener, ToSocketAddrs},
@@ -10,9 +10,16 @@ use tokio::{
     task::JoinHandle,
 };
 
-use crate::client::{Http, State as ClientState};
+use crate::client::{
+    Http, QuorumHttp, State as ClientState, LINEAGE_WALK_LIMIT,
+};
+use crate::config::Quorum;
+use crate::feeder::GatewayClient;
+use crate::hash::receipt;
+use crate::head::{walk_parents, Reorg};
+use crate::store::{HeaderStore, StoredHeader};
 
-use crate::exe::err::Error;
+use crate::exe::{err::Error, overlay::StateOverride};
 
 use super::gen::*;
 use gen::GetBlockWithTxHashesResult;
@@ -37,21 +44,54 @@ impl Server {
     }
 }
 
+/// What the server serves: the verified state and the reorgs seen on the way
+/// (both kept up to date by the caller) and the stored headers, with the node
+/// the requests are forwarded to and the additional providers that must agree
+/// on what cannot be verified.
+pub struct RpcConfig {
+    pub url: String,
+    pub gateway: GatewayClient,
+    pub state: Arc<RwLock<ClientState>>,
+    pub reorgs: Arc<RwLock<Vec<Reorg>>>,
+    pub headers: Arc<RwLock<HeaderStore>>,
+    pub providers: Vec<String>,
+    pub quorum: Quorum,
+}
+
+impl RpcConfig {
+    /// Serve the state from a single node, with no stored headers.
+    pub fn new(
+        url: &str,
+        gateway: GatewayClient,
+        state: Arc<RwLock<ClientState>>,
+    ) -> Self {
+        Self {
+            url: url.to_owned(),
+            gateway,
+            state,
+            reorgs: Default::default(),
+            headers: Default::default(),
+            providers: vec![],
+            quorum: Quorum::default(),
+        }
+    }
+}
+
 pub async fn serve<A: ToSocketAddrs>(
-    url: &str,
+    config: RpcConfig,
     addr: A,
-    state: Arc<RwLock<ClientState>>,
 ) -> Result<Server, Error> {
     let listener = TcpListener::bind(addr).await?;
-    let server = serve_on(url, listener, state)?;
+    let server = serve_on(config, listener).await?;
     Ok(server)
 }
 
-fn serve_on(
-    url: &str,
+async fn serve_on(
+    config: RpcConfig,
     listener: TcpListener,
-    state: Arc<RwLock<ClientState>>,
 ) -> Result<Server, Error> {
+    let RpcConfig { url, gateway, state, reorgs, headers, providers, quorum } =
+        config;
     const DEFAULT_TIMEOUT: std::time::Duration =
         std::time::Duration::from_secs(30);
     let client = reqwest::ClientBuilder::new()
@@ -59,10 +99,23 @@ fn serve_on(
         .timeout(DEFAULT_TIMEOUT)
         .build()?;
 
+    let quorum = gen::client::Client::new(
+        &url,
+        QuorumHttp::new(Http(client.clone()), providers, quorum),
+    );
+    // The chain does not change while serving, so it is only asked for once
+    let chain_id = quorum.chainId().await?;
+
     let ctx = Context {
-        url: url.to_owned(),
-        client: Arc::new(gen::client::Client::new(url, Http(client))),
+        client: Arc::new(gen::client::Client::new(&url, Http(client.clone()))),
+        http: Http(client),
+        url,
+        quorum: Arc::new(quorum),
+        gateway: Arc::new(gateway),
         state,
+        reorgs,
+        headers,
+        chain_id,
     };
 
     let app = Router::new().route("/rpc", post(handle_request)).with_state(ctx);
@@ -108,31 +161,648 @@ impl IntoResponse for RpcError {
     }
 }
 
+/// Result of `beerus_callWithOverrides`: it is verified by the chain only
+/// when no override is given.
+#[derive(Debug, Serialize)]
+struct OverriddenCallResult {
+    result: Vec<Felt>,
+    verified: bool,
+}
+
+/// Outcome of a single call of `beerus_multicall`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CallOutcome {
+    Result(Vec<Felt>),
+    Error { code: i64, message: String },
+}
+
+/// A reorg seen while following the head, as returned by `beerus_getReorgs`.
+#[derive(Debug, Serialize)]
+struct ReorgResult {
+    ancestor: u64,
+    dropped: Vec<ReorgBlock>,
+    head: ReorgBlock,
+}
+
+#[derive(Debug, Serialize)]
+struct ReorgBlock {
+    block_number: u64,
+    block_hash: Felt,
+}
+
+impl From<&Reorg> for ReorgResult {
+    fn from(reorg: &Reorg) -> Self {
+        let block = |(block_number, block_hash): (u64, Felt)| ReorgBlock {
+            block_number,
+            block_hash,
+        };
+        Self {
+            ancestor: reorg.ancestor,
+            dropped: reorg.dropped.iter().cloned().map(block).collect(),
+            head: block((
+                reorg.head.block_number,
+                reorg.head.block_hash.clone(),
+            )),
+        }
+    }
+}
+
 #[derive(Clone)]
 struct Context {
     url: String,
     client: Arc<gen::client::Client<Http>>,
+    // For the values checked over the JSON received (e.g. legacy classes)
+    http: Http,
+    // For the methods that cannot be verified: the providers must agree
+    quorum: Arc<gen::client::Client<QuorumHttp<Http>>>,
+    gateway: Arc<GatewayClient>,
     state: Arc<RwLock<ClientState>>,
+    reorgs: Arc<RwLock<Vec<Reorg>>>,
+    headers: Arc<RwLock<HeaderStore>>,
+    chain_id: ChainId,
 }
 
 impl Context {
+    /// Fetch the header of a historical block and check that the block hash
+    /// commits to its fields before adopting the state root.
     async fn get_state(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<ClientState, jsonrpc::Error> {
-        let block = self.getBlockWithTxHashes(block_id).await?;
-        let gen::GetBlockWithTxHashesResult::BlockWithTxHashes(block) = block
-        else {
+        let header =
+            self.gateway.get_header(&block_id).await.map_err(|e| {
+                jsonrpc::Error { code: -1, message: e.to_string() }
+            })?;
+        ClientState::try_from(&header)
+    }
+
+    /// Class hash of the contract in the resolved block, proven against the
+    /// state root of the block.
+    async fn class_hash_at(
+        &self,
+        block_id: &BlockId,
+        state_root: Felt,
+        contract_address: &Address,
+    ) -> std::result::Result<Felt, jsonrpc::Error> {
+        let proof = self
+            .client
+            .getProof(block_id.clone(), contract_address.clone(), vec![])
+            .await?;
+
+        let contract_data = proof
+            .verify_contract(state_root, contract_address.clone())?
+            .ok_or(gen::error::CONTRACT_NOT_FOUND)?;
+        let class_hash = contract_data.class_hash.clone();
+        tracing::info!(
+            ?contract_address,
+            ?block_id,
+            ?class_hash,
+            "getClassHashAt"
+        );
+
+        Ok(class_hash)
+    }
+
+    /// Read values of many storage keys of a contract from a single storage
+    /// proof (one request to the node) and verify all of them against it.
+    async fn get_storage_at_batch(
+        &self,
+        contract_address: Address,
+        keys: Vec<StorageKey>,
+        block_id: BlockId,
+    ) -> std::result::Result<Vec<Felt>, jsonrpc::Error> {
+        let (block_id, state_root) = self.resolve_block_id(block_id).await?;
+        tracing::info!(
+            ?contract_address,
+            keys = keys.len(),
+            ?block_id,
+            "getStorageAtBatch"
+        );
+
+        let proof = self
+            .client
+            .getProof(block_id, contract_address.clone(), keys.clone())
+            .await?;
+        let values = proof.storage_values(&keys)?;
+
+        proof.verify_many(state_root, contract_address, &keys, &values)?;
+        tracing::info!("getProof: verified");
+
+        Ok(values)
+    }
+
+    /// Execute the call as if the contracts had the overridden values. The
+    /// result is marked as not verified when any override is given.
+    async fn call_with_overrides(
+        &self,
+        request: FunctionCall,
+        block_id: BlockId,
+        overrides: Vec<StateOverride>,
+    ) -> std::result::Result<OverriddenCallResult, jsonrpc::Error> {
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        let verified = overrides.is_empty();
+        tracing::info!(
+            ?state,
+            overrides = overrides.len(),
+            "callWithOverrides"
+        );
+        let chain_id = self.chain_id.clone();
+
+        let call_info = tokio::task::spawn_blocking(move || {
+            crate::exe::call_with_overrides(
+                &url,
+                Http::new(),
+                request,
+                &overrides,
+                state,
+                &chain_id,
+            )
+        })
+        .await
+        .map_err(|e| {
+            iamgroot::jsonrpc::Error::new(500, format!("join error: {e}"))
+        })??;
+
+        let result: Result<Vec<Felt>, Error> = call_info
+            .execution
+            .retdata
+            .0
+            .into_iter()
+            .map(|e| e.try_into())
+            .collect();
+
+        Ok(OverriddenCallResult { result: result?, verified })
+    }
+
+    /// Execute many calls against the same verified state of the block (see
+    /// `exe::call_many`), returning the outcome of every call.
+    async fn multicall(
+        &self,
+        calls: Vec<FunctionCall>,
+        block_id: BlockId,
+        chained: bool,
+    ) -> std::result::Result<Vec<CallOutcome>, jsonrpc::Error> {
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        tracing::info!(?state, calls = calls.len(), chained, "multicall");
+        let chain_id = self.chain_id.clone();
+
+        let calls = tokio::task::spawn_blocking(move || {
+            crate::exe::call_many(
+                &url,
+                Http::new(),
+                calls,
+                chained,
+                state,
+                &chain_id,
+            )
+        })
+        .await
+        .map_err(|e| {
+            iamgroot::jsonrpc::Error::new(500, format!("join error: {e}"))
+        })??;
+
+        Ok(calls
+            .into_iter()
+            .map(|call_info| {
+                let ret: Result<Vec<Felt>, Error> =
+                    call_info.and_then(|info| {
+                        info.execution
+                            .retdata
+                            .0
+                            .into_iter()
+                            .map(|e| e.try_into())
+                            .collect()
+                    });
+                match ret {
+                    Ok(ret) => CallOutcome::Result(ret),
+                    Err(e) => {
+                        let e = jsonrpc::Error::from(e);
+                        CallOutcome::Error { code: e.code, message: e.message }
+                    }
+                }
+            })
+            .collect())
+    }
+
+    /// Fetch a block with its transactions, check every transaction hash and
+    /// that the transactions match the commitment of the block header.
+    async fn get_block_with_txs(
+        &self,
+        block_id: BlockId,
+    ) -> std::result::Result<BlockWithTxs, jsonrpc::Error> {
+        let mut block = match self.client.getBlockWithTxs(block_id).await? {
+            GetBlockWithTxsResult::BlockWithTxs(block) => block,
+            GetBlockWithTxsResult::PendingBlockWithTxs(_) => {
+                return Err(jsonrpc::Error {
+                    code: -1,
+                    message: "Pending block is not supported".to_owned(),
+                })
+            }
+        };
+
+        let transactions = &block.block_body_with_txs.transactions;
+        let pairs: Vec<_> = transactions
+            .iter()
+            .map(|tx| (&tx.txn, &tx.transaction_hash.0))
+            .collect();
+        self.verify_transactions(&mut block.block_header, &pairs).await?;
+        tracing::info!(
+            block_hash = ?block.block_header.block_hash,
+            transactions = transactions.len(),
+            "getBlockWithTxs: verified"
+        );
+
+        Ok(block)
+    }
+
+    /// Re-execute the transactions of a block on the verified state of its
+    /// parent and trace them. With a transaction given, the block is traced
+    /// only up to (and including) that transaction.
+    async fn trace_block(
+        &self,
+        block_id: BlockId,
+        until: Option<&TxnHash>,
+    ) -> std::result::Result<Vec<(Felt, TransactionTrace)>, jsonrpc::Error>
+    {
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        if state.block_number == 0 {
             return Err(jsonrpc::Error {
                 code: -1,
-                message: "Pending block received".to_owned(),
+                message: "Genesis block cannot be traced".to_owned(),
             });
-        };
-        Ok(ClientState {
-            block_number: *block.block_header.block_number.as_ref() as u64,
-            block_hash: block.block_header.block_hash.0,
-            root: block.block_header.new_root,
+        }
+        let block = self
+            .get_block_with_txs(BlockId::BlockHash {
+                block_hash: BlockHash(state.block_hash.clone()),
+            })
+            .await?;
+        // The header is the verified one of the gateway, so the parent state
+        // is looked up by a verified hash, once the header is checked to be
+        // the one of the block being traced
+        let header = &block.block_header;
+        if !header
+            .block_hash
+            .0
+            .as_ref()
+            .eq_ignore_ascii_case(state.block_hash.as_ref())
+        {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: format!(
+                    "Block mismatch at block {}",
+                    state.block_number
+                ),
+            });
+        }
+        let parent = self
+            .get_state(BlockId::BlockHash {
+                block_hash: header.parent_hash.clone(),
+            })
+            .await?;
+        if parent.block_number + 1 != state.block_number {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: format!(
+                    "Parent block mismatch at block {}",
+                    state.block_number
+                ),
+            });
+        }
+
+        let mut transactions = Vec::new();
+        for tx in block.block_body_with_txs.transactions {
+            let found = until
+                .map(|hash| hash.0.as_ref() == tx.transaction_hash.0.as_ref())
+                .unwrap_or_default();
+            transactions.push((tx.txn, tx.transaction_hash.0));
+            if found {
+                break;
+            }
+        }
+        if let Some(hash) = until {
+            let found = transactions
+                .last()
+                .map(|(_, last)| last.as_ref() == hash.0.as_ref())
+                .unwrap_or_default();
+            if !found {
+                return Err(jsonrpc::Error {
+                    code: -1,
+                    message: "Transaction not found in its block".to_owned(),
+                });
+            }
+        }
+        tracing::info!(?state, txs = transactions.len(), "trace");
+        let hashes: Vec<_> =
+            transactions.iter().map(|(_, hash)| hash.clone()).collect();
+        let chain_id = self.chain_id.clone();
+
+        let traces = tokio::task::spawn_blocking(move || {
+            crate::exe::trace_block(
+                &url,
+                Http::new(),
+                transactions,
+                parent,
+                state,
+                &chain_id,
+            )
         })
+        .await
+        .map_err(|e| {
+            iamgroot::jsonrpc::Error::new(500, format!("join error: {e}"))
+        })??;
+
+        Ok(hashes.into_iter().zip(traces).collect())
+    }
+
+    /// Fetch a block with the receipts of its transactions and check them,
+    /// together with the emitted events, against the block header.
+    async fn get_block_with_receipts(
+        &self,
+        block_id: BlockId,
+    ) -> std::result::Result<BlockWithReceipts, jsonrpc::Error> {
+        let mut block = match self.client.getBlockWithReceipts(block_id).await?
+        {
+            GetBlockWithReceiptsResult::BlockWithReceipts(block) => block,
+            GetBlockWithReceiptsResult::PendingBlockWithReceipts(_) => {
+                return Err(jsonrpc::Error {
+                    code: -1,
+                    message: "Pending block is not supported".to_owned(),
+                })
+            }
+        };
+
+        let transactions = &block.block_body_with_receipts.transactions;
+        let pairs: Vec<_> = transactions
+            .iter()
+            .map(|tx| {
+                let receipt = receipt::common_properties(&tx.receipt);
+                (&tx.transaction, &receipt.transaction_hash.0)
+            })
+            .collect();
+        let (header, gas_consumed) =
+            self.verify_transactions(&mut block.block_header, &pairs).await?;
+
+        let event_count: usize = transactions
+            .iter()
+            .map(|tx| receipt::common_properties(&tx.receipt).events.len())
+            .sum();
+        if header.event_count != event_count as u64 {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Events mismatch: expected={} received={}",
+                    header.event_count, event_count
+                ),
+            ));
+        }
+
+        let version =
+            crate::hash::block::parse_version(&header.starknet_version);
+        let commitment = receipt::event_commitment(transactions, version)?;
+        check_commitment("Event", header.event_commitment, commitment)?;
+        if version >= [0, 13, 2, 0] {
+            let commitment =
+                receipt::receipt_commitment(transactions, &gas_consumed)?;
+            check_commitment("Receipt", header.receipt_commitment, commitment)?;
+        }
+        tracing::info!(
+            block_hash = ?block.block_header.block_hash,
+            transactions = transactions.len(),
+            events = event_count,
+            "getBlockWithReceipts: verified"
+        );
+
+        Ok(block)
+    }
+
+    /// Fetch the verified header of the block from the feeder gateway and
+    /// check the hashes of the transactions and the transaction commitment.
+    /// The header returned by the node is replaced by the verified one.
+    async fn verify_transactions(
+        &self,
+        block_header: &mut BlockHeader,
+        transactions: &[(&Txn, &Felt)],
+    ) -> std::result::Result<
+        (crate::hash::block::BlockHeader, Vec<receipt::GasConsumed>),
+        jsonrpc::Error,
+    > {
+        let chain_id = &self.chain_id;
+        let chain_id = starknet_crypto::Felt::from_hex(chain_id.as_ref())
+            .map_err(|_| jsonrpc::Error {
+                code: -32701,
+                message: format!("Invalid chain id: {}", chain_id.as_ref()),
+            })?;
+        for (txn, transaction_hash) in transactions {
+            crate::hash::tx::verify(txn, chain_id, transaction_hash)?;
+        }
+
+        let block_id =
+            BlockId::BlockHash { block_hash: block_header.block_hash.clone() };
+        let (header, gas_consumed) =
+            self.gateway.get_header_with_gas(&block_id).await.map_err(|e| {
+                jsonrpc::Error { code: -1, message: e.to_string() }
+            })?;
+        let current_block_number = self.state.read().await.block_number;
+        let block_hash = format!("{:#x}", header.block_hash);
+        if header.block_number != *block_header.block_number.as_ref() as u64
+            || block_hash != block_header.block_hash.0.as_ref().to_lowercase()
+            || header.block_number > current_block_number
+            || header.transaction_count != transactions.len() as u64
+        {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: "Failed to verify requested block".to_string(),
+            });
+        }
+
+        let version =
+            crate::hash::block::parse_version(&header.starknet_version);
+        let commitment = crate::hash::tx::transaction_commitment(
+            transactions.iter().copied(),
+            version,
+        )?;
+        check_commitment(
+            "Transaction",
+            header.transaction_commitment,
+            commitment,
+        )?;
+
+        *block_header = response_header(&header)?;
+        Ok((header, gas_consumed))
+    }
+
+    /// Check the events of a page against the verified blocks they come from:
+    /// per block, the page must hold a contiguous run of the events matching
+    /// the filter, which only the first and the last block may cut short.
+    async fn verify_events(
+        &self,
+        filter: &GetEventsFilter,
+        chunk: &EventsChunk,
+    ) -> std::result::Result<(), jsonrpc::Error> {
+        let head = self.state.read().await.block_number;
+        let from = match filter.event_filter.from_block.as_ref() {
+            Some(block_id) => self.block_number_of(block_id, head).await?,
+            None => 0,
+        };
+        let to = match filter.event_filter.to_block.as_ref() {
+            Some(block_id) => self.block_number_of(block_id, head).await?,
+            None => head,
+        };
+
+        let mut blocks: Vec<(u64, &BlockHash, Vec<&EmittedEvent>)> = Vec::new();
+        for event in &chunk.events {
+            let (Some(block_hash), Some(block_number)) =
+                (event.block_hash.as_ref(), event.block_number.as_ref())
+            else {
+                return Err(jsonrpc::Error {
+                    code: -1,
+                    message: "Pending events are not supported".to_owned(),
+                });
+            };
+            let block_number = *block_number.as_ref() as u64;
+            if block_number < from || block_number > to {
+                return Err(jsonrpc::Error::new(
+                    -32700,
+                    format!(
+                        "Event of block {block_number} outside of the filter range {from}..={to}"
+                    ),
+                ));
+            }
+            match blocks.last_mut() {
+                Some((number, hash, events))
+                    if *number == block_number
+                        && hash.0.as_ref() == block_hash.0.as_ref() =>
+                {
+                    events.push(event)
+                }
+                Some((number, ..)) if *number >= block_number => {
+                    return Err(jsonrpc::Error::new(
+                        -32700,
+                        format!("Events out of order at block {block_number}"),
+                    ));
+                }
+                _ => blocks.push((block_number, block_hash, vec![event])),
+            }
+        }
+
+        // Only the blocks from the first to the last event of the page are
+        // checked, so that no matching event is left out in between. A page
+        // continued from the previous one starts within its first block, and
+        // a page to be continued ends within its last block: every other
+        // block must be returned in full.
+        let (first, last) = match (blocks.first(), blocks.last()) {
+            (Some((first, ..)), Some((last, ..))) => (*first, *last),
+            _ => return Ok(()),
+        };
+        if last - first >= MAX_EVENT_BLOCKS {
+            return Err(jsonrpc::Error::new(
+                -32700,
+                format!(
+                    "Events cannot be verified: blocks {first}..={last} exceed the limit of {MAX_EVENT_BLOCKS}"
+                ),
+            ));
+        }
+        let continued = filter.result_page_request.continuation_token.is_some();
+        let truncated = chunk.continuation_token.is_some();
+
+        let fetched = futures::future::try_join_all((first..=last).map(
+            |number| async move {
+                let block_number = BlockNumber::try_new(number as i64)?;
+                self.get_block_with_receipts(BlockId::BlockNumber {
+                    block_number,
+                })
+                .await
+            },
+        ))
+        .await?;
+
+        let mut blocks = blocks.into_iter().peekable();
+        for (number, block) in (first..=last).zip(fetched) {
+            let events = match blocks.next_if(|(n, ..)| *n == number) {
+                Some((_, block_hash, events)) => {
+                    let hash = &block.block_header.block_hash;
+                    if hash.0.as_ref() != block_hash.0.as_ref() {
+                        return Err(jsonrpc::Error::new(
+                            -32700,
+                            format!(
+                                "Events invalid for block {number}: hash {} instead of {}",
+                                block_hash.0.as_ref(),
+                                hash.0.as_ref()
+                            ),
+                        ));
+                    }
+                    events
+                }
+                None => vec![],
+            };
+            let expected: Vec<EventRef> = block
+                .block_body_with_receipts
+                .transactions
+                .iter()
+                .flat_map(|tx| {
+                    let receipt = receipt::common_properties(&tx.receipt);
+                    receipt
+                        .events
+                        .iter()
+                        .filter(move |event| {
+                            matches_filter(&filter.event_filter, event)
+                        })
+                        .map(move |event| {
+                            event_ref(&receipt.transaction_hash, event)
+                        })
+                })
+                .collect();
+            let received: Vec<EventRef> = events
+                .iter()
+                .map(|event| event_ref(&event.transaction_hash, &event.event))
+                .collect();
+
+            let partial_start = number == first && continued;
+            let partial_end = number == last && truncated;
+            if !is_contiguous_run(
+                &expected,
+                &received,
+                partial_start,
+                partial_end,
+            ) {
+                return Err(jsonrpc::Error::new(
+                    -32700,
+                    format!(
+                        "Events invalid for block {number}: expected={} received={}",
+                        expected.len(),
+                        received.len()
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // Number of the block that bounds an event filter.
+    async fn block_number_of(
+        &self,
+        block_id: &BlockId,
+        head: u64,
+    ) -> std::result::Result<u64, jsonrpc::Error> {
+        match block_id {
+            BlockId::BlockNumber { block_number } => {
+                Ok(*block_number.as_ref() as u64)
+            }
+            BlockId::BlockHash { .. } => {
+                let block =
+                    self.get_block_with_receipts(block_id.clone()).await?;
+                Ok(*block.block_header.block_number.as_ref() as u64)
+            }
+            BlockId::BlockTag(BlockTag::Latest) => Ok(head),
+            BlockId::BlockTag(BlockTag::Pending) => Err(jsonrpc::Error {
+                code: -1,
+                message: "Pending block is not supported".to_owned(),
+            }),
+        }
     }
 
     async fn resolve_block_id(
@@ -159,6 +829,39 @@ impl Context {
         }
     }
 
+    /// Resolve the block to its verified state (number, hash and root), e.g.
+    /// to execute a call against it.
+    async fn resolve_state(
+        &self,
+        block_id: BlockId,
+    ) -> std::result::Result<ClientState, jsonrpc::Error> {
+        let current = self.state.read().await.clone();
+        let (block_id, root) = self.resolve_block_id(block_id).await?;
+        let state = match &block_id {
+            BlockId::BlockNumber { block_number }
+                if *block_number.as_ref() as u64 == current.block_number =>
+            {
+                current
+            }
+            BlockId::BlockHash { block_hash }
+                if block_hash.0.as_ref() == current.block_hash.as_ref() =>
+            {
+                current
+            }
+            _ => self.get_state(block_id).await?,
+        };
+        if state.root.as_ref() != root.as_ref() {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: format!(
+                    "State root mismatch at block {}",
+                    state.block_number
+                ),
+            });
+        }
+        Ok(state)
+    }
+
     async fn resolve_block_by_number(
         &self,
         block_number: BlockNumber,
@@ -175,9 +878,22 @@ impl Context {
                 current_state.root.clone(),
             ));
         }
+        let head = (current_state.block_number, &current_state.block_hash);
+        if let Some(header) =
+            self.headers.read().await.get_linked(req_block_number, head)
+        {
+            return Ok((
+                BlockId::BlockHash {
+                    block_hash: BlockHash(header.block_hash.clone()),
+                },
+                header.root.clone(),
+            ));
+        }
+        let block_hash =
+            self.linked_hash(req_block_number, current_state).await?;
         let state = self
-            .get_state(BlockId::BlockNumber {
-                block_number: block_number.clone(),
+            .get_state(BlockId::BlockHash {
+                block_hash: BlockHash(block_hash.clone()),
             })
             .await?;
         if state.block_number != req_block_number {
@@ -187,7 +903,10 @@ impl Context {
                     .to_string(),
             });
         }
-        Ok((BlockId::BlockNumber { block_number }, state.root))
+        Ok((
+            BlockId::BlockHash { block_hash: BlockHash(block_hash) },
+            state.root,
+        ))
     }
 
     async fn resolve_block_by_hash(
@@ -212,8 +931,173 @@ impl Context {
                 message: "Failed to verify requested block by hash".to_string(),
             });
         }
+        let linked =
+            self.linked_hash(state.block_number, current_state).await?;
+        if !linked.as_ref().eq_ignore_ascii_case(block_hash.0.as_ref()) {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: format!(
+                    "Block {} is not an ancestor of the head",
+                    state.block_number
+                ),
+            });
+        }
         Ok((BlockId::BlockHash { block_hash }, state.root))
     }
+
+    /// Hash of the block of the given number on the chain of the head: the
+    /// verified parent links are followed down from the head, through the
+    /// stored headers where they are, and the headers fetched on the way are
+    /// stored. A block too far behind to be linked is not served.
+    async fn linked_hash(
+        &self,
+        block_number: u64,
+        head: &ClientState,
+    ) -> Result<Felt, jsonrpc::Error> {
+        let error = |e: eyre::Report| jsonrpc::Error {
+            code: -1,
+            message: e.to_string(),
+        };
+        let walk = walk_parents(
+            &self.gateway,
+            Some(&*self.headers),
+            (head.block_number, head.block_hash.clone()),
+            LINEAGE_WALK_LIMIT,
+            |number, _| number <= block_number,
+        )
+        .await
+        .map_err(error)?;
+        let headers = walk
+            .fetched
+            .iter()
+            .map(StoredHeader::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        crate::store::save(&self.headers, headers).await.map_err(error)?;
+        match walk.end {
+            Some((number, block_hash)) if number == block_number => {
+                Ok(block_hash)
+            }
+            _ => Err(jsonrpc::Error {
+                code: -1,
+                message: format!(
+                    "Block {block_number} cannot be linked to the head {}",
+                    head.block_number
+                ),
+            }),
+        }
+    }
+}
+
+fn check_commitment(
+    name: &str,
+    provided: starknet_crypto::Felt,
+    computed: starknet_crypto::Felt,
+) -> std::result::Result<(), jsonrpc::Error> {
+    if provided != computed {
+        return Err(jsonrpc::Error::new(
+            -32700,
+            format!(
+                "{name} commitment invalid:\nprovided-commitment -> {provided:#x}\ncomputed-commitment -> {computed:#x}\n"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Block header of an RPC response, made of the verified header of the block.
+fn response_header(
+    header: &crate::hash::block::BlockHeader,
+) -> std::result::Result<BlockHeader, jsonrpc::Error> {
+    use crate::hash::block::{GasPrice, L1DaMode};
+
+    let felt =
+        |felt: &starknet_crypto::Felt| Felt::try_new(&format!("{felt:#x}"));
+    let price = |price: &GasPrice| -> std::result::Result<_, jsonrpc::Error> {
+        Ok(ResourcePrice {
+            price_in_fri: felt(&price.price_in_fri)?,
+            price_in_wei: felt(&price.price_in_wei)?,
+        })
+    };
+    Ok(BlockHeader {
+        block_hash: BlockHash(felt(&header.block_hash)?),
+        block_number: BlockNumber::try_new(header.block_number as i64)?,
+        l1_da_mode: Some(match header.l1_da_mode {
+            L1DaMode::Blob => BlockHeaderL1DaMode::Blob,
+            L1DaMode::Calldata => BlockHeaderL1DaMode::Calldata,
+        }),
+        l1_data_gas_price: Some(price(&header.l1_data_gas_price)?),
+        l1_gas_price: price(&header.l1_gas_price)?,
+        new_root: felt(&header.state_root)?,
+        parent_hash: BlockHash(felt(&header.parent_hash)?),
+        sequencer_address: felt(&header.sequencer_address)?,
+        starknet_version: header.starknet_version.clone(),
+        timestamp: BlockHeaderTimestamp::try_new(header.timestamp as i64)?,
+    })
+}
+
+// Most blocks fetched to verify a single page of events
+const MAX_EVENT_BLOCKS: u64 = 128;
+
+// Transaction hash, emitter, keys and data of an event
+type EventRef<'a> = (&'a str, &'a str, Vec<&'a str>, Vec<&'a str>);
+
+fn event_ref<'a>(
+    transaction_hash: &'a TxnHash,
+    event: &'a Event,
+) -> EventRef<'a> {
+    (
+        transaction_hash.0.as_ref().as_str(),
+        event.from_address.0.as_ref().as_str(),
+        event
+            .event_content
+            .keys
+            .iter()
+            .map(|key| key.as_ref().as_str())
+            .collect(),
+        event
+            .event_content
+            .data
+            .iter()
+            .map(|data| data.as_ref().as_str())
+            .collect(),
+    )
+}
+
+fn matches_filter(filter: &EventFilter, event: &Event) -> bool {
+    let eq = |lhs: &Felt, rhs: &Felt| {
+        lhs.as_ref().eq_ignore_ascii_case(rhs.as_ref())
+    };
+    if let Some(address) = filter.address.as_ref() {
+        if !eq(&address.0, &event.from_address.0) {
+            return false;
+        }
+    }
+    // An empty set of keys at a position matches any key
+    filter.keys.iter().flatten().enumerate().all(|(i, allowed)| {
+        allowed.is_empty()
+            || event
+                .event_content
+                .keys
+                .get(i)
+                .is_some_and(|key| allowed.iter().any(|k| eq(k, key)))
+    })
+}
+
+fn is_contiguous_run<T: PartialEq>(
+    expected: &[T],
+    received: &[T],
+    partial_start: bool,
+    partial_end: bool,
+) -> bool {
+    if received.len() > expected.len() {
+        return false;
+    }
+    (0..=expected.len() - received.len())
+        .filter(|&start| partial_start || start == 0)
+        .filter(|&start| {
+            partial_end || start + received.len() == expected.len()
+        })
+        .any(|start| expected[start..start + received.len()] == *received)
 }
 
 async fn handle_request(
@@ -222,7 +1106,7 @@ async fn handle_request(
 ) -> Result<impl IntoResponse, RpcError> {
     match req {
         Request::Single(req) => {
-            let res = gen::handle(&ctx, &req).await;
+            let res = handle(&ctx, &req).await;
             if req.id.is_some() {
                 Ok(Json(Response::Single(res)))
             } else {
@@ -233,7 +1117,7 @@ async fn handle_request(
             let mut ret = Vec::with_capacity(reqs.len());
             for req in reqs {
                 let ctx = ctx.clone();
-                let res = gen::handle(&ctx, &req).await;
+                let res = handle(&ctx, &req).await;
                 if req.id.is_some() {
                     ret.push(res);
                 }
@@ -243,6 +1127,155 @@ async fn handle_request(
     }
 }
 
+/// Dispatch Beerus extension methods, everything else is handled by the
+/// generated Starknet (and Pathfinder) API handler.
+async fn handle(ctx: &Context, req: &jsonrpc::Request) -> jsonrpc::Response {
+    let params = &req.params.clone().unwrap_or_default();
+
+    let response = match req.method.as_str() {
+        "beerus_getStorageAtBatch" => {
+            handle_get_storage_at_batch(ctx, params).await
+        }
+        "beerus_multicall" => handle_multicall(ctx, params).await,
+        "beerus_callWithOverrides" => {
+            handle_call_with_overrides(ctx, params).await
+        }
+        "beerus_getReorgs" => handle_get_reorgs(ctx).await,
+        _ => return gen::handle(ctx, req).await,
+    };
+
+    if let Some(id) = req.id.as_ref() {
+        response.with_id(id.clone())
+    } else {
+        response
+    }
+}
+
+/// Parse the params of a method, given either by name or by position (the
+/// positional ones are converted into the named ones).
+fn parse_params<ByName, ByPos>(
+    params: &serde_json::Value,
+) -> std::result::Result<ByName, jsonrpc::Response>
+where
+    ByName: DeserializeOwned,
+    ByPos: DeserializeOwned + Into<ByName>,
+{
+    serde_json::from_value::<ByName>(params.clone())
+        .or_else(|_| {
+            serde_json::from_value::<ByPos>(params.clone()).map(Into::into)
+        })
+        .map_err(|error| {
+            tracing::debug!(?error, "failed to parse request params");
+            jsonrpc::Response::error(-32602, "Invalid params")
+        })
+}
+
+/// Respond with the result of a method, or with its error.
+fn respond<T: Serialize>(
+    ret: std::result::Result<T, jsonrpc::Error>,
+) -> jsonrpc::Response {
+    match ret {
+        Ok(ret) => match serde_json::to_value(ret) {
+            Ok(ret) => jsonrpc::Response::result(ret),
+            Err(error) => {
+                tracing::debug!(?error, "failed to parse response object");
+                jsonrpc::Response::error(-32603, "Internal error")
+            }
+        },
+        Err(e) => jsonrpc::Response::error(e.code, &e.message),
+    }
+}
+
+async fn handle_get_reorgs(ctx: &Context) -> jsonrpc::Response {
+    let reorgs = ctx.reorgs.read().await;
+    respond(Ok(reorgs.iter().map(ReorgResult::from).collect::<Vec<_>>()))
+}
+
+async fn handle_get_storage_at_batch(
+    ctx: &Context,
+    params: &serde_json::Value,
+) -> jsonrpc::Response {
+    #[derive(Deserialize)]
+    struct ArgByPos(Address, Vec<StorageKey>, BlockId);
+
+    #[derive(Deserialize)]
+    struct ArgByName {
+        contract_address: Address,
+        keys: Vec<StorageKey>,
+        block_id: BlockId,
+    }
+
+    impl From<ArgByPos> for ArgByName {
+        fn from(ArgByPos(contract_address, keys, block_id): ArgByPos) -> Self {
+            Self { contract_address, keys, block_id }
+        }
+    }
+
+    let ArgByName { contract_address, keys, block_id } =
+        match parse_params::<ArgByName, ArgByPos>(params) {
+            Ok(args) => args,
+            Err(response) => return response,
+        };
+    respond(ctx.get_storage_at_batch(contract_address, keys, block_id).await)
+}
+
+async fn handle_multicall(
+    ctx: &Context,
+    params: &serde_json::Value,
+) -> jsonrpc::Response {
+    #[derive(Deserialize)]
+    struct ArgByPos(Vec<FunctionCall>, BlockId, bool);
+
+    #[derive(Deserialize)]
+    struct ArgByName {
+        calls: Vec<FunctionCall>,
+        block_id: BlockId,
+        #[serde(default)]
+        chained: bool,
+    }
+
+    impl From<ArgByPos> for ArgByName {
+        fn from(ArgByPos(calls, block_id, chained): ArgByPos) -> Self {
+            Self { calls, block_id, chained }
+        }
+    }
+
+    let ArgByName { calls, block_id, chained } =
+        match parse_params::<ArgByName, ArgByPos>(params) {
+            Ok(args) => args,
+            Err(response) => return response,
+        };
+    respond(ctx.multicall(calls, block_id, chained).await)
+}
+
+async fn handle_call_with_overrides(
+    ctx: &Context,
+    params: &serde_json::Value,
+) -> jsonrpc::Response {
+    #[derive(Deserialize)]
+    struct ArgByPos(FunctionCall, BlockId, Vec<StateOverride>);
+
+    #[derive(Deserialize)]
+    struct ArgByName {
+        request: FunctionCall,
+        block_id: BlockId,
+        overrides: Vec<StateOverride>,
+    }
+
+    impl From<ArgByPos> for ArgByName {
+        fn from(ArgByPos(request, block_id, overrides): ArgByPos) -> Self {
+            Self { request, block_id, overrides }
+        }
+    }
+
+    let ArgByName { request, block_id, overrides } =
+        match parse_params::<ArgByName, ArgByPos>(params) {
+            Ok(args) => args,
+            Err(response) => return response,
+        };
+    respond(ctx.call_with_overrides(request, block_id, overrides).await)
+}
+
 #[async_trait::async_trait]
 impl gen::Rpc for Context {
     async fn addDeclareTransaction(
@@ -272,13 +1305,13 @@ impl gen::Rpc for Context {
     async fn blockHashAndNumber(
         &self,
     ) -> std::result::Result<BlockHashAndNumberResult, jsonrpc::Error> {
-        self.client.blockHashAndNumber().await
+        self.quorum.blockHashAndNumber().await
     }
 
     async fn blockNumber(
         &self,
     ) -> std::result::Result<BlockNumber, jsonrpc::Error> {
-        self.client.blockNumber().await
+        self.quorum.blockNumber().await
     }
 
     async fn call(
@@ -286,14 +1319,13 @@ impl gen::Rpc for Context {
         request: FunctionCall,
         block_id: BlockId,
     ) -> std::result::Result<Vec<Felt>, jsonrpc::Error> {
-        let client = gen::client::blocking::Client::new(&self.url, Http::new());
-        let state = self.state.read().await.clone();
-
-        // TODO: address that effectively only the 'latest' block is supported
-        tracing::warn!(requested_block=?block_id, current_state=?state, "call");
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        tracing::info!(?state, "call");
+        let chain_id = self.chain_id.clone();
 
         let call_info = tokio::task::spawn_blocking(move || {
-            crate::exe::call(client.clone(), request, state)
+            crate::exe::call(&url, Http::new(), request, state, &chain_id)
         })
         .await
         .map_err(|e| {
@@ -312,7 +1344,7 @@ impl gen::Rpc for Context {
     }
 
     async fn chainId(&self) -> std::result::Result<ChainId, jsonrpc::Error> {
-        self.client.chainId().await
+        Ok(self.chain_id.clone())
     }
 
     async fn estimateFee(
@@ -321,7 +1353,27 @@ impl gen::Rpc for Context {
         simulation_flags: Vec<SimulationFlagForEstimateFee>,
         block_id: BlockId,
     ) -> std::result::Result<Vec<FeeEstimate>, jsonrpc::Error> {
-        self.client.estimateFee(request, simulation_flags, block_id).await
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        tracing::info!(?state, txs = request.len(), "estimateFee");
+        let chain_id = self.chain_id.clone();
+
+        let estimates = tokio::task::spawn_blocking(move || {
+            crate::exe::estimate_fee(
+                &url,
+                Http::new(),
+                request,
+                &simulation_flags,
+                state,
+                &chain_id,
+            )
+        })
+        .await
+        .map_err(|e| {
+            iamgroot::jsonrpc::Error::new(500, format!("join error: {e}"))
+        })??;
+
+        Ok(estimates)
     }
 
     async fn estimateMessageFee(
@@ -329,7 +1381,7 @@ impl gen::Rpc for Context {
         message: MsgFromL1,
         block_id: BlockId,
     ) -> std::result::Result<FeeEstimate, jsonrpc::Error> {
-        self.client.estimateMessageFee(message, block_id).await
+        self.quorum.estimateMessageFee(message, block_id).await
     }
 
     async fn getBlockTransactionCount(
@@ -337,28 +1389,30 @@ impl gen::Rpc for Context {
         block_id: BlockId,
     ) -> std::result::Result<GetBlockTransactionCountResult, jsonrpc::Error>
     {
-        self.client.getBlockTransactionCount(block_id).await
+        self.quorum.getBlockTransactionCount(block_id).await
     }
 
     async fn getBlockWithReceipts(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<GetBlockWithReceiptsResult, jsonrpc::Error> {
-        self.client.getBlockWithReceipts(block_id).await
+        let block = self.get_block_with_receipts(block_id).await?;
+        Ok(GetBlockWithReceiptsResult::BlockWithReceipts(block))
     }
 
     async fn getBlockWithTxHashes(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<GetBlockWithTxHashesResult, jsonrpc::Error> {
-        self.client.getBlockWithTxHashes(block_id).await
+        self.quorum.getBlockWithTxHashes(block_id).await
     }
 
     async fn getBlockWithTxs(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<GetBlockWithTxsResult, jsonrpc::Error> {
-        self.client.getBlockWithTxs(block_id).await
+        let block = self.get_block_with_txs(block_id).await?;
+        Ok(GetBlockWithTxsResult::BlockWithTxs(block))
     }
 
     async fn getClass(
@@ -366,7 +1420,16 @@ impl gen::Rpc for Context {
         block_id: BlockId,
         class_hash: Felt,
     ) -> std::result::Result<GetClassResult, jsonrpc::Error> {
-        self.client.getClass(block_id, class_hash).await
+        let class = crate::client::request(
+            &self.http,
+            &self.client.url,
+            "starknet_getClass",
+            (block_id, &class_hash),
+        )
+        .await?;
+        let class = crate::hash::class::verified(class, &class_hash)?;
+        tracing::info!(?class_hash, "getClass: verified");
+        Ok(class)
     }
 
     async fn getClassAt(
@@ -374,7 +1437,21 @@ impl gen::Rpc for Context {
         block_id: BlockId,
         contract_address: Address,
     ) -> std::result::Result<GetClassAtResult, jsonrpc::Error> {
-        self.client.getClassAt(block_id, contract_address).await
+        // The class is fetched from the very block its hash was proven at
+        let (block_id, state_root) = self.resolve_block_id(block_id).await?;
+        let class_hash = self
+            .class_hash_at(&block_id, state_root, &contract_address)
+            .await?;
+        let class = crate::client::request(
+            &self.http,
+            &self.client.url,
+            "starknet_getClassAt",
+            (block_id, &contract_address),
+        )
+        .await?;
+        let class = crate::hash::class::verified(class, &class_hash)?;
+        tracing::info!(?contract_address, ?class_hash, "getClassAt: verified");
+        Ok(class)
     }
 
     async fn getClassHashAt(
@@ -382,14 +1459,18 @@ impl gen::Rpc for Context {
         block_id: BlockId,
         contract_address: Address,
     ) -> std::result::Result<Felt, jsonrpc::Error> {
-        self.client.getClassHashAt(block_id, contract_address).await
+        let (block_id, state_root) = self.resolve_block_id(block_id).await?;
+        self.class_hash_at(&block_id, state_root, &contract_address).await
     }
 
     async fn getEvents(
         &self,
         filter: GetEventsFilter,
     ) -> std::result::Result<EventsChunk, jsonrpc::Error> {
-        self.client.getEvents(filter).await
+        let chunk = self.client.getEvents(filter.clone()).await?;
+        self.verify_events(&filter, &chunk).await?;
+        tracing::info!(events = chunk.events.len(), "getEvents: verified");
+        Ok(chunk)
     }
 
     async fn getNonce(
@@ -397,14 +1478,27 @@ impl gen::Rpc for Context {
         block_id: BlockId,
         contract_address: Address,
     ) -> std::result::Result<Felt, jsonrpc::Error> {
-        self.client.getNonce(block_id, contract_address).await
+        let (block_id, state_root) = self.resolve_block_id(block_id).await?;
+
+        let proof = self
+            .client
+            .getProof(block_id.clone(), contract_address.clone(), vec![])
+            .await?;
+
+        let contract_data = proof
+            .verify_contract(state_root, contract_address.clone())?
+            .ok_or(gen::error::CONTRACT_NOT_FOUND)?;
+        let nonce = contract_data.nonce.clone();
+        tracing::info!(?contract_address, ?block_id, ?nonce, "getNonce");
+
+        Ok(nonce)
     }
 
     async fn getStateUpdate(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<GetStateUpdateResult, jsonrpc::Error> {
-        self.client.getStateUpdate(block_id).await
+        self.quorum.getStateUpdate(block_id).await
     }
 
     async fn getStorageAt(
@@ -450,28 +1544,83 @@ impl gen::Rpc for Context {
         GetTransactionByBlockIdAndIndexResult,
         jsonrpc::Error,
     > {
-        self.client.getTransactionByBlockIdAndIndex(block_id, index).await
+        self.quorum.getTransactionByBlockIdAndIndex(block_id, index).await
     }
 
     async fn getTransactionByHash(
         &self,
         transaction_hash: TxnHash,
     ) -> std::result::Result<GetTransactionByHashResult, jsonrpc::Error> {
-        self.client.getTransactionByHash(transaction_hash).await
+        let receipt =
+            self.client.getTransactionReceipt(transaction_hash.clone()).await?;
+        let Some(block_hash) = receipt.block_hash else {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: "Pending transaction is not supported".to_owned(),
+            });
+        };
+        let block =
+            self.get_block_with_txs(BlockId::BlockHash { block_hash }).await?;
+        block
+            .block_body_with_txs
+            .transactions
+            .into_iter()
+            .find(|tx| {
+                tx.transaction_hash.0.as_ref() == transaction_hash.0.as_ref()
+            })
+            .map(|tx| GetTransactionByHashResult {
+                txn: tx.txn,
+                transaction_hash: tx.transaction_hash,
+            })
+            .ok_or_else(|| jsonrpc::Error {
+                code: -1,
+                message: "Transaction not found in its block".to_owned(),
+            })
     }
 
     async fn getTransactionReceipt(
         &self,
         transaction_hash: TxnHash,
     ) -> std::result::Result<TxnReceiptWithBlockInfo, jsonrpc::Error> {
-        self.client.getTransactionReceipt(transaction_hash).await
+        let receipt =
+            self.client.getTransactionReceipt(transaction_hash.clone()).await?;
+        let Some(block_hash) = receipt.block_hash else {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: "Pending transaction is not supported".to_owned(),
+            });
+        };
+        let block = self
+            .get_block_with_receipts(BlockId::BlockHash {
+                block_hash: block_hash.clone(),
+            })
+            .await?;
+        let block_number = block.block_header.block_number.clone();
+        block
+            .block_body_with_receipts
+            .transactions
+            .into_iter()
+            .map(|tx| tx.receipt)
+            .find(|receipt| {
+                receipt::common_properties(receipt).transaction_hash.0.as_ref()
+                    == transaction_hash.0.as_ref()
+            })
+            .map(|txn_receipt| TxnReceiptWithBlockInfo {
+                txn_receipt,
+                block_hash: Some(block_hash),
+                block_number: Some(block_number),
+            })
+            .ok_or_else(|| jsonrpc::Error {
+                code: -1,
+                message: "Transaction not found in its block".to_owned(),
+            })
     }
 
     async fn getTransactionStatus(
         &self,
         transaction_hash: TxnHash,
     ) -> std::result::Result<GetTransactionStatusResult, jsonrpc::Error> {
-        self.client.getTransactionStatus(transaction_hash).await
+        self.quorum.getTransactionStatus(transaction_hash).await
     }
 
     async fn simulateTransactions(
@@ -480,33 +1629,88 @@ impl gen::Rpc for Context {
         transactions: Vec<BroadcastedTxn>,
         simulation_flags: Vec<SimulationFlag>,
     ) -> std::result::Result<Vec<SimulatedTransaction>, jsonrpc::Error> {
-        self.client
-            .simulateTransactions(block_id, transactions, simulation_flags)
-            .await
+        let url = self.url.clone();
+        let state = self.resolve_state(block_id).await?;
+        tracing::info!(
+            ?state,
+            txs = transactions.len(),
+            "simulateTransactions"
+        );
+        let chain_id = self.chain_id.clone();
+
+        let simulated = tokio::task::spawn_blocking(move || {
+            crate::exe::simulate(
+                &url,
+                Http::new(),
+                transactions,
+                &simulation_flags,
+                state,
+                &chain_id,
+            )
+        })
+        .await
+        .map_err(|e| {
+            iamgroot::jsonrpc::Error::new(500, format!("join error: {e}"))
+        })??;
+
+        Ok(simulated)
     }
 
     async fn specVersion(&self) -> std::result::Result<String, jsonrpc::Error> {
-        self.client.specVersion().await
+        self.quorum.specVersion().await
     }
 
     async fn syncing(
         &self,
     ) -> std::result::Result<SyncingResult, jsonrpc::Error> {
-        self.client.syncing().await
+        self.quorum.syncing().await
     }
 
     async fn traceBlockTransactions(
         &self,
         block_id: BlockId,
     ) -> std::result::Result<Vec<BlockTransactionTrace>, jsonrpc::Error> {
-        self.client.traceBlockTransactions(block_id).await
+        let traces = self.trace_block(block_id, None).await?;
+        Ok(traces
+            .into_iter()
+            .map(|(transaction_hash, trace_root)| BlockTransactionTrace {
+                trace_root: Some(trace_root),
+                transaction_hash: Some(transaction_hash),
+            })
+            .collect())
     }
 
     async fn traceTransaction(
         &self,
         transaction_hash: TxnHash,
     ) -> std::result::Result<TransactionTrace, jsonrpc::Error> {
-        self.client.traceTransaction(transaction_hash).await
+        let receipt =
+            self.client.getTransactionReceipt(transaction_hash.clone()).await?;
+        let Some(block_hash) = receipt.block_hash else {
+            return Err(jsonrpc::Error {
+                code: -1,
+                message: "Pending transaction is not supported".to_owned(),
+            });
+        };
+        let mut traces = self
+            .trace_block(
+                BlockId::BlockHash { block_hash },
+                Some(&transaction_hash),
+            )
+            .await?;
+        let (_, trace) = traces.pop().ok_or_else(|| jsonrpc::Error {
+            code: -1,
+            message: "Transaction not found in its block".to_owned(),
+        })?;
+        Ok(trace)
+    }
+
+    async fn getClassProof(
+        &self,
+        block_id: gen::BlockId,
+        class_hash: gen::Felt,
+    ) -> std::result::Result<gen::GetClassProofResult, jsonrpc::Error> {
+        self.client.getClassProof(block_id, class_hash).await
     }
 
     async fn getProof(
@@ -522,11 +1726,11 @@ impl gen::Rpc for Context {
         &self,
         transaction_hash: gen::TxnHash,
     ) -> std::result::Result<gen::TxGatewayStatus, jsonrpc::Error> {
-        self.client.getTxStatus(transaction_hash).await
+        self.quorum.getTxStatus(transaction_hash).await
     }
 
     async fn version(&self) -> std::result::Result<String, jsonrpc::Error> {
-        self.client.version().await
+        self.quorum.version().await
     }
 }
 
@@ -537,22 +1741,28 @@ mod tests {
     use iamgroot::jsonrpc;
     use tokio::sync::RwLock;
     use wiremock::{
-        matchers::any, Mock, MockGuard, MockServer, ResponseTemplate,
+        matchers::{any, path, query_param},
+        Mock, MockGuard, MockServer, ResponseTemplate,
     };
 
     use crate::{
-        client::Http,
-        rpc::{BlockHash, BlockId, BlockNumber, BlockTag, Felt},
+        client::{Http, QuorumHttp, QUORUM_ERROR_CODE},
+        config::{Quorum, MAINNET_GATEWAY_PUBKEY, MAINNET_STARKNET_CHAINID},
+        feeder::GatewayClient,
+        hash::block::BlockHeader,
+        head::Reorg,
+        rpc::{BlockHash, BlockId, BlockNumber, BlockTag, ChainId, Felt},
+        store::StoredHeader,
     };
 
-    use super::{client::Client, ClientState, Context};
+    use super::{client::Client, handle, ClientState, Context};
 
     fn make_state(block_number: u64, block_hash: &str) -> ClientState {
-        ClientState {
+        ClientState::new(
             block_number,
-            block_hash: Felt::try_new(block_hash).unwrap(),
-            root: Felt::try_new("0x0").unwrap(),
-        }
+            Felt::try_new(block_hash).unwrap(),
+            Felt::try_new("0x0").unwrap(),
+        )
     }
 
     fn make_context(
@@ -563,8 +1773,19 @@ mod tests {
         let client = reqwest::Client::new();
         Context {
             url: url_local.to_string(),
-            client: Arc::new(Client::new(url_client, Http(client))),
+            client: Arc::new(Client::new(url_client, Http(client.clone()))),
+            http: Http(client.clone()),
+            quorum: Arc::new(Client::new(
+                url_client,
+                QuorumHttp::new(Http(client), vec![], Quorum::default()),
+            )),
+            gateway: Arc::new(
+                GatewayClient::new(url_client, MAINNET_GATEWAY_PUBKEY).unwrap(),
+            ),
             state: Arc::new(RwLock::new(state)),
+            reorgs: Default::default(),
+            headers: Default::default(),
+            chain_id: ChainId::try_new(MAINNET_STARKNET_CHAINID).unwrap(),
         }
     }
 
@@ -609,29 +1830,40 @@ mod tests {
         }
     }
 
-    fn get_block_with_tx_hashes_response(
-        block_num: u64,
-        block_hash: &str,
-    ) -> String {
+    fn make_header(block_num: u64) -> BlockHeader {
+        BlockHeader {
+            block_number: block_num,
+            state_root: starknet_crypto::Felt::from_hex("0x4").unwrap(),
+            parent_hash: starknet_crypto::Felt::from_hex("0x5").unwrap(),
+            sequencer_address: starknet_crypto::Felt::from_hex("0x6").unwrap(),
+            timestamp: 1,
+            starknet_version: "0.13.1".to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Valid hash of the header served for the given block number.
+    fn block_hash(block_num: u64) -> String {
+        format!("{:#x}", make_header(block_num).compute_hash())
+    }
+
+    fn get_block_response(block_num: u64, block_hash: &str) -> String {
         serde_json::to_string(&serde_json::json!(
             {
-                "jsonrpc": "2.0",
-                "id":1,
-                "result": {
-                    "block_hash": block_hash,
-                    "block_number": block_num,
-                    "l1_gas_price": {
-                        "price_in_fri": "0x2",
-                        "price_in_wei": "0x3"
-                    },
-                    "new_root": "0x4",
-                    "parent_hash": "0x5",
-                    "sequencer_address": "0x6",
-                    "starknet_version": "0.13.1",
-                    "status": "ACCEPTED_ON_L1",
-                    "timestamp": 1,
-                    "transactions" : []
-                }
+                "block_hash": block_hash,
+                "block_number": block_num,
+                "l1_gas_price": {
+                    "price_in_fri": "0x2",
+                    "price_in_wei": "0x3"
+                },
+                "state_root": "0x4",
+                "parent_block_hash": "0x5",
+                "sequencer_address": "0x6",
+                "starknet_version": "0.13.1",
+                "status": "ACCEPTED_ON_L1",
+                "timestamp": 1,
+                "transactions" : [],
+                "transaction_receipts" : []
             }
         ))
         .unwrap()
@@ -646,7 +1878,7 @@ mod tests {
     ) -> (MockGuard, Context) {
         let mock_guard = Mock::given(any())
             .respond_with(ResponseTemplate::new(200).set_body_string(
-                get_block_with_tx_hashes_response(
+                get_block_response(
                     starknet_response_block_num,
                     starknet_response_block_hash,
                 ),
@@ -661,6 +1893,70 @@ mod tests {
         (mock_guard, context)
     }
 
+    /// Headers of the blocks linked by parent hashes, each one served by the
+    /// gateway by its hash, and a context with the last one as the head.
+    async fn setup_chain(
+        starknet_server: &MockServer,
+        block_numbers: std::ops::RangeInclusive<u64>,
+    ) -> (Context, Vec<BlockHeader>) {
+        let mut parent_hash = starknet_crypto::Felt::ZERO;
+        let mut chain = Vec::new();
+        for block_number in block_numbers {
+            let header = chain_header(block_number, parent_hash);
+            mount_header(starknet_server, &header).await;
+            parent_hash = header.block_hash;
+            chain.push(header);
+        }
+        let state = ClientState::try_from(chain.last().unwrap()).unwrap();
+        let context =
+            make_context("127.0.0.1:3030", &starknet_server.uri(), state);
+        (context, chain)
+    }
+
+    fn chain_header(
+        block_number: u64,
+        parent_hash: starknet_crypto::Felt,
+    ) -> BlockHeader {
+        let mut header = BlockHeader {
+            block_number,
+            parent_hash,
+            state_root: block_number.into(),
+            ..make_header(block_number)
+        };
+        header.block_hash = header.compute_hash();
+        header
+    }
+
+    async fn mount_header(starknet_server: &MockServer, header: &BlockHeader) {
+        let block_hash = hash_of(header);
+        Mock::given(path("/feeder_gateway/get_block"))
+            .and(query_param("blockHash", block_hash.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "block_hash": block_hash,
+                    "block_number": header.block_number,
+                    "l1_gas_price": {
+                        "price_in_fri": "0x2",
+                        "price_in_wei": "0x3"
+                    },
+                    "state_root": format!("{:#x}", header.state_root),
+                    "parent_block_hash": format!("{:#x}", header.parent_hash),
+                    "sequencer_address": "0x6",
+                    "starknet_version": "0.13.1",
+                    "status": "ACCEPTED_ON_L1",
+                    "timestamp": 1,
+                    "transactions": [],
+                    "transaction_receipts": []
+                }),
+            ))
+            .mount(starknet_server)
+            .await;
+    }
+
+    fn hash_of(header: &BlockHeader) -> String {
+        format!("{:#x}", header.block_hash)
+    }
+
     async fn resolve_block_by_number_test(
         requested_starknet_block_num: u64,
         block_num: u64,
@@ -675,7 +1971,7 @@ mod tests {
             &starknet_server,
             block_num,
             starknet_response_block_num,
-            "0x3",
+            &block_hash(starknet_response_block_num),
             expect_request,
         )
         .await;
@@ -731,25 +2027,19 @@ mod tests {
 
     #[tokio::test]
     async fn resolve_block_by_number_request_lower_success() {
-        let requested_starknet_block_num = 3;
-        let state_block_num = 27;
-        let starknet_response_block_number = 3;
-        let expected_num_request = 1;
+        let starknet_server = MockServer::start().await;
+        let (context, chain) = setup_chain(&starknet_server, 3..=27).await;
 
-        let result = resolve_block_by_number_test(
-            requested_starknet_block_num,
-            state_block_num,
-            starknet_response_block_number,
-            expected_num_request,
-        )
-        .await;
+        let state = &context.state.read().await;
+        let (block_id, root) = context
+            .resolve_block_by_number(BlockNumber::try_new(3).unwrap(), state)
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
-        let (returned_block, _) = result.unwrap();
-        assert!(eq(
-            &block_from_number(requested_starknet_block_num),
-            &returned_block
-        ));
+        assert!(eq(&block_from_hash(&hash_of(&chain[0])), &block_id));
+        assert_eq!(root.as_ref(), "0x3");
+        // The headers fetched to link the block to the head are stored
+        assert_eq!(context.headers.read().await.len(), 24);
     }
 
     #[tokio::test]
@@ -813,29 +2103,108 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn resolve_block_by_number_from_header_store() {
+        let starknet_server = MockServer::start().await;
+        let (_mock_guard, context) =
+            setup_test_env(&starknet_server, 27, 3, &block_hash(3), 0).await;
+        // Stored headers linked by parent hashes up to the head (0x27)
+        store_headers(&context, 3..=27).await;
+
+        let state = &context.state.read().await;
+        let (block_id, root) = context
+            .resolve_block_by_number(BlockNumber::try_new(3).unwrap(), state)
+            .await
+            .unwrap();
+
+        assert!(eq(&block_from_hash("0x3"), &block_id));
+        assert_eq!(root.as_ref(), "0x4");
+    }
+
+    #[tokio::test]
+    async fn resolve_block_by_number_unlinked_header_store() {
+        let starknet_server = MockServer::start().await;
+        let (_mock_guard, context) =
+            setup_test_env(&starknet_server, 27, 3, &block_hash(3), 1).await;
+        // The stored block 3 is not linked to the head, and the gateway does
+        // not serve the head to link it
+        store_headers(&context, 3..=20).await;
+
+        let state = &context.state.read().await;
+        let result = context
+            .resolve_block_by_number(BlockNumber::try_new(3).unwrap(), state)
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    async fn store_headers(
+        context: &Context,
+        block_numbers: std::ops::RangeInclusive<u64>,
+    ) {
+        let mut headers = context.headers.write().await;
+        for block_number in block_numbers {
+            let felt = |n: u64| Felt::try_new(&format!("0x{n}")).unwrap();
+            headers.insert(StoredHeader {
+                block_number,
+                block_hash: felt(block_number),
+                parent_hash: felt(block_number - 1),
+                root: felt(block_number + 1),
+                timestamp: 1,
+            });
+        }
+    }
+
+    #[tokio::test]
+    async fn resolve_block_by_number_invalid_block_hash() {
+        let starknet_server = MockServer::start().await;
+        let (_mock_guard, context) =
+            setup_test_env(&starknet_server, 27, 3, &block_hash(4), 1).await;
+
+        let state = &context.state.read().await;
+        let result = context
+            .resolve_block_by_number(BlockNumber::try_new(3).unwrap(), state)
+            .await;
+
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn resolve_block_by_hash_different_success() {
-        let requested_starknet_block_hash = "0x3";
-        let state_block_num = 27;
-        let starknet_response_block_hash = requested_starknet_block_hash;
-        let starknet_response_block_num = 3;
-        let expected_num_request = 1;
+        let starknet_server = MockServer::start().await;
+        let (context, chain) = setup_chain(&starknet_server, 3..=27).await;
+        let block_hash = hash_of(&chain[0]);
 
-        let result = resolve_block_by_hash_test(
-            requested_starknet_block_hash,
-            state_block_num,
-            starknet_response_block_hash,
-            starknet_response_block_num,
-            expected_num_request,
-        )
-        .await;
+        let state = &context.state.read().await;
+        let (block_id, root) = context
+            .resolve_block_by_hash(
+                BlockHash(Felt::try_new(&block_hash).unwrap()),
+                state,
+            )
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
-        let (returned_block, _) = result.unwrap();
-        assert!(eq(
-            &block_from_hash(requested_starknet_block_hash),
-            &returned_block
-        ));
+        assert!(eq(&block_from_hash(&block_hash), &block_id));
+        assert_eq!(root.as_ref(), "0x3");
+    }
+
+    #[tokio::test]
+    async fn resolve_block_by_hash_unlinked_error() {
+        let starknet_server = MockServer::start().await;
+        let (context, _) = setup_chain(&starknet_server, 3..=27).await;
+        // A valid block that is not an ancestor of the head
+        let fork = chain_header(3, starknet_crypto::Felt::ONE);
+        mount_header(&starknet_server, &fork).await;
+
+        let state = &context.state.read().await;
+        let result = context
+            .resolve_block_by_hash(
+                BlockHash(Felt::try_new(&hash_of(&fork)).unwrap()),
+                state,
+            )
+            .await;
+
+        assert!(result.is_err());
     }
 
     #[tokio::test]
@@ -865,7 +2234,7 @@ mod tests {
 
     #[tokio::test]
     async fn resolve_block_by_hash_wrong_number_return_error() {
-        let requested_starknet_block_hash = "0x99";
+        let requested_starknet_block_hash = &block_hash(27);
         let state_block_num = 27;
         let starknet_response_block_hash = requested_starknet_block_hash;
         let starknet_response_block_num = 27;
@@ -905,45 +2274,24 @@ mod tests {
 
     #[tokio::test]
     async fn resolve_block_id_number_success() {
-        let request_block = block_from_number(3);
-        let block_num = 27;
-        let starknet_response_block_num = 3;
-        let starknet_response_block_hash = "0x3";
-        let expected_num_request = 1;
+        let starknet_server = MockServer::start().await;
+        let (context, chain) = setup_chain(&starknet_server, 3..=27).await;
 
-        let result = resolve_block_id_test(
-            request_block.clone(),
-            block_num,
-            starknet_response_block_num,
-            starknet_response_block_hash,
-            expected_num_request,
-        )
-        .await;
+        let (returned_block, _) =
+            context.resolve_block_id(block_from_number(3)).await.unwrap();
 
-        assert!(result.is_ok());
-        let (returned_block, _) = result.unwrap();
-        assert!(eq(&request_block, &returned_block));
+        assert!(eq(&block_from_hash(&hash_of(&chain[0])), &returned_block));
     }
 
     #[tokio::test]
     async fn resolve_block_id_hash_success() {
-        let request_block = block_from_hash("0x3");
-        let block_num = 27;
-        let starknet_response_block_num = 3;
-        let starknet_response_block_hash = "0x3";
-        let expected_num_request = 1;
+        let starknet_server = MockServer::start().await;
+        let (context, chain) = setup_chain(&starknet_server, 3..=27).await;
+        let request_block = block_from_hash(&hash_of(&chain[0]));
 
-        let result = resolve_block_id_test(
-            request_block.clone(),
-            block_num,
-            starknet_response_block_num,
-            starknet_response_block_hash,
-            expected_num_request,
-        )
-        .await;
+        let (returned_block, _) =
+            context.resolve_block_id(request_block.clone()).await.unwrap();
 
-        assert!(result.is_ok());
-        let (returned_block, _) = result.unwrap();
         assert!(eq(&request_block, &returned_block));
     }
 
@@ -988,4 +2336,166 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn resolve_state_latest() {
+        let starknet_server = MockServer::start().await;
+        let (_mock_guard, context) =
+            setup_test_env(&starknet_server, 27, 33, "0x33", 0).await;
+
+        let state =
+            context.resolve_state(block_from_tag("latest")).await.unwrap();
+        assert_eq!(state.block_number, 27);
+        assert_eq!(state.block_hash.as_ref(), "0x27");
+    }
+
+    #[tokio::test]
+    async fn resolve_state_historical() {
+        let starknet_server = MockServer::start().await;
+        let (context, chain) = setup_chain(&starknet_server, 3..=27).await;
+
+        let state = context.resolve_state(block_from_number(3)).await.unwrap();
+        assert_eq!(state.block_number, 3);
+        assert_eq!(state.block_hash.as_ref(), &hash_of(&chain[0]));
+        assert_eq!(state.root.as_ref(), "0x3");
+    }
+
+    #[tokio::test]
+    async fn get_reorgs() {
+        let starknet_server = MockServer::start().await;
+        let context = make_context(
+            "127.0.0.1:3030",
+            &starknet_server.uri(),
+            make_state(11, "0xbb"),
+        );
+        context.reorgs.write().await.push(Reorg {
+            ancestor: 10,
+            dropped: vec![(11, Felt::try_new("0xb").unwrap())],
+            head: make_state(11, "0xbb"),
+        });
+
+        let req = jsonrpc::Request::new(
+            "beerus_getReorgs".to_string(),
+            serde_json::json!([]),
+        )
+        .with_id(jsonrpc::Id::Number(1));
+        let res = handle(&context, &req).await;
+        assert_eq!(
+            res.result.unwrap(),
+            serde_json::json!([{
+                "ancestor": 10,
+                "dropped": [{"block_number": 11, "block_hash": "0xb"}],
+                "head": {"block_number": 11, "block_hash": "0xbb"}
+            }])
+        );
+    }
+
+    async fn mock_block_number(block_number: u64) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "result": block_number,
+                    "id": 1
+                }),
+            ))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn quorum_of_providers() {
+        use crate::gen::Rpc;
+
+        let servers = [
+            mock_block_number(7).await,
+            mock_block_number(7).await,
+            mock_block_number(8).await,
+        ];
+        let client = |quorum: Quorum, providers: &[MockServer]| {
+            Client::new(
+                &servers[0].uri(),
+                QuorumHttp::new(
+                    Http::new(),
+                    providers.iter().map(|server| server.uri()).collect(),
+                    quorum,
+                ),
+            )
+        };
+
+        let block_number =
+            client(Quorum::Majority, &servers[1..]).blockNumber().await;
+        assert_eq!(*block_number.unwrap().as_ref(), 7);
+
+        let error =
+            client(Quorum::All, &servers[1..]).blockNumber().await.unwrap_err();
+        assert_eq!(error.code, QUORUM_ERROR_CODE);
+
+        let error = client(Quorum::Majority, &servers[2..])
+            .blockNumber()
+            .await
+            .unwrap_err();
+        assert_eq!(error.code, QUORUM_ERROR_CODE);
+    }
+
+    #[test]
+    fn matches_event_filter() {
+        let event: super::Event = serde_json::from_value(serde_json::json!({
+            "from_address": "0x1234",
+            "keys": ["0x99", "0xaa"],
+            "data": []
+        }))
+        .unwrap();
+        let filter = |value: serde_json::Value| -> super::EventFilter {
+            serde_json::from_value(value).unwrap()
+        };
+
+        assert!(super::matches_filter(&filter(serde_json::json!({})), &event));
+        assert!(super::matches_filter(
+            &filter(serde_json::json!({
+                "address": "0x1234",
+                "keys": [[], ["0xbb", "0xAA"]]
+            })),
+            &event
+        ));
+        assert!(!super::matches_filter(
+            &filter(serde_json::json!({"address": "0x5678"})),
+            &event
+        ));
+        assert!(!super::matches_filter(
+            &filter(serde_json::json!({"keys": [["0x99"], [], ["0x1"]]})),
+            &event
+        ));
+    }
+
+    #[test]
+    fn contiguous_run_of_events() {
+        let expected = [1, 2, 3, 4];
+
+        assert!(super::is_contiguous_run(
+            &expected,
+            &[1, 2, 3, 4],
+            false,
+            false
+        ));
+        assert!(super::is_contiguous_run(&expected, &[3, 4], true, false));
+        assert!(super::is_contiguous_run(&expected, &[1, 2], false, true));
+        assert!(super::is_contiguous_run(&expected, &[2, 3], true, true));
+
+        assert!(!super::is_contiguous_run(&expected, &[1, 2, 3], false, false));
+        assert!(!super::is_contiguous_run(&expected, &[1, 3, 4], true, true));
+        assert!(!super::is_contiguous_run(&expected, &[2, 3], false, true));
+        assert!(!super::is_contiguous_run(
+            &expected,
+            &[1, 2, 3, 4, 5],
+            true,
+            true
+        ));
+
+        // a block in the range without returned events must have none
+        assert!(super::is_contiguous_run::<u8>(&[], &[], false, false));
+        assert!(!super::is_contiguous_run(&expected, &[], false, false));
+    }
 }
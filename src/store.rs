@@ -0,0 +1,375 @@
+use std::{
+    collections::BTreeMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+};
+
+use eyre::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{
+    client::State,
+    feeder::GatewayClient,
+    gen::Felt,
+    hash::block::BlockHeader,
+    head::{walk_parents, KnownHeaders},
+};
+
+const HEADERS_FILE: &str = "headers.jsonl";
+
+/// Header of a block that is linked by parent hashes to a trusted head.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StoredHeader {
+    pub block_number: u64,
+    pub block_hash: Felt,
+    pub parent_hash: Felt,
+    pub root: Felt,
+    pub timestamp: u64,
+}
+
+impl TryFrom<&BlockHeader> for StoredHeader {
+    type Error = iamgroot::jsonrpc::Error;
+
+    fn try_from(header: &BlockHeader) -> Result<Self, Self::Error> {
+        Ok(Self {
+            block_number: header.block_number,
+            block_hash: Felt::try_new(&format!("{:#x}", header.block_hash))?,
+            parent_hash: Felt::try_new(&format!("{:#x}", header.parent_hash))?,
+            root: Felt::try_new(&format!("{:#x}", header.state_root))?,
+            timestamp: header.timestamp,
+        })
+    }
+}
+
+/// Verified block headers, appended to a file in the data directory (when
+/// one is given) and loaded back on start. Later records override earlier
+/// ones, so a header replaced after a reorg wins on the next load. The file is
+/// written by [`save`] and [`truncate`] outside of the lock of the store.
+#[derive(Debug, Default)]
+pub struct HeaderStore {
+    path: Option<PathBuf>,
+    headers: BTreeMap<u64, StoredHeader>,
+}
+
+impl HeaderStore {
+    pub fn open<P: AsRef<Path>>(data_dir: P) -> Result<Self> {
+        let path = data_dir.as_ref().join(HEADERS_FILE);
+        let mut headers = BTreeMap::new();
+        if path.exists() {
+            let file = File::open(&path).context("failed to open headers")?;
+            for line in BufReader::new(file).lines() {
+                let line = line.context("failed to read headers")?;
+                if line.is_empty() {
+                    continue;
+                }
+                let header: StoredHeader = serde_json::from_str(&line)
+                    .context("failed to parse header")?;
+                headers.insert(header.block_number, header);
+            }
+        }
+        tracing::info!(?path, headers = headers.len(), "header store opened");
+        Ok(Self { path: Some(path), headers })
+    }
+
+    pub fn get(&self, block_number: u64) -> Option<&StoredHeader> {
+        self.headers.get(&block_number)
+    }
+
+    /// The stored header of the block, only if the stored headers link it by
+    /// parent hashes to the given head: a header left from a fork (e.g. one
+    /// loaded from the file after a reorg happened offline) is not returned.
+    pub fn get_linked(
+        &self,
+        block_number: u64,
+        (head_number, head_hash): (u64, &Felt),
+    ) -> Option<&StoredHeader> {
+        let mut expected = (head_number, head_hash);
+        for (_, header) in self.headers.range(block_number..=head_number).rev()
+        {
+            if header.block_number != expected.0
+                || header.block_hash.as_ref() != expected.1.as_ref()
+            {
+                return None;
+            }
+            if header.block_number == block_number {
+                return Some(header);
+            }
+            expected = (header.block_number - 1, &header.parent_hash);
+        }
+        None
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Keep the header in memory only: see [`save`] to persist it as well.
+    pub fn insert(&mut self, header: StoredHeader) {
+        self.headers.insert(header.block_number, header);
+    }
+
+    /// Follow the parent hashes from the given block down through the stored
+    /// headers and return the first block that is missing (or belongs to a
+    /// different fork), or `None` when the chain is complete to genesis.
+    pub fn first_gap(
+        &self,
+        mut block_number: u64,
+        block_hash: &Felt,
+    ) -> Option<(u64, Felt)> {
+        let mut block_hash = block_hash.clone();
+        while let Some(header) = self.headers.get(&block_number) {
+            if header.block_hash.as_ref() != block_hash.as_ref() {
+                break;
+            }
+            if block_number == 0 {
+                return None;
+            }
+            block_hash = header.parent_hash.clone();
+            block_number -= 1;
+        }
+        Some((block_number, block_hash))
+    }
+}
+
+// A store that is being written to is skipped rather than waited for: the
+// headers are then fetched from the gateway.
+impl KnownHeaders for RwLock<HeaderStore> {
+    fn link(&self, block_number: u64) -> Option<(Felt, Felt)> {
+        let store = self.try_read().ok()?;
+        let header = store.get(block_number)?;
+        Some((header.block_hash.clone(), header.parent_hash.clone()))
+    }
+}
+
+/// Append the headers to the file of the store, then add them to the store.
+pub async fn save(
+    store: &RwLock<HeaderStore>,
+    headers: Vec<StoredHeader>,
+) -> Result<()> {
+    if headers.is_empty() {
+        return Ok(());
+    }
+    let path = store.read().await.path.clone();
+    if let Some(path) = path {
+        let lines = to_lines(&headers)?;
+        write_file(path, lines, true).await?;
+    }
+    let mut store = store.write().await;
+    for header in headers {
+        store.insert(header);
+    }
+    Ok(())
+}
+
+/// Drop the headers above the given block (e.g. the ones replaced by a reorg)
+/// from the store, then rewrite the file without them.
+pub async fn truncate(
+    store: &RwLock<HeaderStore>,
+    block_number: u64,
+) -> Result<usize> {
+    let dropped = store.write().await.headers.split_off(&(block_number + 1));
+    if dropped.is_empty() {
+        return Ok(0);
+    }
+    let (path, lines) = {
+        let store = store.read().await;
+        (store.path.clone(), to_lines(store.headers.values())?)
+    };
+    if let Some(path) = path {
+        write_file(path, lines, false).await?;
+    }
+    Ok(dropped.len())
+}
+
+fn to_lines<'a>(
+    headers: impl IntoIterator<Item = &'a StoredHeader>,
+) -> Result<String> {
+    let mut lines = String::new();
+    for header in headers {
+        lines.push_str(&serde_json::to_string(header)?);
+        lines.push('\n');
+    }
+    Ok(lines)
+}
+
+// Files are written on the blocking pool, so that the runtime is not blocked
+async fn write_file(path: PathBuf, lines: String, append: bool) -> Result<()> {
+    tokio::task::spawn_blocking(move || -> Result<()> {
+        if append {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .context("failed to open headers")?;
+            file.write_all(lines.as_bytes())
+                .context("failed to write headers")?;
+        } else {
+            // Replace the file at once, so that it is never left half written
+            let temp = path.with_extension("tmp");
+            std::fs::write(&temp, lines).context("failed to write headers")?;
+            std::fs::rename(&temp, &path).context("failed to write headers")?;
+        }
+        Ok(())
+    })
+    .await?
+}
+
+/// Walk the parent hashes back from the trusted head, fetch the missing
+/// headers from the feeder gateway (each one checked to be the very block
+/// expected) and store them. At most `limit` headers are fetched per call.
+pub async fn sync(
+    store: &RwLock<HeaderStore>,
+    gateway: &GatewayClient,
+    head: &State,
+    limit: usize,
+) -> Result<usize> {
+    let mut from = (head.block_number, head.block_hash.clone());
+    let mut synced = 0;
+    while synced < limit {
+        let Some(gap) = store.read().await.first_gap(from.0, &from.1) else {
+            break;
+        };
+        // The walk fills the gap down to the next stored header
+        let is_stored = |block_number: u64, block_hash: &Felt| {
+            store
+                .link(block_number)
+                .map(|(hash, _)| hash.as_ref() == block_hash.as_ref())
+                .unwrap_or_default()
+        };
+        let walk =
+            walk_parents(gateway, None, gap, limit - synced, is_stored).await?;
+
+        let headers = walk
+            .fetched
+            .iter()
+            .map(StoredHeader::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| eyre::eyre!("invalid header: {}", e.message))?;
+        synced += headers.len();
+        save(store, headers).await?;
+        let Some(end) = walk.end else {
+            break;
+        };
+        from = end;
+    }
+    Ok(synced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felt(hex: &str) -> Felt {
+        Felt::try_new(hex).unwrap()
+    }
+
+    fn header(block_number: u64, hash: &str, parent: &str) -> StoredHeader {
+        StoredHeader {
+            block_number,
+            block_hash: felt(hash),
+            parent_hash: felt(parent),
+            root: felt("0x1"),
+            timestamp: 1,
+        }
+    }
+
+    #[test]
+    fn test_first_gap() {
+        let mut store = HeaderStore::default();
+        assert_eq!(store.first_gap(3, &felt("0x3")).map(|(n, _)| n), Some(3));
+
+        store.insert(header(3, "0x3", "0x2"));
+        store.insert(header(2, "0x2", "0x1"));
+        let (block_number, block_hash) =
+            store.first_gap(3, &felt("0x3")).unwrap();
+        assert_eq!(block_number, 1);
+        assert_eq!(block_hash.as_ref(), "0x1");
+
+        // the stored block 3 belongs to a different fork
+        let (block_number, block_hash) =
+            store.first_gap(3, &felt("0x33")).unwrap();
+        assert_eq!(block_number, 3);
+        assert_eq!(block_hash.as_ref(), "0x33");
+
+        store.insert(header(1, "0x1", "0x0"));
+        store.insert(header(0, "0x0", "0x0"));
+        assert!(store.first_gap(3, &felt("0x3")).is_none());
+    }
+
+    #[test]
+    fn test_get_linked() {
+        let mut store = HeaderStore::default();
+        store.insert(header(1, "0x1", "0x0"));
+        store.insert(header(2, "0x2", "0x1"));
+        store.insert(header(3, "0x3", "0x2"));
+        let hash = |header: Option<&StoredHeader>| {
+            header.map(|header| header.block_hash.as_ref().to_owned())
+        };
+
+        let head = felt("0x3");
+        assert_eq!(hash(store.get_linked(1, (3, &head))).unwrap(), "0x1");
+        assert_eq!(hash(store.get_linked(3, (3, &head))).unwrap(), "0x3");
+
+        // The head is on a different fork than the stored block 3
+        let head = felt("0x33");
+        assert!(store.get_linked(1, (3, &head)).is_none());
+
+        // The block 4 (the head) is not stored yet
+        let head = felt("0x4");
+        assert!(store.get_linked(1, (4, &head)).is_none());
+
+        // A replaced header breaks the link to the head
+        store.insert(header(2, "0x22", "0x1"));
+        let head = felt("0x3");
+        assert!(store.get_linked(1, (3, &head)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_truncate() {
+        let dir = std::env::temp_dir()
+            .join(format!("beerus-truncate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let store = RwLock::new(HeaderStore::open(&dir).unwrap());
+        let headers = vec![
+            header(1, "0x1", "0x0"),
+            header(2, "0x2", "0x1"),
+            header(3, "0x3", "0x2"),
+        ];
+        save(&store, headers).await.unwrap();
+        assert_eq!(truncate(&store, 1).await.unwrap(), 2);
+        assert_eq!(truncate(&store, 1).await.unwrap(), 0);
+        assert_eq!(store.read().await.len(), 1);
+
+        let store = HeaderStore::open(&dir).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(store.get(2).is_none());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persisted_headers() {
+        let dir = std::env::temp_dir()
+            .join(format!("beerus-headers-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let store = RwLock::new(HeaderStore::open(&dir).unwrap());
+        let headers = vec![header(2, "0x2", "0x1"), header(1, "0x1", "0x0")];
+        save(&store, headers).await.unwrap();
+        save(&store, vec![header(2, "0x22", "0x1")]).await.unwrap();
+
+        let store = HeaderStore::open(&dir).unwrap();
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.get(2).unwrap().block_hash.as_ref(), "0x22");
+        assert_eq!(store.get(1).unwrap().block_hash.as_ref(), "0x1");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
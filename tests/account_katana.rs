@@ -2,6 +2,8 @@ use std::{sync::Arc, thread, time};
 
 use beerus::{
     client::{Http, State},
+    config::MAINNET_GATEWAY_PUBKEY,
+    feeder::GatewayClient,
     gen::{
         client::Client, Address, BlockId, BlockTag, BroadcastedDeclareTxn,
         BroadcastedDeployAccountTxn, BroadcastedInvokeTxn, BroadcastedTxn,
@@ -10,7 +12,7 @@ use beerus::{
         InvokeTxnV1Type, InvokeTxnV1Version, Rpc, SimulationFlagForEstimateFee,
         TxnHash,
     },
-    rpc::{serve, Server},
+    rpc::{serve, RpcConfig, Server},
 };
 use common::err::Error;
 use starknet::{
@@ -37,17 +39,12 @@ async fn setup() -> (Katana, Client<Http>) {
 
 async fn setup_beerus_with_katana() -> Result<(Server, Katana), Error> {
     let katana = Katana::init("http://127.0.0.1:0").await?;
-    let state = State {
-        block_number: 0,
-        block_hash: Felt::try_new("0x0")?,
-        root: Felt::try_new("0x0")?,
-    };
-    let beerus = serve(
-        &format!("http://127.0.0.1:{}", katana.port()),
-        "127.0.0.1:0",
-        Arc::new(RwLock::new(state)),
-    )
-    .await?;
+    let state = State::new(0, Felt::try_new("0x0")?, Felt::try_new("0x0")?);
+    let url = format!("http://127.0.0.1:{}", katana.port());
+    let gateway = GatewayClient::new(&url, MAINNET_GATEWAY_PUBKEY).unwrap();
+    let state = Arc::new(RwLock::new(state));
+    let beerus =
+        serve(RpcConfig::new(&url, gateway, state), "127.0.0.1:0").await?;
     Ok((beerus, katana))
 }
 
@@ -8,6 +8,8 @@ pub enum Error {
     Var(#[from] std::env::VarError),
     #[error("execution failed: {0:?}")]
     Exe(#[from] beerus::exe::err::Error),
+    #[error("http request failed: {0:?}")]
+    Http(#[from] reqwest::Error),
     #[error("serde failed: {0:?}")]
     Json(#[from] serde_json::Error),
     #[error("starknet api error: {0:?}")]
@@ -16,4 +18,6 @@ pub enum Error {
     IO(#[from] std::io::Error),
     #[error("Anyhow error: {0:?}")]
     Anyhow(#[from] anyhow::Error),
+    #[error("Eyre error: {0:?}")]
+    Eyre(#[from] eyre::Report),
 }
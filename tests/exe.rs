@@ -1,8 +1,13 @@
 use beerus::{
     client::{Http, State},
-    exe::call,
+    exe::{call, call_with_state_diff, estimate_fee, simulate},
     gen::{self, blocking::Rpc, client::blocking::Client, FunctionCall},
+    hash::{
+        block::{GasPrice, L1DaMode},
+        tx::contract_address,
+    },
 };
+use starknet_crypto::Felt as StarkFelt;
 
 mod common;
 
@@ -34,7 +39,9 @@ fn test_call_deprecated_contract_class() -> Result<(), Error> {
     let function_call: FunctionCall = serde_json::from_value(json)?;
 
     let state = get_latest_state(&client);
-    let call_info = call(client, function_call, state)?;
+    let chain_id = client.chainId()?;
+    let call_info =
+        call(&client.url, Http::new(), function_call, state, &chain_id)?;
 
     assert!(call_info.execution.retdata.0.is_empty());
 
@@ -53,7 +60,9 @@ fn test_call_regular_contract_class() -> Result<(), Error> {
     let function_call: FunctionCall = serde_json::from_value(json)?;
 
     let state = get_latest_state(&client);
-    let call_info = call(client, function_call, state)?;
+    let chain_id = client.chainId()?;
+    let call_info =
+        call(&client.url, Http::new(), function_call, state, &chain_id)?;
 
     assert_eq!(call_info.execution.retdata.0.len(), 1);
     assert_eq!(
@@ -76,7 +85,9 @@ fn test_issue861() -> Result<(), Error> {
     let function_call: FunctionCall = serde_json::from_value(json)?;
 
     let state = get_latest_state(&client);
-    let call_info = call(client, function_call, state)?;
+    let chain_id = client.chainId()?;
+    let call_info =
+        call(&client.url, Http::new(), function_call, state, &chain_id)?;
 
     assert_eq!(call_info.execution.retdata.0.len(), 2);
     assert_eq!(call_info.execution.retdata.0[1].to_hex_string(), "0x0");
@@ -84,16 +95,190 @@ fn test_issue861() -> Result<(), Error> {
     Ok(())
 }
 
+#[test]
+fn test_call_deploys_contract() -> Result<(), Error> {
+    let client = client!();
+
+    // Universal Deployer Contract: deployContract(class_hash, salt, unique,
+    // calldata) with the OpenZeppelin account class and a public key
+    let class_hash =
+        "0x61dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f";
+    let salt = "0x1234";
+    let public_key = "0x5678";
+    let json = serde_json::json!({
+        "calldata": [class_hash, salt, "0x0", "0x1", public_key],
+        "contract_address": "0x41a78e741e5af2fec34b695679bc6891742439f7afb8484ecd7766661ad02bf",
+        "entry_point_selector": "0x1987cbd17808b9a23693d4de7e246a443cfe37e6e7fbaeabd7d7e6532b07c3d"
+    });
+    let function_call: FunctionCall = serde_json::from_value(json)?;
+
+    let state = get_latest_state(&client);
+    let chain_id = client.chainId()?;
+    let (call_info, state_diff) = call_with_state_diff(
+        &client.url,
+        Http::new(),
+        function_call,
+        state,
+        &chain_id,
+    )?;
+
+    // The contract did not exist in the block, so the deployment reads the
+    // proven empty class hash at its address
+    let felt = |hex: &str| StarkFelt::from_hex(hex).unwrap();
+    let address =
+        contract_address(felt(salt), felt(class_hash), &[felt(public_key)]);
+    assert_eq!(call_info.execution.retdata.0, vec![address]);
+    assert_eq!(call_info.inner_calls.len(), 1);
+
+    // The constructor of the account stores the public key
+    let address = address.to_hex_string();
+    assert_eq!(state_diff.deployed_contracts.len(), 1);
+    let deployed = &state_diff.deployed_contracts[0];
+    assert_eq!(deployed.address.as_ref(), address);
+    assert_eq!(deployed.class_hash.as_ref(), class_hash);
+    assert!(state_diff.replaced_classes.is_empty());
+    let storage = state_diff
+        .storage_diffs
+        .iter()
+        .find(|diff| diff.address.as_ref() == address)
+        .expect("storage of the deployed contract");
+    assert!(storage.storage_entries.iter().any(|entry| {
+        entry.value.as_ref().map(|value| value.as_ref()) == Some(public_key)
+    }));
+
+    Ok(())
+}
+
+#[test]
+fn test_estimate_fee() -> Result<(), Error> {
+    let client = client!();
+
+    let (txn, block_id) = replayed_invoke(&client)?;
+    let flags = vec![];
+    let expected = client.estimateFee(
+        vec![txn.clone()],
+        flags.clone(),
+        block_id.clone(),
+    )?;
+
+    let state = get_state(&client, block_id);
+    let chain_id = client.chainId()?;
+    let ret = estimate_fee(
+        &client.url,
+        Http::new(),
+        vec![txn],
+        &flags,
+        state,
+        &chain_id,
+    )?;
+
+    assert_eq!(ret.len(), 1);
+    assert_eq!(serde_json::to_value(&ret)?, serde_json::to_value(&expected)?);
+    let fee = |felt: &gen::Felt| {
+        u128::from_str_radix(felt.as_ref().trim_start_matches("0x"), 16)
+            .unwrap()
+    };
+    let data_gas = ret[0].data_gas_consumed.as_ref().unwrap();
+    let data_gas_price = ret[0].data_gas_price.as_ref().unwrap();
+    assert_eq!(
+        fee(&ret[0].overall_fee),
+        fee(&ret[0].gas_consumed) * fee(&ret[0].gas_price)
+            + fee(data_gas) * fee(data_gas_price)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_simulate_estimates_fee() -> Result<(), Error> {
+    let client = client!();
+
+    let (txn, block_id) = replayed_invoke(&client)?;
+    let state = get_state(&client, block_id);
+    let chain_id = client.chainId()?;
+
+    // Without the fee charge, simulating runs exactly what estimating does
+    let flags = vec![gen::SimulationFlag::SkipFeeCharge];
+    let simulated = simulate(
+        &client.url,
+        Http::new(),
+        vec![txn.clone()],
+        &flags,
+        state.clone(),
+        &chain_id,
+    )?;
+    let estimated = estimate_fee(
+        &client.url,
+        Http::new(),
+        vec![txn],
+        &[],
+        state,
+        &chain_id,
+    )?;
+
+    assert_eq!(simulated.len(), 1);
+    let simulated = simulated[0].fee_estimation.as_ref().unwrap();
+    assert_eq!(
+        serde_json::to_value(simulated)?,
+        serde_json::to_value(&estimated[0])?
+    );
+
+    Ok(())
+}
+
+// An invoke transaction of mainnet, to be executed again on the state of the
+// block before it
+fn replayed_invoke(
+    client: &Client<Http>,
+) -> Result<(gen::BroadcastedTxn, gen::BlockId), Error> {
+    let transaction_hash = gen::TxnHash(gen::Felt::try_new(
+        "0x4c1672e824b5cd7477fca31ee3ab5a1058534ed1820bb27abc976c2e6095151",
+    )?);
+    let receipt = client.getTransactionReceipt(transaction_hash.clone())?;
+    let block_number = *receipt.block_number.unwrap().as_ref();
+    let gen::Txn::InvokeTxn(txn) =
+        client.getTransactionByHash(transaction_hash)?.txn
+    else {
+        panic!("invoke transaction expected");
+    };
+    let txn = gen::BroadcastedTxn::BroadcastedInvokeTxn(
+        gen::BroadcastedInvokeTxn(txn),
+    );
+    let block_number = gen::BlockNumber::try_new(block_number - 1)?;
+    Ok((txn, gen::BlockId::BlockNumber { block_number }))
+}
+
 fn get_state(client: &Client<Http>, block_id: gen::BlockId) -> State {
     let block = client.getBlockWithTxHashes(block_id).unwrap();
     let gen::GetBlockWithTxHashesResult::BlockWithTxHashes(block) = block
     else {
         panic!("Pending block received");
     };
+    let header = block.block_header;
+    let felt = |felt: &gen::Felt| StarkFelt::from_hex(felt.as_ref()).unwrap();
+    let gas_price = |price: &gen::ResourcePrice| GasPrice {
+        price_in_wei: felt(&price.price_in_wei),
+        price_in_fri: felt(&price.price_in_fri),
+    };
     State {
-        block_number: *block.block_header.block_number.as_ref() as u64,
-        block_hash: block.block_header.block_hash.0,
-        root: block.block_header.new_root,
+        timestamp: *header.timestamp.as_ref() as u64,
+        sequencer_address: felt(&header.sequencer_address),
+        l1_gas_price: gas_price(&header.l1_gas_price),
+        l1_data_gas_price: header
+            .l1_data_gas_price
+            .as_ref()
+            .map(gas_price)
+            .unwrap_or_default(),
+        l1_da_mode: match header.l1_da_mode {
+            Some(gen::BlockHeaderL1DaMode::Blob) => L1DaMode::Blob,
+            _ => L1DaMode::Calldata,
+        },
+        starknet_version: header.starknet_version.clone(),
+        ..State::new(
+            *header.block_number.as_ref() as u64,
+            header.block_hash.0,
+            header.new_root,
+        )
     }
 }
 
@@ -1,15 +1,23 @@
 use std::{thread, time};
 
 use beerus::{
-    config::MAINNET_STARKNET_CHAINID,
+    client::Http,
+    config::{
+        get_gateway_url, MAINNET_GATEWAY_PUBKEY, MAINNET_STARKNET_CHAINID,
+    },
+    feeder::GatewayClient,
     gen::{
-        Address, BlockHash, BlockId, BlockNumber, BlockTag,
-        BroadcastedInvokeTxn, BroadcastedTxn, Felt, FunctionCall,
-        GetBlockWithTxHashesResult, GetBlockWithTxsResult, GetClassAtResult,
-        GetClassResult, GetTransactionByBlockIdAndIndexIndex, InvokeTxn,
-        InvokeTxnV1, InvokeTxnV1Version, PriceUnit, Rpc, StorageKey,
-        SyncingResult, Txn, TxnExecutionStatus, TxnHash, TxnReceipt,
-        TxnReceiptWithBlockInfo, TxnStatus,
+        client::Client, Address, BlockHash, BlockId, BlockNumber, BlockTag,
+        BlockTransactionTrace, BroadcastedInvokeTxn, BroadcastedTxn, Felt,
+        FunctionCall, GetBlockWithTxHashesResult, GetBlockWithTxsResult,
+        GetClassAtResult, GetClassResult, GetTransactionByBlockIdAndIndexIndex,
+        InvokeTxn, InvokeTxnV1, InvokeTxnV1Version, PriceUnit, Rpc, StorageKey,
+        SyncingResult, TransactionTrace, Txn, TxnExecutionStatus, TxnHash,
+        TxnReceipt, TxnReceiptWithBlockInfo, TxnStatus,
+    },
+    hash::{
+        block::parse_version,
+        class::{legacy_class_hash, sierra_class_hash},
     },
 };
 
@@ -275,6 +283,86 @@ async fn test_getStorageAt() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn test_getStorageAtBatch() -> Result<(), Error> {
+    let ctx = setup!();
+
+    let url = format!("http://localhost:{}/rpc", ctx.server.port());
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "beerus_getStorageAtBatch",
+        "params": {
+            "contract_address": "0x6a05844a03bb9e744479e3298f54705a35966ab04140d3d8dd797c1f6dc49d0",
+            "keys": [
+                "0x0341c1bdfd89f69748aa00b5742b03adbffd79b8e80cab5c50d91cd8c2a79be1",
+                "0x0341c1bdfd89f69748aa00b5742b03adbffd79b8e80cab5c50d91cd8c2a79be1"
+            ],
+            "block_id": { "block_number": 600612 }
+        }
+    });
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    const EXPECTED: &str = "0x47616d65206f66204c69666520546f6b656e";
+    assert_eq!(response["result"], serde_json::json!([EXPECTED, EXPECTED]));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multicall() -> Result<(), Error> {
+    let ctx = setup!();
+
+    let url = format!("http://localhost:{}/rpc", ctx.server.port());
+    let erc20_address =
+        "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "beerus_multicall",
+        "params": {
+            "calls": [
+                {
+                    "contract_address": erc20_address,
+                    "entry_point_selector": "0x361458367e696363fbcc70777d07ebbd2394e89fd0adcaf147faccd1d294d60",
+                    "calldata": []
+                },
+                {
+                    "contract_address": erc20_address,
+                    "entry_point_selector": "0x1",
+                    "calldata": []
+                },
+                {
+                    "contract_address": erc20_address,
+                    "entry_point_selector": "0x4c4fb1ab068f6039d5780c68dd0fa2f8742cceb3426d19667778ca7f3518a9",
+                    "calldata": []
+                }
+            ],
+            "block_id": "latest"
+        }
+    });
+    let response: serde_json::Value = reqwest::Client::new()
+        .post(url)
+        .json(&request)
+        .send()
+        .await?
+        .json()
+        .await?;
+
+    let calls = &response["result"];
+    assert_eq!(calls[0], serde_json::json!({ "result": ["0x4574686572"] }));
+    // A failed call does not fail the others
+    assert!(calls[1]["error"]["message"].is_string());
+    assert_eq!(calls[2], serde_json::json!({ "result": ["0x12"] }));
+    Ok(())
+}
+
 #[tokio::test]
 #[allow(non_snake_case)]
 async fn test_getProof() -> Result<(), Error> {
@@ -344,6 +432,29 @@ async fn test_getTransactionReceipt() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_block_hash_mainnet_headers() -> Result<(), Error> {
+    let client = client!();
+    let gateway_url = get_gateway_url(&client.url).await?;
+    let gateway = GatewayClient::new(gateway_url, MAINNET_GATEWAY_PUBKEY)?;
+
+    // Blocks hashed with Pedersen (before 0.13.2) and with Poseidon (since
+    // 0.13.2, with the L2 gas price since 0.13.4): the header of each must
+    // hash to the block hash the gateway returns with it
+    let mut versions = Vec::new();
+    for block_number in [183862, 650000, 700000, 1200000] {
+        let block_id = BlockId::BlockNumber {
+            block_number: BlockNumber::try_new(block_number)?,
+        };
+        let header = gateway.get_header(&block_id).await?;
+        assert_eq!(header.compute_hash(), header.block_hash);
+        versions.push(parse_version(&header.starknet_version));
+    }
+    assert!(versions.iter().any(|version| version < &[0, 13, 2, 0]));
+    assert!(versions.iter().any(|version| version >= &[0, 13, 2, 0]));
+    Ok(())
+}
+
 #[tokio::test]
 #[allow(non_snake_case)]
 async fn test_getClass() -> Result<(), Error> {
@@ -405,6 +516,44 @@ async fn test_getClassAt() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn test_getClass_class_hashes() -> Result<(), Error> {
+    let ctx = setup!();
+    let url = std::env::var("STARKNET_MAINNET_URL")?;
+
+    let block_id = BlockId::BlockTag(BlockTag::Latest);
+
+    // Classes declared on mainnet: two legacy (Cairo 0) ones, then two Sierra
+    // ones, each hashed over the JSON exactly as the node serves it
+    let class_hashes = [
+        "0xd0e183745e9dae3e4e78a8ffedcce0903fc4900beace4e0abf192d4c202da3",
+        "0x25ec026985a3bf9d0cc1fe17326b245dfdc3ff89b8fde106542a3ea56c5a918",
+        "0x1a736d6ed154502257f02b1ccdf4d9d1089f80811cd6acad48e6b6a9d1f2003",
+        "0x61dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f",
+    ];
+    for class_hash in class_hashes {
+        let class_hash = Felt::try_new(class_hash)?;
+        let class = beerus::client::request(
+            &Http::new(),
+            &url,
+            "starknet_getClass",
+            (&block_id, &class_hash),
+        )
+        .await?;
+        let computed = if class.get("sierra_program").is_some() {
+            sierra_class_hash(&serde_json::from_value(class)?)?
+        } else {
+            legacy_class_hash(&class)?
+        };
+        assert_eq!(format!("{computed:#x}"), class_hash.as_ref());
+
+        // Served once verified
+        ctx.client.getClass(block_id.clone(), class_hash).await?;
+    }
+    Ok(())
+}
+
 #[tokio::test]
 #[allow(non_snake_case)]
 async fn test_getClassHashAt() -> Result<(), Error> {
@@ -424,6 +573,55 @@ async fn test_getClassHashAt() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+#[allow(non_snake_case)]
+async fn test_traceBlockTransactions() -> Result<(), Error> {
+    let ctx = setup!();
+    let url = std::env::var("STARKNET_MAINNET_URL")?;
+    let node = Client::new(&url, Http::new());
+
+    let block_id =
+        BlockId::BlockNumber { block_number: BlockNumber::try_new(652076)? };
+
+    let expected = node.traceBlockTransactions(block_id.clone()).await?;
+    let ret = ctx.client.traceBlockTransactions(block_id).await?;
+    assert_eq!(ret.len(), expected.len());
+
+    // entries may come in any order, so both sides are sorted
+    fn normalized(trace: &TransactionTrace) -> serde_json::Value {
+        fn sort(value: serde_json::Value) -> serde_json::Value {
+            match value {
+                serde_json::Value::Array(items) => {
+                    let mut items: Vec<_> =
+                        items.into_iter().map(sort).collect();
+                    items.sort_by_key(|item| item.to_string());
+                    serde_json::Value::Array(items)
+                }
+                serde_json::Value::Object(map) => serde_json::Value::Object(
+                    map.into_iter().map(|(k, v)| (k, sort(v))).collect(),
+                ),
+                value => value,
+            }
+        }
+        let trace = serde_json::to_value(trace).unwrap();
+        sort(trace["state_diff"].clone())
+    }
+
+    for (ret, expected) in ret.iter().zip(expected.iter()) {
+        let hash = |trace: &BlockTransactionTrace| {
+            trace.transaction_hash.as_ref().map(|hash| hash.as_ref().to_owned())
+        };
+        assert_eq!(hash(ret), hash(expected));
+        let (Some(ret), Some(expected)) =
+            (&ret.trace_root, &expected.trace_root)
+        else {
+            panic!("missing trace");
+        };
+        assert_eq!(normalized(ret), normalized(expected));
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn erc20_call() -> Result<(), Error> {
     let ctx = setup!();
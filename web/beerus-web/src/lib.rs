@@ -130,7 +130,13 @@ impl Beerus {
             .map_err(|e| JsValue::from_str(&format!("beerus: invalid config JSON: {e:?}")))?;
         let config = beerus::config::Config {
             gateway_url: Some(config.gateway_url),
+            gateway_pubkey: None,
+            ethereum_rpc: None,
+            core_contract: None,
+            checkpoint: None,
             starknet_rpc: config.starknet_url,
+            starknet_rpc_providers: vec![],
+            quorum: Default::default(),
         };
         let beerus = beerus::client::Client::new(&config, Http(Rc::new(f)))
             .await
@@ -142,7 +148,8 @@ impl Beerus {
     #[wasm_bindgen]
     pub async fn get_state(&mut self) -> Result<JsValue, JsValue> {
         let state = self.beerus.get_state().await
-            .map_err(|e| JsValue::from_str(&format!("failed to get state: {e:?}")))?;
+            .map_err(|e| JsValue::from_str(&format!("failed to get state: {e:?}")))?
+            .latest;
 
         let ret = serde_json::to_string(&dto::State {
             len: state.block_number as i64,